@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stegocrypt::aac::ADTS;
+
+// Parsing arbitrary bytes must return an error rather than panic, and whatever parses must also serialize.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(adts) = ADTS::parse(data) {
+        let _ = adts.to_bytes();
+    }
+});
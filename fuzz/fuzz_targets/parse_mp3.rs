@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stegocrypt::mp3::MP3;
+
+// Parsing arbitrary bytes must return an error rather than panic, and whatever parses must also serialize.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(mp3) = MP3::parse(data) {
+        for frame in &mp3.frames {
+            let _ = frame.side_info();
+        }
+        let _ = mp3.to_bytes();
+    }
+});
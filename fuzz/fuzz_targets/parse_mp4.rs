@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stegocrypt::mp4::MP4;
+
+// Parsing arbitrary bytes must return an error rather than panic, and whatever parses must also serialize.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(mp4) = MP4::parse(data) {
+        let _ = mp4.to_bytes();
+    }
+});
@@ -0,0 +1,26 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stegocrypt::carrier::{sniff, Registry};
+use stegocrypt::stego::EmbedOptions;
+
+// Whatever format the data is taken for, parsing it with every method, reading its bits and estimating its capacity must not panic.
+fuzz_target!(|data: &[u8]| {
+    let _ = sniff(data);
+    let registry = Registry::default();
+    let Ok(carrier) = registry.detect(data) else {
+        return;
+    };
+    for method in carrier.capacity_methods() {
+        let _ = carrier.capacity(data, method, EmbedOptions::default());
+    }
+    for method in carrier.methods() {
+        if let Ok(cover) = carrier.parse(data, method) {
+            for position in 0..cover.positions().min(64) {
+                let _ = cover.read_bit(position);
+                let _ = cover.cost(position);
+            }
+            let _ = cover.to_bytes();
+        }
+    }
+});
@@ -0,0 +1,165 @@
+use crate::mp3::ID3v2;
+use crate::Error;
+
+pub use self::aaccarrier::{AACCarrier, AACCover};
+pub use self::adtsframe::ADTSFrame;
+pub use self::adtsheader::ADTSHeader;
+pub use self::adtsparseerror::ADTSParseError;
+pub use self::headerflagcarrier::HeaderFlagCarrier;
+pub use self::privatebitcarrier::PrivateBitCarrier;
+
+mod aaccarrier;
+mod adtsframe;
+mod adtsheader;
+mod adtsparseerror;
+mod headerflagcarrier;
+mod privatebitcarrier;
+
+/// A parsed ADTS stream of AAC audio: frames with their raw data blocks kept byte for byte.
+pub struct ADTS {
+    /// Bytes before the first frame, such as an ID3v2 tag, written back unchanged.
+    pub leading: Vec<u8>,
+    pub frames: Vec<ADTSFrame>,
+    /// Bytes after the last frame, such as an ID3v1 tag, written back unchanged.
+    pub trailing: Vec<u8>,
+}
+
+impl ADTS {
+    /// Parses an ADTS stream, optionally preceded by an ID3v2 tag, up to the first bytes that do not start a frame.
+    pub fn parse(data: &[u8]) -> Result<ADTS, Error> {
+        let start = match ID3v2::has_id3v2_tag(data) {
+            true => ID3v2::tag_size(data).unwrap_or(data.len()).min(data.len()),
+            false => 0,
+        };
+        let mut frames = Vec::new();
+        let mut offset = start;
+        while offset == start || ADTSHeader::has_sync(&data[offset..]) {
+            let frame = ADTSFrame::parse(&data[offset..]).map_err(|error| Error::ADTS {
+                error,
+                offset,
+                frame: Some(frames.len()),
+            })?;
+            offset += frame.data.len();
+            frames.push(frame);
+        }
+        Ok(ADTS {
+            leading: data[..start].to_vec(),
+            frames,
+            trailing: data[offset..].to_vec(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.leading.clone();
+        for frame in &self.frames {
+            bytes.extend(&frame.data);
+        }
+        bytes.extend(&self.trailing);
+        bytes
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// Builds a stream of `count` MPEG-4 AAC LC frames, 44.1 kHz mono, each holding one raw data block of varying length that starts with a single channel element.
+    /// With `crc`, every frame has an error check with a valid checksum.
+    pub(crate) fn aac_bytes(count: usize, crc: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for number in 0..count {
+            let mut block: Vec<u8> = (0..10 + number * 13 % 200)
+                .map(|byte| (byte * 7 + number) as u8)
+                .collect();
+            block[0] &= 0x1F;
+            let length = 7 + 2 * crc as usize + block.len();
+            let mut frame = vec![
+                0xFF,
+                0xF0 | !crc as u8,
+                1 << 6 | 4 << 2,
+                1 << 6 | (length >> 11) as u8,
+                (length >> 3) as u8,
+                (length << 5) as u8 | 0x1F,
+                0xFC,
+            ];
+            if crc {
+                frame.extend([0, 0]);
+            }
+            frame.extend(block);
+            if crc {
+                let mut protected = frame[..7].to_vec();
+                protected.extend(&frame[9..]);
+                protected.resize(7 + 24, 0);
+                let checksum = crc::Crc::<u16>::new(&crc::CRC_16_CMS).checksum(&protected);
+                frame[7..9].copy_from_slice(&checksum.to_be_bytes());
+            }
+            bytes.extend(frame);
+        }
+        bytes
+    }
+
+    #[test]
+    fn writes_stream_back_byte_for_byte() {
+        let mut bytes = b"ID3\x04\0\0\0\0\0\x04\0\0\0\0".to_vec();
+        bytes.extend(aac_bytes(20, false));
+        bytes.extend(b"TAG");
+        let adts = ADTS::parse(&bytes).unwrap();
+        assert_eq!(adts.frames.len(), 20);
+        assert_eq!(adts.leading.len(), 14);
+        assert_eq!(adts.trailing, b"TAG");
+        assert_eq!(adts.frames[0].header.channel_configuration, 1);
+        assert_eq!(adts.frames[0].verify_crc(), None);
+        assert_eq!(adts.to_bytes(), bytes);
+
+        bytes.truncate(bytes.len() - 5);
+        assert!(matches!(
+            ADTS::parse(&bytes),
+            Err(Error::ADTS {
+                error: ADTSParseError::TruncatedFrame,
+                frame: Some(19),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn checks_header_of_frames_with_several_blocks() {
+        let mut frame = aac_bytes(1, true);
+        // Three raw data blocks, so the error check holds two block positions before the checksum.
+        frame[6] |= 2;
+        frame.splice(7..7, [0, 40, 0, 80]);
+        let length = frame.len();
+        frame[3] = frame[3] & !0x03 | (length >> 11) as u8;
+        frame[4] = (length >> 3) as u8;
+        frame[5] = (length << 5) as u8 | 0x1F;
+        let checksum = crc::Crc::<u16>::new(&crc::CRC_16_CMS).checksum(&frame[..11]);
+        frame[11..13].copy_from_slice(&checksum.to_be_bytes());
+
+        let mut frame = ADTSFrame::parse(&frame).unwrap();
+        assert_eq!(frame.header.raw_data_blocks, 3);
+        assert_eq!(frame.header.length(), 13);
+        assert_eq!(frame.verify_crc(), Some(true));
+        frame.set_private_bit(true);
+        assert_eq!(frame.verify_crc(), Some(true));
+    }
+
+    #[test]
+    fn rejects_frame_shorter_than_its_error_check() {
+        let mut frame = aac_bytes(1, true);
+        // Three raw data blocks put the checksum at bytes 11 and 12, past the end of a 9 byte frame.
+        frame[6] |= 2;
+        frame[3] &= !0x03;
+        frame[4] = 9 >> 3;
+        frame[5] = 9 << 5 | 0x1F;
+        assert!(matches!(
+            ADTS::parse(&frame),
+            Err(Error::ADTS {
+                error: ADTSParseError::InvalidField {
+                    field: "frame length",
+                    value: 9
+                },
+                ..
+            })
+        ));
+    }
+}
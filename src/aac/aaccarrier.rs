@@ -0,0 +1,136 @@
+use crate::aac::{HeaderFlagCarrier, PrivateBitCarrier, ADTS};
+use crate::carrier::{Carrier, Cover};
+use crate::stego::BitCarrier;
+use crate::Error;
+
+/// The ADTS format of streamed AAC audio, embedding in frame private bits or in the original_copy and home flags.
+pub struct AACCarrier;
+
+/// A parsed ADTS stream with one of its carriers selected.
+/// Frames with an error check that does not verify are left out of its positions, since changing their header would leave a wrong checksum.
+pub struct AACCover {
+    pub adts: ADTS,
+    carrier: AACCoverCarrier,
+    /// Indices of the frames the carrier may change.
+    frames: Vec<usize>,
+}
+
+enum AACCoverCarrier {
+    PrivateBit(PrivateBitCarrier),
+    HeaderFlags(HeaderFlagCarrier),
+}
+
+impl AACCover {
+    fn carrier(&self) -> &dyn BitCarrier<ADTS> {
+        match &self.carrier {
+            AACCoverCarrier::PrivateBit(carrier) => carrier,
+            AACCoverCarrier::HeaderFlags(carrier) => carrier,
+        }
+    }
+
+    fn bits_per_frame(&self) -> usize {
+        match &self.carrier {
+            AACCoverCarrier::PrivateBit(_) => 1,
+            AACCoverCarrier::HeaderFlags(_) => 2,
+        }
+    }
+
+    /// Maps a position of the cover to the carrier's position in the frame it falls on.
+    fn carrier_position(&self, position: usize) -> usize {
+        let bits = self.bits_per_frame();
+        self.frames[position / bits] * bits + position % bits
+    }
+}
+
+impl Carrier for AACCarrier {
+    fn name(&self) -> &'static str {
+        "aac"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &["private-bit", "header-flags"]
+    }
+
+    fn parse(&self, data: &[u8], method: &str) -> Result<Box<dyn Cover>, Error> {
+        let carrier = match method {
+            "private-bit" => AACCoverCarrier::PrivateBit(PrivateBitCarrier),
+            "header-flags" => AACCoverCarrier::HeaderFlags(HeaderFlagCarrier),
+            _ => {
+                return Err(Error::UnsupportedMethod {
+                    format: self.name(),
+                    method: method.to_string(),
+                })
+            }
+        };
+        let adts = ADTS::parse(data)?;
+        let frames = (0..adts.frames.len())
+            .filter(|index| adts.frames[*index].verify_crc() != Some(false))
+            .collect();
+        Ok(Box::new(AACCover {
+            adts,
+            carrier,
+            frames,
+        }))
+    }
+}
+
+impl Cover for AACCover {
+    fn positions(&self) -> usize {
+        self.frames.len() * self.bits_per_frame()
+    }
+
+    fn read_bit(&self, position: usize) -> bool {
+        self.carrier()
+            .read_bit(&self.adts, self.carrier_position(position))
+    }
+
+    fn write_bit(&mut self, position: usize, bit: bool) {
+        let position = self.carrier_position(position);
+        match &self.carrier {
+            AACCoverCarrier::PrivateBit(carrier) => {
+                carrier.write_bit(&mut self.adts, position, bit)
+            }
+            AACCoverCarrier::HeaderFlags(carrier) => {
+                carrier.write_bit(&mut self.adts, position, bit)
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.adts.to_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aac::test::aac_bytes;
+    use crate::stego::{EmbedOptions, StegoKey};
+
+    #[test]
+    fn leaves_frames_with_unverified_checksum_alone() {
+        let mut bytes = aac_bytes(400, true);
+        let first = ADTS::parse(&bytes).unwrap().frames[0].data.len();
+        bytes[first + 12] ^= 1;
+        let damaged = ADTS::parse(&bytes).unwrap().frames[1].clone();
+        let key = StegoKey::derive("hunter2");
+        for (method, bits) in [("private-bit", 1), ("header-flags", 2)] {
+            let mut cover = AACCarrier.parse(&bytes, method).unwrap();
+            assert_eq!(cover.positions(), 399 * bits);
+            cover
+                .embed_raw(&key, b"secret", EmbedOptions::default())
+                .unwrap();
+
+            let stego = cover.to_bytes();
+            let adts = ADTS::parse(&stego).unwrap();
+            assert_eq!(adts.frames[1], damaged);
+            assert!(adts
+                .frames
+                .iter()
+                .enumerate()
+                .all(|(index, frame)| frame.verify_crc() == Some(index != 1)));
+            let cover = AACCarrier.parse(&stego, method).unwrap();
+            assert_eq!(cover.extract_raw(&key).unwrap().payload, b"secret");
+        }
+    }
+}
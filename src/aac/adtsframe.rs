@@ -0,0 +1,121 @@
+use crc::{Crc, CRC_16_CMS};
+
+use crate::aac::adtsheader::HEADER_LENGTH;
+use crate::aac::{ADTSHeader, ADTSParseError};
+
+/// CRC-16 with polynomial 0x8005 and initial value 0xFFFF, computed most significant bit first, as for MPEG audio.
+const ADTS_CRC: Crc<u16> = Crc::<u16>::new(&CRC_16_CMS);
+
+/// Bytes at the start of a lone raw data block that its checksum covers along with the header, padded with zeros if the block is shorter.
+const PROTECTED_BLOCK_LENGTH: usize = 24;
+
+/// An ADTS frame: its parsed header and its bytes, header included.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ADTSFrame {
+    pub header: ADTSHeader,
+    pub data: Vec<u8>,
+}
+
+impl ADTSFrame {
+    /// Parses the frame at the start of the data.
+    pub fn parse(data: &[u8]) -> Result<ADTSFrame, ADTSParseError> {
+        let header = ADTSHeader::parse(data)?;
+        let data = data
+            .get(..header.frame_length)
+            .ok_or(ADTSParseError::TruncatedFrame)?;
+        Ok(ADTSFrame {
+            header,
+            data: data.to_vec(),
+        })
+    }
+
+    /// Computes the checksum of the frame's error check.
+    /// With several raw data blocks it covers the header and the block positions; with one, the header and the first 192 bits of the block, as encoders protect a single channel element.
+    fn compute_crc(&self) -> u16 {
+        let mut digest = ADTS_CRC.digest();
+        digest.update(&self.data[..HEADER_LENGTH]);
+        let offset = self.header.checksum_offset();
+        match self.header.raw_data_blocks {
+            1 => {
+                let mut block = [0; PROTECTED_BLOCK_LENGTH];
+                let available = self.data.len().saturating_sub(offset + 2);
+                let length = available.min(PROTECTED_BLOCK_LENGTH);
+                block[..length].copy_from_slice(&self.data[offset + 2..offset + 2 + length]);
+                digest.update(&block);
+            }
+            _ => digest.update(&self.data[HEADER_LENGTH..offset]),
+        }
+        digest.finalize()
+    }
+
+    /// Checks the frame against its checksum. Returns `None` if the frame has no error check.
+    /// Frames whose checksum covers more of the raw data than [`ADTSFrame::compute_crc`] knows of, such as channel pairs, fail the check, so carriers leave them alone.
+    pub fn verify_crc(&self) -> Option<bool> {
+        self.header
+            .checksum
+            .map(|checksum| self.compute_crc() == checksum)
+    }
+
+    /// Writes the changed header fields to the frame bytes, recomputing the checksum if it was valid before.
+    fn update_header(&mut self, header: ADTSHeader) {
+        let crc_valid = self.verify_crc() == Some(true);
+        self.header = header;
+        self.header.write(&mut self.data);
+        if crc_valid {
+            let checksum = self.compute_crc();
+            let offset = self.header.checksum_offset();
+            self.header.checksum = Some(checksum);
+            self.data[offset..offset + 2].copy_from_slice(&checksum.to_be_bytes());
+        }
+    }
+
+    /// Sets the private bit of this frame's header, keeping the frame bytes and checksum in sync.
+    pub fn set_private_bit(&mut self, private_bit: bool) {
+        self.update_header(ADTSHeader {
+            private_bit,
+            ..self.header
+        });
+    }
+
+    /// Sets the original_copy flag of this frame's header, keeping the frame bytes and checksum in sync.
+    pub fn set_original(&mut self, original: bool) {
+        self.update_header(ADTSHeader {
+            original,
+            ..self.header
+        });
+    }
+
+    /// Sets the home flag of this frame's header, keeping the frame bytes and checksum in sync.
+    pub fn set_home(&mut self, home: bool) {
+        self.update_header(ADTSHeader {
+            home,
+            ..self.header
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aac::test::aac_bytes;
+
+    #[test]
+    fn keeps_valid_checksum_in_sync() {
+        let bytes = aac_bytes(2, true);
+        let mut frame = ADTSFrame::parse(&bytes).unwrap();
+        assert_eq!(frame.header.length(), 9);
+        assert_eq!(frame.header.sampling_frequency(), 44100);
+        assert_eq!(frame.verify_crc(), Some(true));
+
+        frame.set_private_bit(true);
+        frame.set_home(true);
+        assert_eq!(frame.data[2] & 0x02, 0x02);
+        assert_eq!(frame.data[3] & 0x10, 0x10);
+        assert_eq!(frame.verify_crc(), Some(true));
+        assert_eq!(ADTSFrame::parse(&frame.data).unwrap(), frame);
+
+        frame.data[12] ^= 1;
+        frame.set_original(true);
+        assert_eq!(frame.verify_crc(), Some(false));
+    }
+}
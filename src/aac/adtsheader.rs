@@ -0,0 +1,110 @@
+use crate::aac::ADTSParseError;
+
+/// Length of an ADTS header without its error check.
+pub const HEADER_LENGTH: usize = 7;
+
+/// Sampling frequencies indexed by the header's sampling_frequency_index. Indices 13 to 15 are reserved or escape to an explicit rate, which ADTS cannot carry.
+const SAMPLING_FREQUENCIES: [u32; 13] = [
+    96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+];
+
+/// The header of an ADTS frame: the fixed header, the variable header and the checksum of the error check, if the frame has one.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ADTSHeader {
+    /// Whether the stream is MPEG-2 AAC rather than MPEG-4 AAC.
+    pub mpeg2: bool,
+    /// The audio object type minus one: 1 for AAC LC.
+    pub profile: u8,
+    pub sampling_frequency_index: u8,
+    pub private_bit: bool,
+    pub channel_configuration: u8,
+    /// The original_copy flag.
+    pub original: bool,
+    pub home: bool,
+    /// Length of the whole frame, header included.
+    pub frame_length: usize,
+    pub buffer_fullness: u16,
+    /// Number of raw data blocks in the frame.
+    pub raw_data_blocks: usize,
+    /// The checksum that follows the header, or `None` if protection_absent is set.
+    pub checksum: Option<u16>,
+}
+
+impl ADTSHeader {
+    /// Returns whether the data starts with the ADTS sync word and a layer of zero.
+    pub fn has_sync(data: &[u8]) -> bool {
+        data.len() >= 2 && data[0] == 0xFF && data[1] & 0xF6 == 0xF0
+    }
+
+    /// Parses the header at the start of a frame.
+    pub fn parse(data: &[u8]) -> Result<ADTSHeader, ADTSParseError> {
+        if !ADTSHeader::has_sync(data) {
+            return Err(ADTSParseError::NoFrameSync);
+        }
+        let bytes = data
+            .get(..HEADER_LENGTH)
+            .ok_or(ADTSParseError::TruncatedFrame)?;
+        let sampling_frequency_index = bytes[2] >> 2 & 0x0F;
+        if sampling_frequency_index as usize >= SAMPLING_FREQUENCIES.len() {
+            return Err(ADTSParseError::InvalidField {
+                field: "sampling frequency index",
+                value: sampling_frequency_index as u32,
+            });
+        }
+        let raw_data_blocks = (bytes[6] & 0x03) as usize + 1;
+        let protected = bytes[1] & 0x01 == 0;
+        let mut header = ADTSHeader {
+            mpeg2: bytes[1] & 0x08 != 0,
+            profile: bytes[2] >> 6,
+            sampling_frequency_index,
+            private_bit: bytes[2] & 0x02 != 0,
+            channel_configuration: (bytes[2] & 0x01) << 2 | bytes[3] >> 6,
+            original: bytes[3] & 0x20 != 0,
+            home: bytes[3] & 0x10 != 0,
+            frame_length: ((bytes[3] & 0x03) as usize) << 11
+                | (bytes[4] as usize) << 3
+                | (bytes[5] >> 5) as usize,
+            buffer_fullness: ((bytes[5] & 0x1F) as u16) << 6 | (bytes[6] >> 2) as u16,
+            raw_data_blocks,
+            checksum: None,
+        };
+        if protected {
+            let offset = header.checksum_offset();
+            let checksum = data
+                .get(offset..offset + 2)
+                .ok_or(ADTSParseError::TruncatedFrame)?;
+            header.checksum = Some(u16::from_be_bytes([checksum[0], checksum[1]]));
+        }
+        // The error check must lie within the frame, so that the checksum can be rewritten in place.
+        if header.frame_length < header.length() {
+            return Err(ADTSParseError::InvalidField {
+                field: "frame length",
+                value: header.frame_length as u32,
+            });
+        }
+        Ok(header)
+    }
+
+    /// Returns the length of the header with its error check: the raw data block positions of a frame with several blocks, and the checksum.
+    pub fn length(&self) -> usize {
+        match self.checksum {
+            Some(_) => self.checksum_offset() + 2,
+            None => HEADER_LENGTH,
+        }
+    }
+
+    /// Offset of the checksum in the frame, after the raw data block positions if there are several blocks.
+    pub(crate) fn checksum_offset(&self) -> usize {
+        HEADER_LENGTH + 2 * (self.raw_data_blocks - 1)
+    }
+
+    pub fn sampling_frequency(&self) -> u32 {
+        SAMPLING_FREQUENCIES[self.sampling_frequency_index as usize]
+    }
+
+    /// Writes the header fields that carriers change back to the first header bytes of a frame.
+    pub fn write(&self, frame: &mut [u8]) {
+        frame[2] = frame[2] & !0x02 | (self.private_bit as u8) << 1;
+        frame[3] = frame[3] & !0x30 | (self.original as u8) << 5 | (self.home as u8) << 4;
+    }
+}
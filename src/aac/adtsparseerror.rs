@@ -0,0 +1,29 @@
+/// Error type for ADTS parsing.
+#[derive(Debug, PartialEq)]
+pub enum ADTSParseError {
+    NoFrameSync,
+    InvalidField { field: &'static str, value: u32 },
+    TruncatedFrame,
+}
+
+impl ADTSParseError {
+    pub fn description(&self) -> String {
+        match self {
+            ADTSParseError::NoFrameSync => String::from("ADTS frame sync expected but not found."),
+            ADTSParseError::InvalidField { field, value } => {
+                format!("Invalid {} in ADTS frame header: {}.", field, value)
+            }
+            ADTSParseError::TruncatedFrame => {
+                String::from("Frame extends past the end of the data.")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ADTSParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(&self.description())
+    }
+}
+
+impl std::error::Error for ADTSParseError {}
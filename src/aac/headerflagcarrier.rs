@@ -0,0 +1,29 @@
+use crate::aac::ADTS;
+use crate::stego::BitCarrier;
+
+/// Carries two payload bits per frame in the original_copy and home flags of the ADTS header.
+/// Both flags are informational and ignored by decoders, but like the private bit they sit in the fixed header, which must stay the same for the whole stream.
+/// Flags that vary between frames are easy to spot, and demuxers that resynchronize by matching the fixed header may reject the frames that differ.
+pub struct HeaderFlagCarrier;
+
+impl BitCarrier<ADTS> for HeaderFlagCarrier {
+    fn positions(&self, cover: &ADTS) -> usize {
+        2 * cover.frames.len()
+    }
+
+    fn read_bit(&self, cover: &ADTS, position: usize) -> bool {
+        let header = &cover.frames[position / 2].header;
+        match position % 2 {
+            0 => header.original,
+            _ => header.home,
+        }
+    }
+
+    fn write_bit(&self, cover: &mut ADTS, position: usize, bit: bool) {
+        let frame = &mut cover.frames[position / 2];
+        match position % 2 {
+            0 => frame.set_original(bit),
+            _ => frame.set_home(bit),
+        }
+    }
+}
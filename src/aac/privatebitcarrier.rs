@@ -0,0 +1,21 @@
+use crate::aac::ADTS;
+use crate::stego::BitCarrier;
+
+/// Carries one payload bit per frame in the private bit of the ADTS header.
+/// Decoders ignore the bit, but it sits in the fixed header, which is meant to be the same in every frame of a stream.
+/// A private bit that changes from frame to frame is an obvious anomaly, and demuxers that resynchronize by matching the fixed header may reject the frames that differ.
+pub struct PrivateBitCarrier;
+
+impl BitCarrier<ADTS> for PrivateBitCarrier {
+    fn positions(&self, cover: &ADTS) -> usize {
+        cover.frames.len()
+    }
+
+    fn read_bit(&self, cover: &ADTS, position: usize) -> bool {
+        cover.frames[position].header.private_bit
+    }
+
+    fn write_bit(&self, cover: &mut ADTS, position: usize, bit: bool) {
+        cover.frames[position].set_private_bit(bit);
+    }
+}
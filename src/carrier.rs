@@ -0,0 +1,206 @@
+use crate::stego::{self, BitCarrier, Capacity, EmbedOptions, Extraction, StegoError, StegoKey};
+use crate::Error;
+
+#[cfg(feature = "crypto")]
+pub use self::embeddingscheme::EmbeddingScheme;
+pub use self::magic::sniff;
+pub use self::registry::Registry;
+
+#[cfg(feature = "crypto")]
+mod embeddingscheme;
+mod magic;
+mod registry;
+
+/// A file format that payloads can be hidden in, offering one or more named methods of carrying bits.
+/// Formats are looked up through a [`Registry`], so that every format goes through the same embed and extract path.
+pub trait Carrier {
+    /// Short lowercase name of the format, such as "mp3".
+    fn name(&self) -> &'static str;
+
+    /// Names of the methods that can embed in this format. The first is the default.
+    fn methods(&self) -> &'static [&'static str];
+
+    /// Names of every method whose capacity can be estimated, which may include methods that cannot embed yet.
+    fn capacity_methods(&self) -> &'static [&'static str] {
+        self.methods()
+    }
+
+    /// Returns whether the data looks like a file of this format.
+    /// By default this checks that [`sniff`] identifies the data by the format's name.
+    fn detect(&self, data: &[u8]) -> bool {
+        sniff(data) == Some(self.name())
+    }
+
+    /// Parses a file and selects one of its methods, returning a cover whose bits can be read and written.
+    fn parse(&self, data: &[u8], method: &str) -> Result<Box<dyn Cover>, Error>;
+
+    /// Estimates how much the method can carry in the file. Returns `None` if its positions cannot be counted.
+    fn capacity(
+        &self,
+        data: &[u8],
+        method: &str,
+        options: EmbedOptions,
+    ) -> Result<Option<Capacity>, Error> {
+        let cover = self.parse(data, method)?;
+        Ok(Some(Capacity::estimate(cover.positions(), options)))
+    }
+}
+
+/// A parsed file with an embedding method chosen, exposing the bit positions of that method.
+pub trait Cover {
+    /// Returns the number of bit positions available.
+    fn positions(&self) -> usize;
+
+    /// Reads the bit currently stored at the given position.
+    fn read_bit(&self, position: usize) -> bool;
+
+    /// Stores a bit at the given position.
+    fn write_bit(&mut self, position: usize, bit: bool);
+
+    /// Returns the distortion caused by changing the bit at the given position, as [`BitCarrier::cost`] does.
+    fn cost(&self, _position: usize) -> f64 {
+        1.0
+    }
+
+    /// Serializes the file with any changes made to it.
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// Writes already sealed data into the cover with [`stego::embed_raw`].
+    /// Formats whose positions come and go as bits are written, such as JPEG under F5, embed with a scheme of their own instead.
+    fn embed_raw(
+        &mut self,
+        key: &StegoKey,
+        data: &[u8],
+        options: EmbedOptions,
+    ) -> Result<(), StegoError> {
+        stego::embed_raw(self, &CoverCarrier, key, data, options)
+    }
+
+    /// Reads back data written with [`Cover::embed_raw`].
+    fn extract_raw(&self, key: &StegoKey) -> Result<Extraction, StegoError> {
+        stego::extract_raw(self, &CoverCarrier, key)
+    }
+}
+
+/// Embeds sealed data into a cover that can make room for it, such as a padding block or a box that grows to fit.
+/// The data is first embedded into the positions the cover already has. If it does not fit, `grow` is given the number of positions it is short by,
+/// rounded up to whole bytes, and the data is embedded again into the grown cover.
+pub fn embed_growing<C: Cover + ?Sized>(
+    cover: &mut C,
+    key: &StegoKey,
+    data: &[u8],
+    options: EmbedOptions,
+    grow: impl FnOnce(&mut C, usize) -> Result<(), StegoError>,
+) -> Result<(), StegoError> {
+    let (required, available) = match stego::embed_raw(cover, &CoverCarrier, key, data, options) {
+        Err(StegoError::PayloadTooLarge {
+            required,
+            available,
+        }) => (required, available),
+        result => return result,
+    };
+    grow(cover, (required - available).div_ceil(8))?;
+    stego::embed_raw(cover, &CoverCarrier, key, data, options)
+}
+
+/// Lets the generic embedding functions in [`crate::stego`] work on any [`Cover`].
+pub struct CoverCarrier;
+
+impl<T: Cover + ?Sized> BitCarrier<T> for CoverCarrier {
+    fn positions(&self, cover: &T) -> usize {
+        cover.positions()
+    }
+
+    fn read_bit(&self, cover: &T, position: usize) -> bool {
+        cover.read_bit(position)
+    }
+
+    fn write_bit(&self, cover: &mut T, position: usize, bit: bool) {
+        cover.write_bit(position, bit)
+    }
+
+    fn cost(&self, cover: &T, position: usize) -> f64 {
+        cover.cost(position)
+    }
+}
+
+#[cfg(all(test, feature = "crypto"))]
+mod test {
+    use super::*;
+    #[cfg(feature = "aac")]
+    use crate::aac::test::aac_bytes;
+    #[cfg(feature = "flac")]
+    use crate::flac::test::flac_bytes;
+    #[cfg(feature = "jpeg")]
+    use crate::jpeg::test::jpeg_bytes;
+    use crate::mp3::test::mp3_bytes;
+    #[cfg(feature = "mp4")]
+    use crate::mp4::test::mp4_bytes;
+    #[cfg(feature = "ogg")]
+    use crate::ogg::test::ogg_bytes;
+    #[cfg(feature = "png")]
+    use crate::png::test::rgba_png_bytes;
+    #[cfg(feature = "wav")]
+    use crate::wav::test::wav_bytes;
+
+    /// A cover file of every format built into the crate, with the name it is detected as and the methods that grow the file to fit a payload.
+    fn covers() -> Vec<(&'static str, Vec<u8>, &'static [&'static str])> {
+        vec![
+            ("mp3", mp3_bytes(500), &[]),
+            #[cfg(feature = "wav")]
+            ("wav", wav_bytes(2000), &[]),
+            #[cfg(feature = "png")]
+            ("png", rgba_png_bytes(40, 30), &[]),
+            #[cfg(feature = "jpeg")]
+            ("jpeg", jpeg_bytes(false), &[]),
+            #[cfg(feature = "flac")]
+            ("flac", flac_bytes(3000), &["application"]),
+            #[cfg(feature = "ogg")]
+            ("ogg", ogg_bytes(1200), &["comment"]),
+            #[cfg(feature = "aac")]
+            ("aac", aac_bytes(1200, true), &[]),
+            #[cfg(feature = "mp4")]
+            (
+                "mp4",
+                mp4_bytes(200, true, false),
+                &["free", "udta", "mdat"],
+            ),
+        ]
+    }
+
+    #[test]
+    fn round_trips_payload_through_every_method_of_every_format() {
+        let registry = Registry::default();
+        let scheme = EmbeddingScheme::new(StegoKey::derive("hunter2"), EmbedOptions::default());
+        for (name, bytes, _) in covers() {
+            let carrier = registry.detect(&bytes).unwrap();
+            assert_eq!(carrier.name(), name);
+            for method in carrier.methods() {
+                let stego = scheme
+                    .embed_file(carrier, method, &bytes, b"secret")
+                    .unwrap();
+                let extraction = scheme.extract_file(carrier, method, &stego).unwrap();
+                assert_eq!(extraction.payload, b"secret", "{} {}", name, method);
+            }
+        }
+    }
+
+    #[test]
+    fn grows_cover_past_its_existing_space() {
+        let registry = Registry::default();
+        let scheme = EmbeddingScheme::new(StegoKey::derive("hunter2"), EmbedOptions::default());
+        for (name, bytes, growing) in covers() {
+            let carrier = registry.detect(&bytes).unwrap();
+            for method in growing {
+                let positions = carrier.parse(&bytes, method).unwrap().positions();
+                let payload = vec![0x5A; positions / 8 + 1];
+                let stego = scheme
+                    .embed_file(carrier, method, &bytes, &payload)
+                    .unwrap();
+                assert!(stego.len() > bytes.len(), "{} {}", name, method);
+                let extraction = scheme.extract_file(carrier, method, &stego).unwrap();
+                assert_eq!(extraction.payload, payload, "{} {}", name, method);
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use crate::carrier::{Carrier, Cover};
+use crate::stego::{Container, EmbedOptions, Extraction, StegoKey};
+use crate::Error;
+
+/// The format-independent half of embedding: sealing the payload in an encrypted container, placing it with the keyed permutation and coding it onto a cover's bits.
+pub struct EmbeddingScheme {
+    key: StegoKey,
+    options: EmbedOptions,
+}
+
+impl EmbeddingScheme {
+    pub fn new(key: StegoKey, options: EmbedOptions) -> EmbeddingScheme {
+        EmbeddingScheme { key, options }
+    }
+
+    pub fn embed(&self, cover: &mut dyn Cover, payload: &[u8]) -> Result<(), Error> {
+        let sealed = Container::new(payload, self.options.compression).seal(&self.key);
+        Ok(cover.embed_raw(&self.key, &sealed, self.options)?)
+    }
+
+    pub fn extract(&self, cover: &dyn Cover) -> Result<Extraction, Error> {
+        let mut extraction = cover.extract_raw(&self.key)?;
+        extraction.payload = Container::open(&self.key, &extraction.payload)?;
+        Ok(extraction)
+    }
+
+    /// Parses a file with the given format and method, embeds the payload and returns the changed file.
+    pub fn embed_file(
+        &self,
+        carrier: &dyn Carrier,
+        method: &str,
+        data: &[u8],
+        payload: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let mut cover = carrier.parse(data, method)?;
+        self.embed(cover.as_mut(), payload)?;
+        Ok(cover.to_bytes())
+    }
+
+    /// Parses a file with the given format and method and extracts its payload.
+    pub fn extract_file(
+        &self,
+        carrier: &dyn Carrier,
+        method: &str,
+        data: &[u8],
+    ) -> Result<Extraction, Error> {
+        self.extract(carrier.parse(data, method)?.as_ref())
+    }
+}
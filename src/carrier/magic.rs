@@ -0,0 +1,77 @@
+use crate::mp3::{ID3v2, MPEGFrameHeader, MPEGLayer};
+
+/// Signatures found at the very start of a file, with the name of the format they identify.
+/// Formats that no carrier supports are listed too, so that users get told what their file is.
+const SIGNATURES: [(&[u8], &str); 7] = [
+    (b"\x89PNG\r\n\x1a\n", "png"),
+    (b"\xFF\xD8\xFF", "jpeg"),
+    (b"OggS", "ogg"),
+    (b"GIF8", "gif"),
+    (b"%PDF", "pdf"),
+    (b"PK\x03\x04", "zip"),
+    (b"BM", "bmp"),
+];
+
+/// Identifies the format of a file from its content rather than its name, returning the format's short name.
+/// Audio streams may start with an ID3v2 tag, which is skipped before looking at the stream itself.
+pub fn sniff(data: &[u8]) -> Option<&'static str> {
+    if let Some((_, name)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| data.starts_with(signature))
+    {
+        return Some(name);
+    }
+    if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WAVE") {
+        return Some("wav");
+    }
+    if data.get(4..8) == Some(b"ftyp") {
+        return Some("mp4");
+    }
+
+    let tag_size = match ID3v2::has_id3v2_tag(data) {
+        true => ID3v2::tag_size(data)?,
+        false => 0,
+    };
+    let stream = match data.get(tag_size..) {
+        // A tag that runs past the data read so far is taken to precede MPEG audio, by far the most common case.
+        None | Some([]) if tag_size > 0 => return Some("mp3"),
+        None => return None,
+        Some(stream) => stream,
+    };
+    if stream.starts_with(b"fLaC") {
+        return Some("flac");
+    }
+    // ADTS shares the 12-bit sync of MPEG audio but always has a layer of zero.
+    if stream.len() >= 2 && stream[0] == 0xFF && stream[1] & 0xF6 == 0xF0 {
+        return Some("aac");
+    }
+    match MPEGFrameHeader::parse(stream) {
+        Ok(header) if header.layer != MPEGLayer::Reserved => Some("mp3"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mp3::test::mp3_bytes;
+
+    #[test]
+    fn sniffs_formats_from_magic_bytes() {
+        assert_eq!(sniff(&mp3_bytes(2)), Some("mp3"));
+        assert_eq!(sniff(b"RIFF\x24\0\0\0WAVEfmt "), Some("wav"));
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\x0DIHDR"), Some("png"));
+        assert_eq!(sniff(b"\0\0\0\x20ftypM4A "), Some("mp4"));
+        assert_eq!(sniff(b"\xFF\xF1\x50\x80\x02\x1F\xFC"), Some("aac"));
+        assert_eq!(sniff(b"RIFF\x24\0\0\0AVI "), None);
+        assert_eq!(sniff(b"plain text"), None);
+    }
+
+    #[test]
+    fn looks_past_id3v2_tag() {
+        let mut data = b"ID3\x04\0\0\0\0\0\x04\0\0\0\0".to_vec();
+        data.extend(b"fLaC\0\0\0\x22");
+        assert_eq!(sniff(&data), Some("flac"));
+        assert_eq!(sniff(&data[..12]), Some("mp3"));
+    }
+}
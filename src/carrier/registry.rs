@@ -0,0 +1,106 @@
+#[cfg(feature = "aac")]
+use crate::aac::AACCarrier;
+use crate::carrier::{sniff, Carrier};
+#[cfg(feature = "flac")]
+use crate::flac::FLACCarrier;
+#[cfg(feature = "jpeg")]
+use crate::jpeg::JPEGCarrier;
+use crate::mp3::MP3Carrier;
+#[cfg(feature = "mp4")]
+use crate::mp4::MP4Carrier;
+#[cfg(feature = "ogg")]
+use crate::ogg::OggCarrier;
+#[cfg(feature = "png")]
+use crate::png::PNGCarrier;
+#[cfg(feature = "wav")]
+use crate::wav::WAVCarrier;
+use crate::Error;
+
+/// The set of formats available for embedding, looked up by name or by the content of a file.
+pub struct Registry {
+    carriers: Vec<Box<dyn Carrier>>,
+}
+
+impl Registry {
+    /// Creates a registry with no formats.
+    pub fn empty() -> Registry {
+        Registry {
+            carriers: Vec::new(),
+        }
+    }
+
+    /// Adds a format. Formats registered earlier are tried first by [`Registry::detect`].
+    pub fn register(&mut self, carrier: Box<dyn Carrier>) {
+        self.carriers.push(carrier);
+    }
+
+    /// Looks up a format by name.
+    pub fn get(&self, name: &str) -> Option<&dyn Carrier> {
+        self.carriers().find(|carrier| carrier.name() == name)
+    }
+
+    /// Returns the first format that recognizes the data.
+    /// Fails with [`Error::UnsupportedFormat`], naming the format if it could still be identified, when none does.
+    pub fn detect(&self, data: &[u8]) -> Result<&dyn Carrier, Error> {
+        self.carriers()
+            .find(|carrier| carrier.detect(data))
+            .ok_or(Error::UnsupportedFormat {
+                detected: sniff(data),
+            })
+    }
+
+    pub fn carriers(&self) -> impl Iterator<Item = &dyn Carrier> {
+        self.carriers.iter().map(|carrier| carrier.as_ref())
+    }
+}
+
+impl Default for Registry {
+    /// Creates a registry with every format built into the crate.
+    fn default() -> Registry {
+        let mut registry = Registry::empty();
+        registry.register(Box::new(MP3Carrier));
+        #[cfg(feature = "wav")]
+        registry.register(Box::new(WAVCarrier));
+        #[cfg(feature = "png")]
+        registry.register(Box::new(PNGCarrier));
+        #[cfg(feature = "jpeg")]
+        registry.register(Box::new(JPEGCarrier));
+        #[cfg(feature = "flac")]
+        registry.register(Box::new(FLACCarrier));
+        #[cfg(feature = "ogg")]
+        registry.register(Box::new(OggCarrier));
+        #[cfg(feature = "aac")]
+        registry.register(Box::new(AACCarrier));
+        #[cfg(feature = "mp4")]
+        registry.register(Box::new(MP4Carrier));
+        registry
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(feature = "jpeg")]
+    use crate::jpeg::test::jpeg_bytes;
+    use crate::mp3::test::mp3_bytes;
+
+    #[test]
+    fn finds_built_in_formats() {
+        let registry = Registry::default();
+        assert_eq!(registry.get("mp3").unwrap().name(), "mp3");
+        assert!(registry.get("tiff").is_none());
+        assert_eq!(registry.detect(&mp3_bytes(3)).unwrap().name(), "mp3");
+        #[cfg(feature = "jpeg")]
+        assert_eq!(registry.detect(&jpeg_bytes(false)).unwrap().name(), "jpeg");
+        assert!(matches!(
+            registry.detect(b"GIF89a"),
+            Err(Error::UnsupportedFormat {
+                detected: Some("gif")
+            })
+        ));
+        assert!(matches!(
+            registry.detect(b"plain text"),
+            Err(Error::UnsupportedFormat { detected: None })
+        ));
+    }
+}
@@ -0,0 +1,356 @@
+use std::io::{Read, Write};
+
+use clap::{command, value_parser, Arg, ArgMatches, Command};
+
+use stegocrypt::carrier::{sniff, Carrier, EmbeddingScheme, Registry};
+use stegocrypt::mp3::{MP3Report, MP3};
+use stegocrypt::stego::{
+    Capacity, Coding, Compression, EmbedOptions, Layout, StegoError, StegoKey,
+};
+use stegocrypt::Error;
+
+pub use self::clierror::CliError;
+
+mod clierror;
+
+/// Bytes read from the start of a file to identify its format, enough to look past a typical ID3v2 tag.
+const HEAD_LENGTH: u64 = 64 * 1024;
+
+pub fn command() -> Command {
+    let method = Arg::new("method")
+        .long("method")
+        .short('m')
+        .help("Carrier used to hold the payload, by default the first one the format offers");
+    let coding = Arg::new("coding")
+        .long("coding")
+        .value_parser(["plain", "hamming", "trellis"])
+        .default_value("plain");
+    let layout = Arg::new("layout")
+        .long("layout")
+        .value_parser(["scattered", "packets"])
+        .default_value("scattered");
+    let fec = Arg::new("fec")
+        .long("fec")
+        .value_parser(value_parser!(u8))
+        .default_value("0")
+        .help("Error correction overhead as a percentage of the payload");
+    let password = Arg::new("password")
+        .long("password")
+        .short('p')
+        .required(true)
+        .help("Password the placement and encryption keys are derived from");
+
+    command!()
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("embed")
+                .about("Hide a payload in a cover file")
+                .arg(Arg::new("cover").required(true).index(1))
+                .arg(Arg::new("payload").required(true).index(2))
+                .arg(Arg::new("output").long("output").short('o').required(true))
+                .arg(method.clone())
+                .arg(password.clone())
+                .arg(coding.clone())
+                .arg(layout.clone())
+                .arg(
+                    Arg::new("compression")
+                        .long("compression")
+                        .value_parser(["none", "deflate", "zstd"])
+                        .default_value("none"),
+                )
+                .arg(fec.clone()),
+        )
+        .subcommand(
+            Command::new("extract")
+                .about("Recover a payload from a file")
+                .arg(Arg::new("input").required(true).index(1))
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .help("Write the payload here instead of to standard output"),
+                )
+                .arg(method.clone())
+                .arg(password.clone()),
+        )
+        .subcommand(
+            Command::new("info")
+                .about("Describe the structure of a file")
+                .arg(Arg::new("input").required(true).index(1))
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .short('f')
+                        .value_parser(["table", "json"])
+                        .default_value("table"),
+                ),
+        )
+        .subcommand(
+            Command::new("capacity")
+                .about("Show how much each method can carry in a file")
+                .arg(Arg::new("input").required(true).index(1))
+                .arg(coding)
+                .arg(layout)
+                .arg(fec),
+        )
+        .subcommand(
+            Command::new("verify")
+                .about("Check whether a file holds a readable payload for a password")
+                .arg(Arg::new("input").required(true).index(1))
+                .arg(password),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), CliError> {
+    match matches.subcommand() {
+        Some(("embed", matches)) => embed(matches),
+        Some(("extract", matches)) => extract(matches),
+        Some(("info", matches)) => info(matches),
+        Some(("capacity", matches)) => capacity(matches),
+        Some(("verify", matches)) => verify(matches),
+        _ => unreachable!("clap requires a known subcommand"),
+    }
+}
+
+fn embed(matches: &ArgMatches) -> Result<(), CliError> {
+    let registry = Registry::default();
+    let cover = read(string(matches, "cover"))?;
+    let carrier = registry.detect(&cover)?;
+    let payload = read(string(matches, "payload"))?;
+    let key = StegoKey::derive(string(matches, "password"));
+
+    let options = EmbedOptions {
+        compression: match string(matches, "compression") {
+            "deflate" => Compression::Deflate,
+            "zstd" => Compression::Zstd,
+            _ => Compression::None,
+        },
+        ..embed_options(matches)
+    };
+
+    let scheme = EmbeddingScheme::new(key, options);
+    let stego = scheme.embed_file(carrier, method(matches, carrier), &cover, &payload)?;
+    write(string(matches, "output"), &stego)
+}
+
+/// Reads the layout, coding and error correction options shared by the embed and capacity commands.
+fn embed_options(matches: &ArgMatches) -> EmbedOptions {
+    EmbedOptions {
+        layout: match string(matches, "layout") {
+            "packets" => Layout::Packets,
+            _ => Layout::Scattered,
+        },
+        coding: match string(matches, "coding") {
+            "hamming" => Coding::Hamming,
+            "trellis" => Coding::Trellis,
+            _ => Coding::Plain,
+        },
+        compression: Compression::None,
+        fec_overhead: *matches.get_one::<u8>("fec").expect("fec has a default"),
+    }
+}
+
+fn extract(matches: &ArgMatches) -> Result<(), CliError> {
+    let registry = Registry::default();
+    let input = read(string(matches, "input"))?;
+    let carrier = registry.detect(&input)?;
+    let key = StegoKey::derive(string(matches, "password"));
+    let scheme = EmbeddingScheme::new(key, EmbedOptions::default());
+    let extraction = scheme.extract_file(carrier, method(matches, carrier), &input)?;
+
+    if extraction.corrected_errors > 0 {
+        eprintln!("Corrected {} damaged bytes.", extraction.corrected_errors);
+    }
+    match matches.get_one::<String>("output") {
+        Some(output) => write(output, &extraction.payload),
+        None => print(&extraction.payload),
+    }
+}
+
+fn info(matches: &ArgMatches) -> Result<(), CliError> {
+    let path = string(matches, "input");
+    let detected = sniff(&read_head(path)?);
+    if detected != Some("mp3") {
+        return Err(Error::UnsupportedFormat { detected }.into());
+    }
+    let mp3 = open(path)?;
+    let report = MP3Report::new(&mp3);
+    match string(matches, "format") {
+        "json" => print_line(&report.to_json()),
+        _ => print_line(&report.to_table()),
+    }
+}
+
+fn capacity(matches: &ArgMatches) -> Result<(), CliError> {
+    let registry = Registry::default();
+    let input = read(string(matches, "input"))?;
+    let carrier = registry.detect(&input)?;
+    let options = embed_options(matches);
+    for method in carrier.capacity_methods() {
+        let capacity = carrier.capacity(&input, method, options)?;
+        let embeddable = carrier.methods().contains(method);
+        print_line(&capacity_line(method, capacity, embeddable))?;
+    }
+    Ok(())
+}
+
+/// Describes the capacity of one method, marking methods that only have an estimate and no carrier to embed with.
+fn capacity_line(method: &str, capacity: Option<Capacity>, embeddable: bool) -> String {
+    let line = match capacity {
+        Some(capacity) => format!(
+            "{}: {} carrier bits, up to {} payload bytes, changing {:.1}% of carrier bits",
+            method,
+            capacity.raw_bits,
+            capacity.payload_bytes,
+            capacity.change_rate * 100.0
+        ),
+        None => format!("{}: cannot be estimated for this file", method),
+    };
+    match embeddable {
+        true => line,
+        false => format!("{} (estimate only, cannot be used with embed)", line),
+    }
+}
+
+fn verify(matches: &ArgMatches) -> Result<(), CliError> {
+    let registry = Registry::default();
+    let input = read(string(matches, "input"))?;
+    let carrier = registry.detect(&input)?;
+    let key = StegoKey::derive(string(matches, "password"));
+    let scheme = EmbeddingScheme::new(key, EmbedOptions::default());
+
+    let mut last_error = None;
+    for method in carrier.methods() {
+        match scheme.extract_file(carrier, method, &input) {
+            Ok(extraction) => {
+                return print_line(&format!(
+                    "Found a {} byte payload using {}.",
+                    extraction.payload.len(),
+                    method
+                ));
+            }
+            Err(error) => last_error = Some(error),
+        }
+    }
+    Err(last_error.map_or(CliError::Stego(StegoError::NoPayload), CliError::from))
+}
+
+/// Returns the method chosen on the command line, or the format's default.
+fn method<'a>(matches: &'a ArgMatches, carrier: &dyn Carrier) -> &'a str {
+    matches
+        .get_one::<String>("method")
+        .map_or(carrier.methods()[0], String::as_str)
+}
+
+fn string<'a>(matches: &'a ArgMatches, id: &str) -> &'a str {
+    matches
+        .get_one::<String>(id)
+        .expect("required arguments are enforced by clap")
+}
+
+/// Parses an MP3 file, reading it frame by frame rather than loading it whole.
+fn open(path: &str) -> Result<MP3, CliError> {
+    let io_error = |error| CliError::Io {
+        path: path.to_string(),
+        error,
+    };
+    let file = std::fs::File::open(path).map_err(io_error)?;
+    MP3::read(std::io::BufReader::new(file)).map_err(|error| match error {
+        Error::Io(error) => io_error(error),
+        error => CliError::from(error),
+    })
+}
+
+/// Reads enough of the start of a file to identify its format.
+fn read_head(path: &str) -> Result<Vec<u8>, CliError> {
+    let io_error = |error| CliError::Io {
+        path: path.to_string(),
+        error,
+    };
+    let file = std::fs::File::open(path).map_err(io_error)?;
+    let mut head = Vec::new();
+    file.take(HEAD_LENGTH)
+        .read_to_end(&mut head)
+        .map_err(io_error)?;
+    Ok(head)
+}
+
+fn read(path: &str) -> Result<Vec<u8>, CliError> {
+    std::fs::read(path).map_err(|error| CliError::Io {
+        path: path.to_string(),
+        error,
+    })
+}
+
+/// Writes to standard output, reporting failures such as a closed pipe as errors rather than panicking.
+fn print(data: &[u8]) -> Result<(), CliError> {
+    std::io::stdout()
+        .write_all(data)
+        .map_err(|error| CliError::Io {
+            path: String::from("standard output"),
+            error,
+        })
+}
+
+fn print_line(text: &str) -> Result<(), CliError> {
+    print(format!("{}\n", text).as_bytes())
+}
+
+fn write(path: &str, data: &[u8]) -> Result<(), CliError> {
+    std::fs::write(path, data).map_err(|error| CliError::Io {
+        path: path.to_string(),
+        error,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn builds_valid_command() {
+        command().debug_assert();
+    }
+
+    #[test]
+    fn maps_errors_to_distinct_exit_codes() {
+        let too_large = CliError::Stego(StegoError::PayloadTooLarge {
+            required: 2,
+            available: 1,
+        });
+        let errors = [
+            CliError::Io {
+                path: String::new(),
+                error: std::io::ErrorKind::NotFound.into(),
+            },
+            CliError::Parse(Error::Io(std::io::ErrorKind::InvalidData.into())),
+            too_large,
+            CliError::Stego(StegoError::NoEmbeddingPath),
+            CliError::Stego(StegoError::TooManyPackets { count: 70000 }),
+            CliError::Stego(StegoError::NoPayload),
+            CliError::Stego(StegoError::DecryptionFailed),
+            CliError::Stego(StegoError::Uncorrectable),
+            CliError::Stego(StegoError::MissingPackets { found: 1, total: 2 }),
+            CliError::Unsupported(Error::UnsupportedFormat { detected: None }),
+            CliError::Unsupported(Error::UnsupportedMethod {
+                format: "MP3",
+                method: "sign".to_string(),
+            }),
+        ];
+        let mut codes: Vec<i32> = errors.iter().map(CliError::exit_code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+    }
+
+    #[test]
+    fn marks_methods_that_cannot_embed() {
+        let capacity = Capacity::estimate(1000, EmbedOptions::default());
+        let line = capacity_line("private-bit", Some(capacity), true);
+        assert!(line.starts_with("private-bit: 1000 carrier bits"));
+        assert!(!line.contains("estimate only"));
+        assert!(capacity_line("sign-bits", Some(capacity), false)
+            .ends_with("(estimate only, cannot be used with embed)"));
+        assert!(capacity_line("sign-bits", None, false).contains("cannot be estimated"));
+    }
+}
@@ -0,0 +1,68 @@
+use stegocrypt::stego::StegoError;
+use stegocrypt::Error;
+
+/// Error type for the command line interface.
+/// Each kind of failure exits with its own status code so that scripts can tell them apart.
+#[derive(Debug)]
+pub enum CliError {
+    Io {
+        path: String,
+        error: std::io::Error,
+    },
+    /// An input file could not be parsed. Holds any library error other than [`Error::Stego`].
+    Parse(Error),
+    Stego(StegoError),
+    /// The input or a requested option is not supported, such as a file format no carrier handles or an embedding method the format does not offer.
+    Unsupported(Error),
+}
+
+impl CliError {
+    pub fn description(&self) -> String {
+        match self {
+            CliError::Io { path, error } => format!("Could not access {}: {}", path, error),
+            CliError::Parse(error) => {
+                format!(
+                    "Encountered error while parsing input file.\n{}",
+                    error.description()
+                )
+            }
+            CliError::Stego(error) => error.description(),
+            CliError::Unsupported(error) => error.description(),
+        }
+    }
+
+    /// Returns the process exit status for this error. Status 2 is left to clap for usage errors.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Io { .. } => 3,
+            CliError::Parse(_) => 4,
+            CliError::Stego(StegoError::PayloadTooLarge { .. }) => 5,
+            CliError::Stego(StegoError::NoEmbeddingPath) => 6,
+            CliError::Stego(StegoError::TooManyPackets { .. }) => 7,
+            CliError::Stego(StegoError::NoPayload) => 8,
+            CliError::Stego(StegoError::DecryptionFailed) => 9,
+            CliError::Stego(StegoError::Uncorrectable) => 10,
+            CliError::Stego(StegoError::MissingPackets { .. }) => 11,
+            CliError::Unsupported(Error::UnsupportedMethod { .. }) => 13,
+            CliError::Unsupported(_) => 12,
+        }
+    }
+}
+
+impl From<Error> for CliError {
+    fn from(error: Error) -> CliError {
+        match error {
+            Error::Stego(error) => CliError::Stego(error),
+            error @ (Error::UnsupportedMethod { .. } | Error::UnsupportedFormat { .. }) => {
+                CliError::Unsupported(error)
+            }
+            error => CliError::Parse(error),
+        }
+    }
+}
+
+impl From<StegoError> for CliError {
+    fn from(error: StegoError) -> CliError {
+        CliError::Stego(error)
+    }
+}
@@ -0,0 +1,290 @@
+#[cfg(feature = "aac")]
+use crate::aac::ADTSParseError;
+#[cfg(feature = "flac")]
+use crate::flac::FLACParseError;
+#[cfg(feature = "jpeg")]
+use crate::jpeg::JPEGParseError;
+use crate::mp3::{ID3v2ParseError, MPEGParseError};
+#[cfg(feature = "mp4")]
+use crate::mp4::MP4ParseError;
+#[cfg(feature = "ogg")]
+use crate::ogg::OggParseError;
+#[cfg(feature = "png")]
+use crate::png::PNGParseError;
+use crate::stego::StegoError;
+#[cfg(feature = "wav")]
+use crate::wav::WAVParseError;
+
+/// Error type for everything the library can fail at.
+/// Parse errors carry the byte offset in the input where the problem was found, and the index of the frame being parsed if it was inside one.
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    MPEG {
+        error: MPEGParseError,
+        offset: usize,
+        frame: Option<usize>,
+    },
+    ID3v2 {
+        error: ID3v2ParseError,
+        offset: usize,
+        frame: Option<usize>,
+    },
+    #[cfg(feature = "wav")]
+    WAV {
+        error: WAVParseError,
+        offset: usize,
+    },
+    #[cfg(feature = "png")]
+    PNG {
+        error: PNGParseError,
+        offset: usize,
+    },
+    #[cfg(feature = "jpeg")]
+    JPEG {
+        error: JPEGParseError,
+        offset: usize,
+    },
+    #[cfg(feature = "flac")]
+    FLAC {
+        error: FLACParseError,
+        offset: usize,
+    },
+    #[cfg(feature = "ogg")]
+    Ogg {
+        error: OggParseError,
+        offset: usize,
+    },
+    #[cfg(feature = "aac")]
+    ADTS {
+        error: ADTSParseError,
+        offset: usize,
+        frame: Option<usize>,
+    },
+    #[cfg(feature = "mp4")]
+    MP4 {
+        error: MP4ParseError,
+        offset: usize,
+    },
+    Stego(StegoError),
+    /// The format has no embedding method of this name.
+    UnsupportedMethod {
+        format: &'static str,
+        method: String,
+    },
+    /// No carrier supports the input. Holds the name of the input's format if it could be identified anyway.
+    UnsupportedFormat {
+        detected: Option<&'static str>,
+    },
+}
+
+impl Error {
+    pub fn description(&self) -> String {
+        match self {
+            Error::MPEG {
+                error,
+                offset,
+                frame,
+            } => format!(
+                "{} ({})",
+                error.description(),
+                Error::location(*offset, *frame)
+            ),
+            Error::ID3v2 {
+                error,
+                offset,
+                frame,
+            } => format!(
+                "{} ({} of the ID3v2 tag)",
+                error.description(),
+                Error::location(*offset, *frame)
+            ),
+            #[cfg(feature = "wav")]
+            Error::WAV { error, offset } => format!(
+                "{} ({})",
+                error.description(),
+                Error::location(*offset, None)
+            ),
+            #[cfg(feature = "png")]
+            Error::PNG { error, offset } => format!(
+                "{} ({})",
+                error.description(),
+                Error::location(*offset, None)
+            ),
+            #[cfg(feature = "jpeg")]
+            Error::JPEG { error, offset } => format!(
+                "{} ({})",
+                error.description(),
+                Error::location(*offset, None)
+            ),
+            #[cfg(feature = "flac")]
+            Error::FLAC { error, offset } => format!(
+                "{} ({})",
+                error.description(),
+                Error::location(*offset, None)
+            ),
+            #[cfg(feature = "ogg")]
+            Error::Ogg { error, offset } => format!(
+                "{} ({})",
+                error.description(),
+                Error::location(*offset, None)
+            ),
+            #[cfg(feature = "aac")]
+            Error::ADTS {
+                error,
+                offset,
+                frame,
+            } => format!(
+                "{} ({})",
+                error.description(),
+                Error::location(*offset, *frame)
+            ),
+            #[cfg(feature = "mp4")]
+            Error::MP4 { error, offset } => format!(
+                "{} ({})",
+                error.description(),
+                Error::location(*offset, None)
+            ),
+            Error::Stego(error) => error.description(),
+            Error::Io(error) => format!("Could not read the input: {}", error),
+            Error::UnsupportedMethod { format, method } => format!(
+                "The {} format has no embedding method named \"{}\".",
+                format, method
+            ),
+            Error::UnsupportedFormat {
+                detected: Some(format),
+            } => format!(
+                "The input is a {} file, which cannot carry payloads.",
+                format
+            ),
+            Error::UnsupportedFormat { detected: None } => {
+                String::from("The input is not in any supported format.")
+            }
+        }
+    }
+
+    /// Returns the byte offset in the input where a parse error was found.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::MPEG { offset, .. } | Error::ID3v2 { offset, .. } => Some(*offset),
+            #[cfg(feature = "wav")]
+            Error::WAV { offset, .. } => Some(*offset),
+            #[cfg(feature = "png")]
+            Error::PNG { offset, .. } => Some(*offset),
+            #[cfg(feature = "jpeg")]
+            Error::JPEG { offset, .. } => Some(*offset),
+            #[cfg(feature = "flac")]
+            Error::FLAC { offset, .. } => Some(*offset),
+            #[cfg(feature = "ogg")]
+            Error::Ogg { offset, .. } => Some(*offset),
+            #[cfg(feature = "aac")]
+            Error::ADTS { offset, .. } => Some(*offset),
+            #[cfg(feature = "mp4")]
+            Error::MP4 { offset, .. } => Some(*offset),
+            Error::Stego(_)
+            | Error::Io(_)
+            | Error::UnsupportedMethod { .. }
+            | Error::UnsupportedFormat { .. } => None,
+        }
+    }
+
+    /// Returns the index of the frame that was being parsed when the error was found.
+    pub fn frame(&self) -> Option<usize> {
+        match self {
+            Error::MPEG { frame, .. } | Error::ID3v2 { frame, .. } => *frame,
+            #[cfg(feature = "aac")]
+            Error::ADTS { frame, .. } => *frame,
+            Error::Stego(_)
+            | Error::Io(_)
+            | Error::UnsupportedMethod { .. }
+            | Error::UnsupportedFormat { .. } => None,
+            #[cfg(feature = "wav")]
+            Error::WAV { .. } => None,
+            #[cfg(feature = "png")]
+            Error::PNG { .. } => None,
+            #[cfg(feature = "jpeg")]
+            Error::JPEG { .. } => None,
+            #[cfg(feature = "flac")]
+            Error::FLAC { .. } => None,
+            #[cfg(feature = "ogg")]
+            Error::Ogg { .. } => None,
+            #[cfg(feature = "mp4")]
+            Error::MP4 { .. } => None,
+        }
+    }
+
+    fn location(offset: usize, frame: Option<usize>) -> String {
+        match frame {
+            Some(frame) => format!("at byte {}, in frame {}", offset, frame),
+            None => format!("at byte {}", offset),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(&self.description())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::MPEG { error, .. } => Some(error),
+            Error::ID3v2 { error, .. } => Some(error),
+            #[cfg(feature = "wav")]
+            Error::WAV { error, .. } => Some(error),
+            #[cfg(feature = "png")]
+            Error::PNG { error, .. } => Some(error),
+            #[cfg(feature = "jpeg")]
+            Error::JPEG { error, .. } => Some(error),
+            #[cfg(feature = "flac")]
+            Error::FLAC { error, .. } => Some(error),
+            #[cfg(feature = "ogg")]
+            Error::Ogg { error, .. } => Some(error),
+            #[cfg(feature = "aac")]
+            Error::ADTS { error, .. } => Some(error),
+            #[cfg(feature = "mp4")]
+            Error::MP4 { error, .. } => Some(error),
+            Error::Stego(error) => Some(error),
+            Error::Io(error) => Some(error),
+            Error::UnsupportedMethod { .. } | Error::UnsupportedFormat { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+impl From<StegoError> for Error {
+    fn from(error: StegoError) -> Error {
+        Error::Stego(error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn describes_location_of_parse_errors() {
+        let error = Error::ID3v2 {
+            error: ID3v2ParseError::Truncated,
+            offset: 42,
+            frame: Some(3),
+        };
+        assert_eq!(error.offset(), Some(42));
+        assert_eq!(error.frame(), Some(3));
+        assert!(error.to_string().contains("at byte 42, in frame 3"));
+    }
+
+    #[test]
+    fn exposes_source_error() {
+        let error = Error::from(StegoError::NoPayload);
+        let source = std::error::Error::source(&error).unwrap();
+        assert_eq!(source.to_string(), StegoError::NoPayload.description());
+    }
+}
@@ -0,0 +1,360 @@
+use md5::{Digest, Md5};
+
+use crate::mp3::ID3v2;
+use crate::Error;
+
+pub use self::blockcarrier::BlockCarrier;
+pub use self::flaccarrier::{FLACCarrier, FLACCover};
+pub use self::flacparseerror::FLACParseError;
+pub use self::frame::Frame;
+pub use self::frameheader::{ChannelAssignment, FrameHeader};
+pub use self::metadatablock::MetadataBlock;
+pub use self::samplecarrier::SampleCarrier;
+pub use self::streaminfo::StreamInfo;
+
+mod bitreader;
+mod bitwriter;
+mod blockcarrier;
+mod flaccarrier;
+mod flacparseerror;
+mod frame;
+mod frameheader;
+mod metadatablock;
+mod samplecarrier;
+mod streaminfo;
+mod subframe;
+
+/// Length of a seek point in a SEEKTABLE block: sample number, byte offset and sample count.
+const SEEK_POINT_LENGTH: usize = 18;
+
+/// A parsed FLAC stream: its metadata blocks kept byte for byte and its frames decoded to samples.
+pub struct FLAC {
+    /// Bytes before the fLaC marker, such as an ID3v2 tag, written back unchanged.
+    pub leading: Vec<u8>,
+    pub stream_info: StreamInfo,
+    /// Metadata blocks in file order. The STREAMINFO block is written from `stream_info`.
+    pub blocks: Vec<MetadataBlock>,
+    frames: Vec<Frame>,
+    /// Index of the first sample of each frame, counting every channel, followed by the total.
+    frame_samples: Vec<usize>,
+    /// Bytes after the last frame, such as an ID3v1 tag, written back unchanged.
+    pub trailing: Vec<u8>,
+}
+
+impl FLAC {
+    /// Parses a FLAC stream, optionally preceded by an ID3v2 tag, and decodes every frame.
+    pub fn parse(data: &[u8]) -> Result<FLAC, Error> {
+        let error = |error, offset| Error::FLAC { error, offset };
+        let start = match ID3v2::has_id3v2_tag(data) {
+            true => ID3v2::tag_size(data).unwrap_or(data.len()).min(data.len()),
+            false => 0,
+        };
+        if !data[start..].starts_with(b"fLaC") {
+            return Err(error(FLACParseError::NotFLAC, start));
+        }
+
+        let mut blocks = Vec::new();
+        let mut offset = start + 4;
+        loop {
+            let (block, last, length) =
+                MetadataBlock::parse(&data[offset..]).map_err(|e| error(e, offset))?;
+            blocks.push(block);
+            offset += length;
+            if last {
+                break;
+            }
+        }
+        if blocks[0].kind != MetadataBlock::STREAMINFO {
+            return Err(error(FLACParseError::MissingStreamInfo, start + 4));
+        }
+        let stream_info = StreamInfo::parse(&blocks[0].data).map_err(|e| error(e, start + 4))?;
+
+        let mut frames = Vec::new();
+        let mut frame_samples = vec![0];
+        while FrameHeader::has_sync(&data[offset..]) {
+            let (frame, length) =
+                Frame::parse(&data[offset..], &stream_info).map_err(|e| error(e, offset))?;
+            frame_samples.push(frame_samples[frames.len()] + frame.block_size() * frame.channels());
+            frames.push(frame);
+            offset += length;
+        }
+
+        Ok(FLAC {
+            leading: data[..start].to_vec(),
+            stream_info,
+            blocks,
+            frames,
+            frame_samples,
+            trailing: data[offset..].to_vec(),
+        })
+    }
+
+    /// Serializes the stream.
+    /// If any sample has changed, the changed frames are encoded again and the MD5, frame sizes and seek points of the metadata are brought in line, unless they were left unset.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let frames: Vec<Vec<u8>> = self.frames.iter().map(Frame::to_bytes).collect();
+        let modified = self.frames.iter().any(Frame::is_modified);
+        let mut stream_info = self.stream_info.clone();
+        if modified {
+            if stream_info.md5 != [0; 16] {
+                stream_info.md5 = self.md5();
+            }
+            let lengths = frames.iter().map(|frame| frame.len() as u32);
+            if stream_info.min_frame_size != 0 {
+                stream_info.min_frame_size = lengths.clone().min().unwrap_or(0);
+            }
+            if stream_info.max_frame_size != 0 {
+                stream_info.max_frame_size = lengths.max().unwrap_or(0);
+            }
+        }
+
+        let mut bytes = self.leading.clone();
+        bytes.extend(b"fLaC");
+        for (index, block) in self.blocks.iter().enumerate() {
+            let last = index + 1 == self.blocks.len();
+            let block = match block.kind {
+                MetadataBlock::STREAMINFO => MetadataBlock {
+                    kind: block.kind,
+                    data: stream_info.to_bytes(),
+                },
+                MetadataBlock::SEEKTABLE if modified => self.seek_table(block, &frames),
+                _ => block.clone(),
+            };
+            bytes.extend(block.to_bytes(last));
+        }
+        for frame in frames {
+            bytes.extend(frame);
+        }
+        bytes.extend(&self.trailing);
+        bytes
+    }
+
+    /// Moves the seek points that fall on the start of a frame to where that frame now begins.
+    fn seek_table(&self, block: &MetadataBlock, frames: &[Vec<u8>]) -> MetadataBlock {
+        let mut offsets = Vec::with_capacity(frames.len());
+        let mut offset = 0u64;
+        for frame in frames {
+            offsets.push(offset);
+            offset += frame.len() as u64;
+        }
+
+        let mut data = block.data.clone();
+        for point in data.chunks_exact_mut(SEEK_POINT_LENGTH) {
+            let sample = u64::from_be_bytes(point[0..8].try_into().unwrap());
+            let frame = self
+                .frame_samples
+                .binary_search_by_key(&sample, |first| {
+                    *first as u64 / self.stream_info.channels as u64
+                })
+                .ok()
+                .filter(|frame| *frame < frames.len());
+            if let Some(frame) = frame {
+                point[8..16].copy_from_slice(&offsets[frame].to_be_bytes());
+            }
+        }
+        MetadataBlock {
+            kind: block.kind,
+            data,
+        }
+    }
+
+    /// Computes the MD5 of the samples as STREAMINFO records it: interleaved, little-endian, in whole bytes.
+    fn md5(&self) -> [u8; 16] {
+        let width = (self.stream_info.bits_per_sample as usize).div_ceil(8);
+        let mut md5 = Md5::new();
+        let mut buffer = Vec::new();
+        for frame in &self.frames {
+            buffer.clear();
+            for index in 0..frame.block_size() {
+                for channel in 0..frame.channels() {
+                    buffer.extend(&frame.sample(channel, index).to_le_bytes()[..width]);
+                }
+            }
+            md5.update(&buffer);
+        }
+        md5.finalize().into()
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Returns the number of samples, counting each channel separately.
+    pub fn sample_count(&self) -> usize {
+        self.frame_samples[self.frames.len()]
+    }
+
+    /// Returns the frame, channel and index within the channel of a sample.
+    fn locate(&self, index: usize) -> (usize, usize, usize) {
+        let frame = self.frame_samples.partition_point(|first| *first <= index) - 1;
+        let within = index - self.frame_samples[frame];
+        let channels = self.frames[frame].channels();
+        (frame, within % channels, within / channels)
+    }
+
+    /// Returns a sample, with the samples of every channel interleaved as in a WAV file.
+    pub fn sample(&self, index: usize) -> i64 {
+        let (frame, channel, index) = self.locate(index);
+        self.frames[frame].sample(channel, index) as i64
+    }
+
+    pub fn set_sample(&mut self, index: usize, value: i64) {
+        let (frame, channel, index) = self.locate(index);
+        self.frames[frame].set_sample(channel, index, value as i32);
+    }
+
+    /// Returns the smallest and largest values a sample can take.
+    pub fn sample_range(&self) -> (i64, i64) {
+        let bits = self.stream_info.bits_per_sample;
+        (-(1 << (bits - 1)), (1 << (bits - 1)) - 1)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// Samples per channel in every frame but the last.
+    const BLOCK_SIZE: usize = 576;
+
+    /// Builds a 16-bit stereo stream of `count` samples per channel, in frames of 576 samples and a shorter last one.
+    /// The frames take turns at each channel assignment, and the metadata holds a seek table, a 64-byte PADDING block and an APPLICATION block.
+    pub(crate) fn flac_bytes(count: usize) -> Vec<u8> {
+        let mut info = vec![0x02, 0x40, 0x02, 0x40, 0, 0, 1, 0, 0, 1];
+        let packed = 44100u64 << 44 | 1 << 41 | 15 << 36 | count as u64;
+        info.extend(packed.to_be_bytes());
+        info.extend([1; 16]);
+        let stream_info = StreamInfo::parse(&info).unwrap();
+
+        let frames: Vec<Frame> = (0..count.div_ceil(BLOCK_SIZE))
+            .map(|number| {
+                let first = number * BLOCK_SIZE;
+                let block_size = (count - first).min(BLOCK_SIZE);
+                // Fixed block size, 576 samples or a 16-bit size, 44.1 kHz, 16 bits per sample.
+                let (block_code, size) = match block_size {
+                    BLOCK_SIZE => (2, vec![]),
+                    _ => (7, (block_size as u16 - 1).to_be_bytes().to_vec()),
+                };
+                let channels = [1, 8, 9, 10][number % 4];
+                let mut raw = vec![0xFF, 0xF8, block_code << 4 | 9, channels << 4 | 8];
+                raw.push(number as u8);
+                raw.extend(size);
+                raw.push(crc::Crc::<u8>::new(&crc::CRC_8_SMBUS).checksum(&raw));
+                let header = FrameHeader::parse(&raw, &stream_info).unwrap();
+
+                let samples = (0..2)
+                    .map(|channel| {
+                        (first..first + block_size)
+                            .map(|i| {
+                                let phase = i as f64 * (0.031 + channel as f64 * 0.007);
+                                (phase.sin() * 9000.0) as i32 + (i * 7919 % 61) as i32 - 30
+                            })
+                            .collect()
+                    })
+                    .collect();
+                Frame::new(header, samples)
+            })
+            .collect();
+
+        let mut seek_table = Vec::new();
+        for (sample, offset) in [(0u64, 0u64), (BLOCK_SIZE as u64 * 2, 1), (u64::MAX, 0)] {
+            seek_table.extend(sample.to_be_bytes());
+            seek_table.extend(offset.to_be_bytes());
+            seek_table.extend((BLOCK_SIZE as u16).to_be_bytes());
+        }
+        let mut application = b"test".to_vec();
+        application.extend([7; 12]);
+        let blocks = vec![
+            MetadataBlock {
+                kind: MetadataBlock::STREAMINFO,
+                data: info,
+            },
+            MetadataBlock {
+                kind: MetadataBlock::SEEKTABLE,
+                data: seek_table,
+            },
+            MetadataBlock {
+                kind: MetadataBlock::APPLICATION,
+                data: application,
+            },
+            MetadataBlock {
+                kind: MetadataBlock::PADDING,
+                data: vec![0; 64],
+            },
+        ];
+
+        let mut frame_samples = vec![0];
+        for frame in &frames {
+            frame_samples.push(frame_samples.last().unwrap() + frame.block_size() * 2);
+        }
+        FLAC {
+            leading: Vec::new(),
+            stream_info,
+            blocks,
+            frames,
+            frame_samples,
+            trailing: Vec::new(),
+        }
+        .to_bytes()
+    }
+
+    #[test]
+    fn writes_unchanged_stream_back() {
+        let bytes = flac_bytes(2000);
+        let flac = FLAC::parse(&bytes).unwrap();
+        assert_eq!(flac.frames().len(), 4);
+        assert_eq!(flac.sample_count(), 4000);
+        assert_eq!(flac.frames()[3].block_size(), 2000 - 3 * BLOCK_SIZE);
+        assert_eq!(flac.to_bytes(), bytes);
+
+        let mut tagged = b"ID3\x04\0\0\0\0\0\x05hello".to_vec();
+        tagged.extend(&bytes);
+        tagged.extend(b"TAG");
+        let flac = FLAC::parse(&tagged).unwrap();
+        assert_eq!(flac.trailing, b"TAG");
+        assert_eq!(flac.to_bytes(), tagged);
+    }
+
+    #[test]
+    fn rejects_damaged_or_truncated_frame() {
+        let bytes = flac_bytes(2000);
+        let flac_error = |data: &[u8]| match FLAC::parse(data) {
+            Err(Error::FLAC { error, .. }) => error,
+            _ => panic!("expected a FLAC error"),
+        };
+
+        let mut damaged = bytes.clone();
+        *damaged.last_mut().unwrap() ^= 1;
+        assert_eq!(flac_error(&damaged), FLACParseError::ChecksumMismatch);
+        assert_eq!(
+            flac_error(&bytes[..bytes.len() - 1]),
+            FLACParseError::TruncatedFrame
+        );
+        assert_eq!(flac_error(&bytes[..20]), FLACParseError::TruncatedBlock);
+    }
+
+    #[test]
+    fn encodes_changed_samples_and_updates_metadata() {
+        let original = FLAC::parse(&flac_bytes(2000)).unwrap();
+        let mut flac = FLAC::parse(&flac_bytes(2000)).unwrap();
+        for index in (0..flac.sample_count()).step_by(3) {
+            flac.set_sample(index, flac.sample(index) ^ 1);
+        }
+        flac.set_sample(3001, 32767);
+
+        let parsed = FLAC::parse(&flac.to_bytes()).unwrap();
+        assert!((0..parsed.sample_count()).all(|i| parsed.sample(i) == flac.sample(i)));
+        assert_eq!(parsed.stream_info.md5, flac.md5());
+        assert_ne!(parsed.stream_info.md5, original.stream_info.md5);
+
+        let frame_lengths: Vec<usize> =
+            parsed.frames().iter().map(|f| f.to_bytes().len()).collect();
+        assert_eq!(
+            parsed.stream_info.max_frame_size as usize,
+            *frame_lengths.iter().max().unwrap()
+        );
+        let seek_table = &parsed.blocks[1].data;
+        let offset = u64::from_be_bytes(seek_table[26..34].try_into().unwrap());
+        assert_eq!(offset as usize, frame_lengths[0] + frame_lengths[1]);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::flac::FLACParseError;
+
+/// Reads a FLAC frame bit by bit, most significant bit first.
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    /// Position in bits from the start of the data.
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8], position: usize) -> BitReader<'a> {
+        BitReader {
+            data,
+            position: position * 8,
+        }
+    }
+
+    /// Returns the number of whole bytes read so far, counting a partly read byte.
+    pub fn byte_position(&self) -> usize {
+        self.position.div_ceil(8)
+    }
+
+    fn byte(&self) -> Result<u8, FLACParseError> {
+        self.data
+            .get(self.position / 8)
+            .copied()
+            .ok_or(FLACParseError::TruncatedFrame)
+    }
+
+    pub fn read_bit(&mut self) -> Result<bool, FLACParseError> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    /// Reads an unsigned value of up to 64 bits.
+    pub fn read_bits(&mut self, length: u32) -> Result<u64, FLACParseError> {
+        let mut value = 0;
+        let mut remaining = length;
+        while remaining > 0 {
+            let offset = (self.position % 8) as u32;
+            let available = 8 - offset;
+            let take = available.min(remaining);
+            let bits = (self.byte()? as u64 >> (available - take)) & ((1 << take) - 1);
+            value = (value << take) | bits;
+            remaining -= take;
+            self.position += take as usize;
+        }
+        Ok(value)
+    }
+
+    /// Reads a two's complement value of up to 64 bits. A length of zero reads nothing and returns zero.
+    pub fn read_signed(&mut self, length: u32) -> Result<i64, FLACParseError> {
+        match length {
+            0 => Ok(0),
+            _ => Ok(((self.read_bits(length)? << (64 - length)) as i64) >> (64 - length)),
+        }
+    }
+
+    /// Reads a unary value: the number of zero bits before the next one bit.
+    pub fn read_unary(&mut self) -> Result<u32, FLACParseError> {
+        let mut count = 0;
+        loop {
+            let offset = (self.position % 8) as u32;
+            let bits = self.byte()? << offset;
+            if bits == 0 {
+                count += 8 - offset;
+                self.position += (8 - offset) as usize;
+            } else {
+                let zeros = bits.leading_zeros();
+                count += zeros;
+                self.position += zeros as usize + 1;
+                return Ok(count);
+            }
+        }
+    }
+
+    /// Skips to the start of the next byte.
+    pub fn align(&mut self) {
+        self.position = self.byte_position() * 8;
+    }
+}
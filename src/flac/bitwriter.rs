@@ -0,0 +1,79 @@
+/// Writes a FLAC frame bit by bit, most significant bit first.
+#[derive(Default)]
+pub struct BitWriter {
+    pub data: Vec<u8>,
+    /// Bits not yet written to `data`, in the low `count` bits.
+    buffer: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    pub fn new(data: Vec<u8>) -> BitWriter {
+        BitWriter {
+            data,
+            ..BitWriter::default()
+        }
+    }
+
+    /// Writes the low `length` bits of a value, up to 64.
+    pub fn write_bits(&mut self, value: u64, length: u32) {
+        if length > 32 {
+            self.write_bits(value >> 32, length - 32);
+            self.write_bits(value, 32);
+            return;
+        }
+        self.buffer = (self.buffer << length) | (value & ((1 << length) - 1));
+        self.count += length;
+        while self.count >= 8 {
+            self.count -= 8;
+            self.data.push((self.buffer >> self.count) as u8);
+        }
+    }
+
+    pub fn write_signed(&mut self, value: i64, length: u32) {
+        self.write_bits(value as u64, length);
+    }
+
+    /// Writes `value` zero bits followed by a one bit.
+    pub fn write_unary(&mut self, value: u64) {
+        for _ in 0..value / 32 {
+            self.write_bits(0, 32);
+        }
+        self.write_bits(1, (value % 32) as u32 + 1);
+    }
+
+    /// Pads the last byte with zero bits.
+    pub fn align(&mut self) {
+        if self.count > 0 {
+            self.write_bits(0, 8 - self.count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::flac::bitreader::BitReader;
+    use crate::flac::FLACParseError;
+
+    #[test]
+    fn reads_what_was_written() {
+        let mut writer = BitWriter::new(vec![0xAA]);
+        writer.write_bits(5, 3);
+        writer.write_signed(-300, 33);
+        writer.write_unary(41);
+        writer.write_unary(0);
+        writer.write_bits(0xFFFF_FFFF_FFFF, 48);
+        writer.align();
+
+        let mut reader = BitReader::new(&writer.data, 1);
+        assert_eq!(reader.read_bits(3).unwrap(), 5);
+        assert_eq!(reader.read_signed(33).unwrap(), -300);
+        assert_eq!(reader.read_unary().unwrap(), 41);
+        assert_eq!(reader.read_unary().unwrap(), 0);
+        assert_eq!(reader.read_bits(48).unwrap(), 0xFFFF_FFFF_FFFF);
+        reader.align();
+        assert_eq!(reader.byte_position(), writer.data.len());
+        assert_eq!(reader.read_bit(), Err(FLACParseError::TruncatedFrame));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::flac::{MetadataBlock, FLAC};
+use crate::stego::BitCarrier;
+
+/// Application ID of the APPLICATION block that payloads are written to.
+pub const APPLICATION_ID: [u8; 4] = *b"stgc";
+
+/// Carries payload bits in the body of a metadata block: the first PADDING block, or an APPLICATION block of its own.
+/// Either is easy to find for anyone who looks, but can hold far more than the samples.
+#[derive(Clone, Copy)]
+pub struct BlockCarrier {
+    kind: u8,
+}
+
+impl BlockCarrier {
+    pub fn padding() -> BlockCarrier {
+        BlockCarrier {
+            kind: MetadataBlock::PADDING,
+        }
+    }
+
+    pub fn application() -> BlockCarrier {
+        BlockCarrier {
+            kind: MetadataBlock::APPLICATION,
+        }
+    }
+
+    /// Returns whether the carrier can make room for a payload with [`BlockCarrier::reserve`].
+    pub fn is_resizable(&self) -> bool {
+        self.kind == MetadataBlock::APPLICATION
+    }
+
+    /// Bytes at the start of the block body that carry no payload: the application ID of an APPLICATION block.
+    fn skip(&self) -> usize {
+        match self.kind {
+            MetadataBlock::APPLICATION => APPLICATION_ID.len(),
+            _ => 0,
+        }
+    }
+
+    fn find(&self, cover: &FLAC) -> Option<usize> {
+        cover.blocks.iter().position(|block| {
+            block.kind == self.kind
+                && (self.kind != MetadataBlock::APPLICATION
+                    || block.data.starts_with(&APPLICATION_ID))
+        })
+    }
+
+    /// Replaces the APPLICATION block with one of `length` zero bytes after the application ID, adding it ahead of any padding if there is none.
+    pub fn reserve(&self, cover: &mut FLAC, length: usize) {
+        let mut data = APPLICATION_ID.to_vec();
+        data.resize(APPLICATION_ID.len() + length, 0);
+        match self.find(cover) {
+            Some(index) => cover.blocks[index].data = data,
+            None => {
+                let index = cover
+                    .blocks
+                    .iter()
+                    .position(|block| block.kind == MetadataBlock::PADDING)
+                    .unwrap_or(cover.blocks.len());
+                cover.blocks.insert(
+                    index,
+                    MetadataBlock {
+                        kind: self.kind,
+                        data,
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl BitCarrier<FLAC> for BlockCarrier {
+    fn positions(&self, cover: &FLAC) -> usize {
+        self.find(cover).map_or(0, |index| {
+            (cover.blocks[index].data.len() - self.skip()) * 8
+        })
+    }
+
+    fn read_bit(&self, cover: &FLAC, position: usize) -> bool {
+        let block = &cover.blocks[self.find(cover).expect("positions exist only in a block")];
+        block.data[self.skip() + position / 8] & (0x80 >> (position % 8)) != 0
+    }
+
+    fn write_bit(&self, cover: &mut FLAC, position: usize, bit: bool) {
+        let index = self.find(cover).expect("positions exist only in a block");
+        let byte = &mut cover.blocks[index].data[self.skip() + position / 8];
+        match bit {
+            true => *byte |= 0x80 >> (position % 8),
+            false => *byte &= !(0x80 >> (position % 8)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::flac::test::flac_bytes;
+
+    #[test]
+    fn adds_application_block_before_padding() {
+        let mut flac = FLAC::parse(&flac_bytes(100)).unwrap();
+        let carrier = BlockCarrier::application();
+        assert_eq!(carrier.positions(&flac), 0);
+        assert_eq!(BlockCarrier::padding().positions(&flac), 64 * 8);
+
+        carrier.reserve(&mut flac, 3);
+        carrier.write_bit(&mut flac, 9, true);
+        let flac = FLAC::parse(&flac.to_bytes()).unwrap();
+        assert_eq!(flac.blocks.len(), 5);
+        assert_eq!(flac.blocks[3].data, b"stgc\0\x40\0");
+        assert_eq!(flac.blocks[4].kind, MetadataBlock::PADDING);
+        assert!(carrier.read_bit(&flac, 9));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::carrier::{embed_growing, Carrier, Cover, CoverCarrier};
+use crate::flac::blockcarrier::APPLICATION_ID;
+use crate::flac::{BlockCarrier, MetadataBlock, SampleCarrier, FLAC};
+use crate::stego::{self, BitCarrier, Capacity, EmbedOptions, StegoError, StegoKey};
+use crate::Error;
+
+/// The FLAC format, embedding in the lowest bit of the samples or in the PADDING or an APPLICATION metadata block.
+pub struct FLACCarrier;
+
+/// A parsed FLAC stream with one of its carriers selected.
+pub struct FLACCover {
+    pub flac: FLAC,
+    carrier: FLACCoverCarrier,
+}
+
+enum FLACCoverCarrier {
+    Sample(SampleCarrier),
+    Block(BlockCarrier),
+}
+
+impl FLACCover {
+    fn carrier(&self) -> &dyn BitCarrier<FLAC> {
+        match &self.carrier {
+            FLACCoverCarrier::Sample(carrier) => carrier,
+            FLACCoverCarrier::Block(carrier) => carrier,
+        }
+    }
+}
+
+impl Carrier for FLACCarrier {
+    fn name(&self) -> &'static str {
+        "flac"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &["lsb", "padding", "application"]
+    }
+
+    fn parse(&self, data: &[u8], method: &str) -> Result<Box<dyn Cover>, Error> {
+        let carrier = match method {
+            "lsb" => FLACCoverCarrier::Sample(SampleCarrier),
+            "padding" => FLACCoverCarrier::Block(BlockCarrier::padding()),
+            "application" => FLACCoverCarrier::Block(BlockCarrier::application()),
+            _ => {
+                return Err(Error::UnsupportedMethod {
+                    format: self.name(),
+                    method: method.to_string(),
+                })
+            }
+        };
+        Ok(Box::new(FLACCover {
+            flac: FLAC::parse(data)?,
+            carrier,
+        }))
+    }
+
+    /// The APPLICATION block grows to fit the payload, so its capacity is that of the largest block.
+    fn capacity(
+        &self,
+        data: &[u8],
+        method: &str,
+        options: EmbedOptions,
+    ) -> Result<Option<Capacity>, Error> {
+        let cover = self.parse(data, method)?;
+        let positions = match method {
+            "application" => (MetadataBlock::MAX_LENGTH - APPLICATION_ID.len()) * 8,
+            _ => cover.positions(),
+        };
+        Ok(Some(Capacity::estimate(positions, options)))
+    }
+}
+
+impl Cover for FLACCover {
+    fn positions(&self) -> usize {
+        self.carrier().positions(&self.flac)
+    }
+
+    fn read_bit(&self, position: usize) -> bool {
+        self.carrier().read_bit(&self.flac, position)
+    }
+
+    fn write_bit(&mut self, position: usize, bit: bool) {
+        match &self.carrier {
+            FLACCoverCarrier::Sample(carrier) => carrier.write_bit(&mut self.flac, position, bit),
+            FLACCoverCarrier::Block(carrier) => carrier.write_bit(&mut self.flac, position, bit),
+        }
+    }
+
+    fn cost(&self, position: usize) -> f64 {
+        self.carrier().cost(&self.flac, position)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.flac.to_bytes()
+    }
+
+    /// An APPLICATION block is emptied and then grown to the payload, up to the largest block there can be.
+    fn embed_raw(
+        &mut self,
+        key: &StegoKey,
+        data: &[u8],
+        options: EmbedOptions,
+    ) -> Result<(), StegoError> {
+        let carrier = match &self.carrier {
+            FLACCoverCarrier::Block(carrier) if carrier.is_resizable() => *carrier,
+            _ => return stego::embed_raw(self, &CoverCarrier, key, data, options),
+        };
+        carrier.reserve(&mut self.flac, 0);
+        embed_growing(self, key, data, options, |cover, length| {
+            let available = MetadataBlock::MAX_LENGTH - APPLICATION_ID.len();
+            if length > available {
+                return Err(StegoError::PayloadTooLarge {
+                    required: length * 8,
+                    available: available * 8,
+                });
+            }
+            carrier.reserve(&mut cover.flac, length);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::flac::test::flac_bytes;
+
+    #[test]
+    fn keeps_padding_at_its_size() {
+        let key = StegoKey::derive("hunter2");
+        let mut cover = FLACCarrier.parse(&flac_bytes(1000), "padding").unwrap();
+        assert!(matches!(
+            cover.embed_raw(&key, &[0x5A; 64], EmbedOptions::default()),
+            Err(StegoError::PayloadTooLarge { available: 512, .. })
+        ));
+    }
+}
@@ -0,0 +1,47 @@
+/// Error type for FLAC parsing.
+#[derive(Debug, PartialEq)]
+pub enum FLACParseError {
+    NotFLAC,
+    TruncatedBlock,
+    MissingStreamInfo,
+    InvalidStreamInfo,
+    InvalidFrameHeader,
+    InvalidSubframe,
+    InvalidResidual,
+    TruncatedFrame,
+    ChecksumMismatch,
+}
+
+impl FLACParseError {
+    pub fn description(&self) -> String {
+        match self {
+            FLACParseError::NotFLAC => String::from("Input does not start with a fLaC marker."),
+            FLACParseError::TruncatedBlock => {
+                String::from("A metadata block extends past the end of the data.")
+            }
+            FLACParseError::MissingStreamInfo => {
+                String::from("The first metadata block is not a STREAMINFO block.")
+            }
+            FLACParseError::InvalidStreamInfo => {
+                String::from("The STREAMINFO block describes an impossible stream.")
+            }
+            FLACParseError::InvalidFrameHeader => String::from("Invalid frame header."),
+            FLACParseError::InvalidSubframe => String::from("Invalid subframe."),
+            FLACParseError::InvalidResidual => String::from("Invalid residual coding."),
+            FLACParseError::TruncatedFrame => {
+                String::from("A frame extends past the end of the data.")
+            }
+            FLACParseError::ChecksumMismatch => {
+                String::from("A frame does not match its CRC-16 checksum.")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for FLACParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(&self.description())
+    }
+}
+
+impl std::error::Error for FLACParseError {}
@@ -0,0 +1,161 @@
+use crc::{Crc, CRC_16_UMTS};
+
+use crate::flac::bitreader::BitReader;
+use crate::flac::bitwriter::BitWriter;
+use crate::flac::subframe::{self, Predictor};
+use crate::flac::{ChannelAssignment, FLACParseError, FrameHeader, StreamInfo};
+
+/// CRC-16 protecting a whole frame: polynomial 0x8005, initial value zero, not reflected.
+const FLAC_CRC_16: Crc<u16> = Crc::<u16>::new(&CRC_16_UMTS);
+
+/// A FLAC frame decoded to its samples.
+/// The frame is written back as read until one of its samples changes, and is then encoded again losslessly.
+pub struct Frame {
+    pub header: FrameHeader,
+    /// Samples of each channel, with any inter-channel decorrelation undone.
+    samples: Vec<Vec<i32>>,
+    /// Predictor of each subframe, tried again when the frame is encoded.
+    predictors: Vec<Predictor>,
+    /// The frame as read.
+    data: Vec<u8>,
+    modified: bool,
+}
+
+impl Frame {
+    /// Creates a frame to be encoded from the samples of each channel.
+    pub fn new(header: FrameHeader, samples: Vec<Vec<i32>>) -> Frame {
+        Frame {
+            predictors: vec![Predictor::Verbatim; samples.len()],
+            header,
+            samples,
+            data: Vec::new(),
+            modified: true,
+        }
+    }
+
+    /// Decodes the frame at the start of the data, returning it with its length.
+    pub fn parse(data: &[u8], stream_info: &StreamInfo) -> Result<(Frame, usize), FLACParseError> {
+        let header = FrameHeader::parse(data, stream_info)?;
+        let mut reader = BitReader::new(data, header.raw.len());
+        let mut subframes = Vec::new();
+        let mut predictors = Vec::new();
+        for channel in 0..header.channels.channels() {
+            let bits = header.bits_per_sample + header.channels.is_side(channel) as u32;
+            let (samples, predictor) = subframe::decode(&mut reader, header.block_size, bits)?;
+            subframes.push(samples);
+            predictors.push(predictor);
+        }
+        reader.align();
+
+        let end = reader.byte_position() + 2;
+        let checksum = data
+            .get(end - 2..end)
+            .ok_or(FLACParseError::TruncatedFrame)?;
+        if FLAC_CRC_16.checksum(&data[..end - 2]).to_be_bytes() != checksum {
+            return Err(FLACParseError::ChecksumMismatch);
+        }
+        let frame = Frame {
+            samples: decorrelate(header.channels, subframes),
+            header,
+            predictors,
+            data: data[..end].to_vec(),
+            modified: false,
+        };
+        Ok((frame, end))
+    }
+
+    pub fn channels(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns the number of samples per channel.
+    pub fn block_size(&self) -> usize {
+        self.header.block_size
+    }
+
+    pub fn sample(&self, channel: usize, index: usize) -> i32 {
+        self.samples[channel][index]
+    }
+
+    pub fn set_sample(&mut self, channel: usize, index: usize, value: i32) {
+        if self.samples[channel][index] != value {
+            self.samples[channel][index] = value;
+            self.modified = true;
+        }
+    }
+
+    /// Returns whether any sample has changed since the frame was read.
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Serializes the frame, encoding it again if any of its samples has changed.
+    /// The header is kept as it was, and so is the channel assignment.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        if !self.modified {
+            return self.data.clone();
+        }
+        let mut writer = BitWriter::new(self.header.raw.clone());
+        for (channel, samples) in correlate(self.header.channels, &self.samples)
+            .iter()
+            .enumerate()
+        {
+            let bits = self.header.bits_per_sample + self.header.channels.is_side(channel) as u32;
+            subframe::encode(&mut writer, samples, bits, &self.predictors[channel]);
+        }
+        writer.align();
+        let checksum = FLAC_CRC_16.checksum(&writer.data);
+        writer.data.extend(checksum.to_be_bytes());
+        writer.data
+    }
+}
+
+/// Turns the decoded subframes into left and right channels.
+fn decorrelate(channels: ChannelAssignment, mut subframes: Vec<Vec<i64>>) -> Vec<Vec<i32>> {
+    if channels != ChannelAssignment::Independent(subframes.len() as u8) {
+        let (first, second) = (&subframes[0], &subframes[1]);
+        let (left, right) = first
+            .iter()
+            .zip(second)
+            .map(|(first, second)| match channels {
+                ChannelAssignment::LeftSide => (*first, first - second),
+                ChannelAssignment::RightSide => (first + second, *second),
+                _ => {
+                    let mid = (first << 1) | (second & 1);
+                    ((mid + second) >> 1, (mid - second) >> 1)
+                }
+            })
+            .unzip();
+        subframes = vec![left, right];
+    }
+    subframes
+        .into_iter()
+        .map(|samples| samples.into_iter().map(|sample| sample as i32).collect())
+        .collect()
+}
+
+/// Turns left and right channels into the subframes of the channel assignment.
+fn correlate(channels: ChannelAssignment, samples: &[Vec<i32>]) -> Vec<Vec<i64>> {
+    let widened = |samples: &Vec<i32>| samples.iter().map(|sample| *sample as i64).collect();
+    if channels == ChannelAssignment::Independent(samples.len() as u8) {
+        return samples.iter().map(widened).collect();
+    }
+    let (left, right): (Vec<i64>, Vec<i64>) = (widened(&samples[0]), widened(&samples[1]));
+    let side = left
+        .iter()
+        .zip(&right)
+        .map(|(left, right)| left - right)
+        .collect();
+    match channels {
+        ChannelAssignment::LeftSide => vec![left, side],
+        ChannelAssignment::RightSide => vec![side, right],
+        _ => {
+            let mid = left
+                .iter()
+                .zip(&right)
+                .map(|(left, right)| (left + right) >> 1)
+                .collect();
+            vec![mid, side]
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use crc::{Crc, CRC_8_SMBUS};
+
+use crate::flac::{FLACParseError, StreamInfo};
+
+/// CRC-8 protecting a frame header: polynomial 0x07, initial value zero.
+const FLAC_CRC_8: Crc<u8> = Crc::<u8>::new(&CRC_8_SMBUS);
+
+/// How the channels of a frame are coded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChannelAssignment {
+    /// Each channel coded on its own.
+    Independent(u8),
+    /// Left channel and the difference left minus right.
+    LeftSide,
+    /// Difference left minus right and the right channel.
+    RightSide,
+    /// Average of left and right, rounded down, and their difference.
+    MidSide,
+}
+
+impl ChannelAssignment {
+    pub fn channels(&self) -> usize {
+        match self {
+            ChannelAssignment::Independent(channels) => *channels as usize,
+            _ => 2,
+        }
+    }
+
+    /// Returns whether the subframe of a channel holds a side channel, which needs one more bit per sample.
+    pub fn is_side(&self, channel: usize) -> bool {
+        match self {
+            ChannelAssignment::Independent(_) => false,
+            ChannelAssignment::RightSide => channel == 0,
+            ChannelAssignment::LeftSide | ChannelAssignment::MidSide => channel == 1,
+        }
+    }
+}
+
+/// The header of a FLAC frame. Only the fields needed to decode the frame are interpreted; the bytes are kept to be written back unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameHeader {
+    /// Samples per channel.
+    pub block_size: usize,
+    pub channels: ChannelAssignment,
+    pub bits_per_sample: u32,
+    /// The header as read, up to and including its CRC-8.
+    pub raw: Vec<u8>,
+}
+
+impl FrameHeader {
+    /// Returns whether the data starts with the 14-bit frame sync code.
+    pub fn has_sync(data: &[u8]) -> bool {
+        data.len() >= 2 && data[0] == 0xFF && data[1] & 0xFE == 0xF8
+    }
+
+    pub fn parse(data: &[u8], stream_info: &StreamInfo) -> Result<FrameHeader, FLACParseError> {
+        let invalid = FLACParseError::InvalidFrameHeader;
+        if !FrameHeader::has_sync(data) {
+            return Err(invalid);
+        }
+        let byte = |position: usize| {
+            data.get(position)
+                .copied()
+                .ok_or(FLACParseError::TruncatedFrame)
+        };
+        let (block_code, rate_code) = (byte(2)? >> 4, byte(2)? & 0x0F);
+        let (channel_code, size_code) = (byte(3)? >> 4, (byte(3)? >> 1) & 0x07);
+        if byte(3)? & 1 != 0 {
+            return Err(invalid);
+        }
+
+        // The frame or sample number is coded like UTF-8, in up to seven bytes.
+        let mut position = 5 + match byte(4)?.leading_ones() {
+            0 => 0,
+            length @ 2..=7 => length as usize - 1,
+            _ => return Err(invalid),
+        };
+        if !(5..position).all(|p| byte(p).is_ok_and(|b| b & 0xC0 == 0x80)) {
+            return Err(invalid);
+        }
+
+        let block_size = match block_code {
+            0 => return Err(invalid),
+            1 => 192,
+            2..=5 => 576 << (block_code - 2),
+            6 => {
+                position += 1;
+                byte(position - 1)? as usize + 1
+            }
+            7 => {
+                position += 2;
+                u16::from_be_bytes([byte(position - 2)?, byte(position - 1)?]) as usize + 1
+            }
+            _ => 256 << (block_code - 8),
+        };
+        position += match rate_code {
+            12 => 1,
+            13 | 14 => 2,
+            15 => return Err(invalid),
+            _ => 0,
+        };
+        let bits_per_sample = match size_code {
+            0 => stream_info.bits_per_sample as u32,
+            1 => 8,
+            2 => 12,
+            4 => 16,
+            5 => 20,
+            6 => 24,
+            7 => 32,
+            _ => return Err(invalid),
+        };
+        let channels = match channel_code {
+            0..=7 => ChannelAssignment::Independent(channel_code + 1),
+            8 => ChannelAssignment::LeftSide,
+            9 => ChannelAssignment::RightSide,
+            10 => ChannelAssignment::MidSide,
+            _ => return Err(invalid),
+        };
+        if channels.channels() != stream_info.channels as usize {
+            return Err(invalid);
+        }
+
+        if FLAC_CRC_8.checksum(&data[..position]) != byte(position)? {
+            return Err(FLACParseError::ChecksumMismatch);
+        }
+        Ok(FrameHeader {
+            block_size,
+            channels,
+            bits_per_sample,
+            raw: data[..position + 1].to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_stream_info_and_frame_header() {
+        let mut info = [0; 34];
+        info[10..14].copy_from_slice(&[0x0A, 0xC4, 0x42, 0xF0]);
+        let stream_info = StreamInfo::parse(&info).unwrap();
+        assert_eq!(stream_info.sample_rate, 44100);
+        assert_eq!((stream_info.channels, stream_info.bits_per_sample), (2, 16));
+        assert_eq!(stream_info.to_bytes(), info);
+
+        // Block size 4097 in 16 bits, 44.1 kHz, mid/side, 16 bits per sample, sample number 0x123 in two bytes.
+        let mut data = vec![0xFF, 0xF9, 0x79, 0xA8, 0xC4, 0xA3, 0x10, 0x00];
+        data.push(FLAC_CRC_8.checksum(&data));
+        let header = FrameHeader::parse(&data, &stream_info).unwrap();
+        assert_eq!(header.block_size, 4097);
+        assert_eq!(header.channels, ChannelAssignment::MidSide);
+        assert_eq!(header.bits_per_sample, 16);
+        assert_eq!(header.raw, data);
+
+        data[8] ^= 1;
+        assert_eq!(
+            FrameHeader::parse(&data, &stream_info),
+            Err(FLACParseError::ChecksumMismatch)
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use crate::flac::FLACParseError;
+
+/// Length of a metadata block header: a last-block flag, a 7-bit block type and a 24-bit length.
+const HEADER_LENGTH: usize = 4;
+
+/// A metadata block of a FLAC stream, kept byte for byte.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataBlock {
+    pub kind: u8,
+    pub data: Vec<u8>,
+}
+
+impl MetadataBlock {
+    pub const STREAMINFO: u8 = 0;
+    pub const PADDING: u8 = 1;
+    pub const APPLICATION: u8 = 2;
+    pub const SEEKTABLE: u8 = 3;
+
+    /// Largest body a block can have, given its 24-bit length field.
+    pub const MAX_LENGTH: usize = (1 << 24) - 1;
+
+    /// Parses the block at the start of the data, returning it with whether it is the last block and its length including the header.
+    pub fn parse(data: &[u8]) -> Result<(MetadataBlock, bool, usize), FLACParseError> {
+        let header = data
+            .get(0..HEADER_LENGTH)
+            .ok_or(FLACParseError::TruncatedBlock)?;
+        let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let body = data
+            .get(HEADER_LENGTH..HEADER_LENGTH + length)
+            .ok_or(FLACParseError::TruncatedBlock)?;
+        let block = MetadataBlock {
+            kind: header[0] & 0x7F,
+            data: body.to_vec(),
+        };
+        Ok((block, header[0] & 0x80 != 0, HEADER_LENGTH + length))
+    }
+
+    pub fn to_bytes(&self, last: bool) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + self.data.len());
+        bytes.push(self.kind | if last { 0x80 } else { 0 });
+        bytes.extend(&(self.data.len() as u32).to_be_bytes()[1..]);
+        bytes.extend(&self.data);
+        bytes
+    }
+}
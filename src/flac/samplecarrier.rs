@@ -0,0 +1,26 @@
+use crate::flac::FLAC;
+use crate::stego::{lsb_match, sample_cost, BitCarrier};
+
+/// Carries payload bits in the lowest bit of every sample, written by LSB matching.
+/// The frames holding changed samples are encoded again when the stream is written, so the stream stays lossless.
+pub struct SampleCarrier;
+
+impl BitCarrier<FLAC> for SampleCarrier {
+    fn positions(&self, cover: &FLAC) -> usize {
+        cover.sample_count()
+    }
+
+    fn read_bit(&self, cover: &FLAC, position: usize) -> bool {
+        cover.sample(position) & 1 == 1
+    }
+
+    fn write_bit(&self, cover: &mut FLAC, position: usize, bit: bool) {
+        let (min, max) = cover.sample_range();
+        let value = lsb_match(cover.sample(position), 1, 0, bit, min, max, position);
+        cover.set_sample(position, value);
+    }
+
+    fn cost(&self, cover: &FLAC, position: usize) -> f64 {
+        sample_cost(cover.sample(position))
+    }
+}
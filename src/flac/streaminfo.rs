@@ -0,0 +1,61 @@
+use crate::flac::FLACParseError;
+
+/// Length of the body of a STREAMINFO block.
+const LENGTH: usize = 34;
+
+/// The STREAMINFO block, describing the whole stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamInfo {
+    pub min_block_size: u16,
+    pub max_block_size: u16,
+    /// Smallest frame in bytes, or zero if unknown.
+    pub min_frame_size: u32,
+    /// Largest frame in bytes, or zero if unknown.
+    pub max_frame_size: u32,
+    pub sample_rate: u32,
+    pub channels: u8,
+    pub bits_per_sample: u8,
+    /// Samples per channel, or zero if unknown.
+    pub total_samples: u64,
+    /// MD5 of the decoded samples, or all zeros if it was not computed.
+    pub md5: [u8; 16],
+}
+
+impl StreamInfo {
+    pub fn parse(data: &[u8]) -> Result<StreamInfo, FLACParseError> {
+        if data.len() != LENGTH {
+            return Err(FLACParseError::InvalidStreamInfo);
+        }
+        let packed = u64::from_be_bytes(data[10..18].try_into().unwrap());
+        let stream_info = StreamInfo {
+            min_block_size: u16::from_be_bytes([data[0], data[1]]),
+            max_block_size: u16::from_be_bytes([data[2], data[3]]),
+            min_frame_size: u32::from_be_bytes([0, data[4], data[5], data[6]]),
+            max_frame_size: u32::from_be_bytes([0, data[7], data[8], data[9]]),
+            sample_rate: (packed >> 44) as u32,
+            channels: ((packed >> 41) & 0x7) as u8 + 1,
+            bits_per_sample: ((packed >> 36) & 0x1F) as u8 + 1,
+            total_samples: packed & 0xF_FFFF_FFFF,
+            md5: data[18..].try_into().unwrap(),
+        };
+        if stream_info.bits_per_sample < 4 {
+            return Err(FLACParseError::InvalidStreamInfo);
+        }
+        Ok(stream_info)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(LENGTH);
+        bytes.extend(self.min_block_size.to_be_bytes());
+        bytes.extend(self.max_block_size.to_be_bytes());
+        bytes.extend(&self.min_frame_size.to_be_bytes()[1..]);
+        bytes.extend(&self.max_frame_size.to_be_bytes()[1..]);
+        let packed = (self.sample_rate as u64) << 44
+            | (self.channels as u64 - 1) << 41
+            | (self.bits_per_sample as u64 - 1) << 36
+            | self.total_samples;
+        bytes.extend(packed.to_be_bytes());
+        bytes.extend(self.md5);
+        bytes
+    }
+}
@@ -0,0 +1,392 @@
+use crate::flac::bitreader::BitReader;
+use crate::flac::bitwriter::BitWriter;
+use crate::flac::FLACParseError;
+
+/// Coefficients of the fixed predictors of orders zero to four.
+const FIXED_COEFFICIENTS: [&[i64]; 5] = [&[], &[1], &[2, -1], &[3, -3, 1], &[4, -6, 4, -1]];
+
+/// Largest partition order tried when coding a residual.
+const MAX_PARTITION_ORDER: u32 = 8;
+
+/// How the samples of a subframe are predicted from the ones before them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predictor {
+    Constant,
+    Verbatim,
+    /// One of the fixed polynomial predictors, by order.
+    Fixed(usize),
+    /// An LPC predictor with quantized coefficients, applied to the most recent sample first.
+    Linear {
+        precision: u32,
+        shift: u32,
+        coefficients: Vec<i64>,
+    },
+}
+
+impl Predictor {
+    fn order(&self) -> usize {
+        match self {
+            Predictor::Constant | Predictor::Verbatim => 0,
+            Predictor::Fixed(order) => *order,
+            Predictor::Linear { coefficients, .. } => coefficients.len(),
+        }
+    }
+
+    /// Returns the subframe type code.
+    fn kind(&self) -> u64 {
+        match self {
+            Predictor::Constant => 0,
+            Predictor::Verbatim => 1,
+            Predictor::Fixed(order) => 8 + *order as u64,
+            Predictor::Linear { coefficients, .. } => 31 + coefficients.len() as u64,
+        }
+    }
+
+    /// Predicts the next sample from those decoded so far.
+    fn predict(&self, history: &[i64]) -> i64 {
+        let (coefficients, shift) = match self {
+            Predictor::Fixed(order) => (FIXED_COEFFICIENTS[*order], 0),
+            Predictor::Linear {
+                coefficients,
+                shift,
+                ..
+            } => (coefficients.as_slice(), *shift),
+            _ => return 0,
+        };
+        let sum: i64 = coefficients
+            .iter()
+            .zip(history.iter().rev())
+            .map(|(coefficient, sample)| coefficient * sample)
+            .sum();
+        sum >> shift
+    }
+
+    /// Returns the residual left after prediction, or `None` if some of it falls outside the 32-bit range a decoder has to handle.
+    fn residual(&self, samples: &[i64]) -> Option<Vec<i64>> {
+        let order = self.order();
+        (order..samples.len())
+            .map(|i| {
+                let residual = samples[i] - self.predict(&samples[..i]);
+                i32::try_from(residual).ok().map(i64::from)
+            })
+            .collect()
+    }
+}
+
+/// Decodes a subframe of `block_size` samples of `bits` bits each, returning the samples and the predictor they were coded with.
+pub fn decode(
+    reader: &mut BitReader,
+    block_size: usize,
+    bits: u32,
+) -> Result<(Vec<i64>, Predictor), FLACParseError> {
+    let invalid = FLACParseError::InvalidSubframe;
+    if reader.read_bit()? {
+        return Err(invalid);
+    }
+    let kind = reader.read_bits(6)? as usize;
+    let wasted = match reader.read_bit()? {
+        true => reader.read_unary()? + 1,
+        false => 0,
+    };
+    if wasted >= bits {
+        return Err(invalid);
+    }
+    let bits = bits - wasted;
+
+    let mut samples = Vec::with_capacity(block_size);
+    let predictor = match kind {
+        0 => {
+            samples.resize(block_size, reader.read_signed(bits)?);
+            Predictor::Constant
+        }
+        1 => {
+            for _ in 0..block_size {
+                samples.push(reader.read_signed(bits)?);
+            }
+            Predictor::Verbatim
+        }
+        8..=12 | 32..=63 => {
+            let order = if kind < 32 { kind - 8 } else { kind - 31 };
+            if order > block_size {
+                return Err(invalid);
+            }
+            for _ in 0..order {
+                samples.push(reader.read_signed(bits)?);
+            }
+            let predictor = match kind {
+                8..=12 => Predictor::Fixed(order),
+                _ => {
+                    let precision = reader.read_bits(4)? as u32 + 1;
+                    let shift = reader.read_signed(5)?;
+                    if precision == 16 || shift < 0 {
+                        return Err(invalid);
+                    }
+                    let coefficients = (0..order)
+                        .map(|_| reader.read_signed(precision))
+                        .collect::<Result<Vec<i64>, FLACParseError>>()?;
+                    Predictor::Linear {
+                        precision,
+                        shift: shift as u32,
+                        coefficients,
+                    }
+                }
+            };
+            // Samples that do not fit in their bits could grow without bound through the predictor.
+            let limit = 1 << (bits - 1);
+            for residual in decode_residual(reader, block_size, order)? {
+                let sample = residual + predictor.predict(&samples);
+                if !(-limit..limit).contains(&sample) {
+                    return Err(invalid);
+                }
+                samples.push(sample);
+            }
+            predictor
+        }
+        _ => return Err(invalid),
+    };
+
+    if wasted > 0 {
+        for sample in &mut samples {
+            *sample <<= wasted;
+        }
+    }
+    Ok((samples, predictor))
+}
+
+fn decode_residual(
+    reader: &mut BitReader,
+    block_size: usize,
+    order: usize,
+) -> Result<Vec<i64>, FLACParseError> {
+    let invalid = FLACParseError::InvalidResidual;
+    let parameter_bits = match reader.read_bits(2)? {
+        0 => 4,
+        1 => 5,
+        _ => return Err(invalid),
+    };
+    let escape = (1 << parameter_bits) - 1;
+    let partition_order = reader.read_bits(4)? as u32;
+    let partition_size = block_size >> partition_order;
+    if partition_size << partition_order != block_size || partition_size < order {
+        return Err(invalid);
+    }
+
+    let mut residual = Vec::with_capacity(block_size - order);
+    for partition in 0..1 << partition_order {
+        let count = partition_size - if partition == 0 { order } else { 0 };
+        let parameter = reader.read_bits(parameter_bits)? as u32;
+        if parameter == escape {
+            let bits = reader.read_bits(5)? as u32;
+            for _ in 0..count {
+                residual.push(reader.read_signed(bits)?);
+            }
+        } else {
+            for _ in 0..count {
+                let quotient = reader.read_unary()? as u64;
+                let folded = quotient << parameter | reader.read_bits(parameter)?;
+                residual.push((folded >> 1) as i64 ^ -((folded & 1) as i64));
+            }
+        }
+    }
+    Ok(residual)
+}
+
+/// Codes a subframe of samples of `bits` bits each with whichever predictor tried gives the fewest bits.
+/// The fixed predictors are always tried, and so is the LPC predictor the subframe was read with, which keeps a re-encoded frame close to its original size.
+pub fn encode(writer: &mut BitWriter, samples: &[i64], bits: u32, original: &Predictor) {
+    if samples.iter().all(|sample| *sample == samples[0]) {
+        write_header(writer, &Predictor::Constant, 0);
+        writer.write_signed(samples[0], bits);
+        return;
+    }
+
+    // Low bits that are zero in every sample are left out and signalled as wasted bits.
+    let wasted = samples
+        .iter()
+        .fold(0, |bits, sample| bits | sample)
+        .trailing_zeros();
+    let samples: Vec<i64> = samples.iter().map(|sample| sample >> wasted).collect();
+    let bits = bits - wasted;
+
+    let mut candidates: Vec<Predictor> = (0..FIXED_COEFFICIENTS.len())
+        .map(Predictor::Fixed)
+        .collect();
+    if let Predictor::Linear { .. } = original {
+        candidates.push(original.clone());
+    }
+    let mut best = (samples.len() * bits as usize, Predictor::Verbatim, None);
+    for predictor in candidates {
+        let order = predictor.order();
+        if order >= samples.len() {
+            continue;
+        }
+        let Some(residual) = predictor.residual(&samples) else {
+            continue;
+        };
+        let coding = RiceCoding::choose(&residual, samples.len(), order);
+        let parameters = match &predictor {
+            Predictor::Linear { precision, .. } => 4 + 5 + order * *precision as usize,
+            _ => 0,
+        };
+        let cost = order * bits as usize + parameters + coding.bits;
+        if cost < best.0 {
+            best = (cost, predictor, Some((coding, residual)));
+        }
+    }
+
+    let (_, predictor, coding) = best;
+    write_header(writer, &predictor, wasted);
+    let order = predictor.order();
+    let warm_up = match predictor {
+        Predictor::Verbatim => samples.len(),
+        _ => order,
+    };
+    for sample in &samples[..warm_up] {
+        writer.write_signed(*sample, bits);
+    }
+    if let Predictor::Linear {
+        precision,
+        shift,
+        coefficients,
+    } = &predictor
+    {
+        writer.write_bits(*precision as u64 - 1, 4);
+        writer.write_bits(*shift as u64, 5);
+        for coefficient in coefficients {
+            writer.write_signed(*coefficient, *precision);
+        }
+    }
+    if let Some((coding, residual)) = coding {
+        coding.write(writer, &residual, samples.len(), order);
+    }
+}
+
+fn write_header(writer: &mut BitWriter, predictor: &Predictor, wasted: u32) {
+    writer.write_bits(predictor.kind(), 7);
+    match wasted {
+        0 => writer.write_bits(0, 1),
+        _ => {
+            writer.write_bits(1, 1);
+            writer.write_unary(wasted as u64 - 1);
+        }
+    }
+}
+
+/// Maps a signed residual to the unsigned value that is Rice coded: zero, -1, 1, -2, 2 and so on.
+fn fold(residual: i64) -> u64 {
+    ((residual << 1) ^ (residual >> 63)) as u64
+}
+
+/// A choice of partition order and Rice parameters for coding a residual.
+struct RiceCoding {
+    partition_order: u32,
+    parameters: Vec<u32>,
+    /// Bits the coded residual takes, including the method and partition order.
+    bits: usize,
+}
+
+impl RiceCoding {
+    fn choose(residual: &[i64], block_size: usize, order: usize) -> RiceCoding {
+        let folded: Vec<u64> = residual.iter().map(|residual| fold(*residual)).collect();
+        let mut best: Option<RiceCoding> = None;
+        for partition_order in 0..=MAX_PARTITION_ORDER {
+            let partition_size = block_size >> partition_order;
+            if partition_size << partition_order != block_size || partition_size <= order {
+                break;
+            }
+            let mut parameters = Vec::with_capacity(1 << partition_order);
+            let mut bits = 2 + 4;
+            let mut start = 0;
+            for partition in 0..1 << partition_order {
+                let count = partition_size - if partition == 0 { order } else { 0 };
+                let (parameter, cost) = RiceCoding::parameter(&folded[start..start + count]);
+                parameters.push(parameter);
+                bits += cost;
+                start += count;
+            }
+            bits += parameters.len() * RiceCoding::parameter_bits(&parameters) as usize;
+            if best.as_ref().is_none_or(|best| bits < best.bits) {
+                best = Some(RiceCoding {
+                    partition_order,
+                    parameters,
+                    bits,
+                });
+            }
+        }
+        best.expect("a block longer than the predictor order allows partition order zero")
+    }
+
+    /// Picks the Rice parameter that codes a partition in the fewest bits, trying those around the logarithm of its mean.
+    fn parameter(values: &[u64]) -> (u32, usize) {
+        if values.is_empty() {
+            return (0, 0);
+        }
+        let mean = values.iter().sum::<u64>() / values.len() as u64;
+        let estimate = 63 - (mean | 1).leading_zeros();
+        (estimate.saturating_sub(1)..=(estimate + 1).min(30))
+            .map(|parameter| {
+                let cost = values
+                    .iter()
+                    .map(|value| 1 + parameter as usize + (value >> parameter) as usize)
+                    .sum();
+                (parameter, cost)
+            })
+            .min_by_key(|(_, cost)| *cost)
+            .unwrap()
+    }
+
+    /// Parameters of 15 and up need the 5-bit parameter coding, since 15 is the escape code of the 4-bit one.
+    fn parameter_bits(parameters: &[u32]) -> u32 {
+        match parameters.iter().any(|parameter| *parameter >= 15) {
+            true => 5,
+            false => 4,
+        }
+    }
+
+    fn write(&self, writer: &mut BitWriter, residual: &[i64], block_size: usize, order: usize) {
+        let parameter_bits = RiceCoding::parameter_bits(&self.parameters);
+        writer.write_bits(parameter_bits as u64 - 4, 2);
+        writer.write_bits(self.partition_order as u64, 4);
+        let partition_size = block_size >> self.partition_order;
+        let mut start = 0;
+        for (partition, parameter) in self.parameters.iter().enumerate() {
+            let count = partition_size - if partition == 0 { order } else { 0 };
+            writer.write_bits(*parameter as u64, parameter_bits);
+            for residual in &residual[start..start + count] {
+                let folded = fold(*residual);
+                writer.write_unary(folded >> parameter);
+                writer.write_bits(folded, *parameter);
+            }
+            start += count;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        let lpc = Predictor::Linear {
+            precision: 12,
+            shift: 9,
+            coefficients: vec![900, -420, 30],
+        };
+        let ramp: Vec<i64> = (0..256).map(|i| (i * i % 977 - 400) * 4).collect();
+        let cases = [
+            (vec![-5; 64], 8, Predictor::Verbatim),
+            (ramp.clone(), 17, lpc.clone()),
+            (ramp, 16, Predictor::Verbatim),
+            (vec![0, 1, -2, 2_000_000_000, -2_000_000_000], 32, lpc),
+        ];
+        for (samples, bits, original) in cases {
+            let mut writer = BitWriter::default();
+            encode(&mut writer, &samples, bits, &original);
+            writer.align();
+            let mut reader = BitReader::new(&writer.data, 0);
+            let (decoded, _) = decode(&mut reader, samples.len(), bits).unwrap();
+            assert_eq!(decoded, samples);
+        }
+    }
+}
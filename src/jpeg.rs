@@ -0,0 +1,486 @@
+use crate::Error;
+
+use self::bitwriter::BitWriter;
+use self::scan::{HuffmanTables, Token};
+
+pub use self::f5::{capacity_f5, embed_f5, extract_f5};
+pub use self::frameheader::{Component, FrameHeader};
+pub use self::huffmantable::HuffmanTable;
+pub use self::jpegcarrier::{JPEGCarrier, JPEGCover};
+pub use self::jpegparseerror::JPEGParseError;
+pub use self::scan::{Block, Scan};
+pub use self::scanheader::{ScanComponent, ScanHeader};
+
+mod bitreader;
+mod bitwriter;
+mod f5;
+mod frameheader;
+mod huffmantable;
+mod jpegcarrier;
+mod jpegparseerror;
+mod scan;
+mod scanheader;
+
+const SOI: u8 = 0xD8;
+const EOI: u8 = 0xD9;
+const SOS: u8 = 0xDA;
+const DHT: u8 = 0xC4;
+const DRI: u8 = 0xDD;
+
+/// A segment of a JPEG file between the SOI and EOI markers.
+pub enum Segment {
+    /// A marker segment other than a scan, such as APPn, COM, DQT, DHT or SOF, kept as read.
+    Marker {
+        marker: u8,
+        data: Vec<u8>,
+    },
+    Scan(Box<Scan>),
+}
+
+/// A parsed JPEG image, holding the quantized DCT coefficients of every block.
+/// The coefficients are re-encoded without going back to pixels, so nothing but the changed coefficients differs in the written file.
+pub struct JPEG {
+    pub frame: FrameHeader,
+    pub segments: Vec<Segment>,
+    /// Blocks of each component, row by row and padded to whole MCUs.
+    coefficients: Vec<Vec<Block>>,
+    modified: bool,
+    /// The EOI marker and anything after it, written back unchanged.
+    pub trailing: Vec<u8>,
+}
+
+impl JPEG {
+    /// Parses a Huffman-coded baseline, extended sequential or progressive JPEG file and decodes its scans.
+    pub fn parse(data: &[u8]) -> Result<JPEG, Error> {
+        let error = |error, offset| Error::JPEG { error, offset };
+        if !data.starts_with(&[0xFF, SOI]) {
+            return Err(error(JPEGParseError::NotJPEG, 0));
+        }
+
+        let mut segments = Vec::new();
+        let mut frame: Option<FrameHeader> = None;
+        let mut coefficients = Vec::new();
+        let mut tables = HuffmanTables::default();
+        let mut restart_interval = 0;
+        let mut offset = 2;
+        while offset < data.len() {
+            // Any number of 0xFF fill bytes may precede a marker.
+            while data.get(offset + 1) == Some(&0xFF) && data[offset] == 0xFF {
+                offset += 1;
+            }
+            let marker = match data.get(offset..offset + 2) {
+                Some([0xFF, marker]) => *marker,
+                _ => return Err(error(JPEGParseError::TruncatedSegment, offset)),
+            };
+            if marker == EOI {
+                break;
+            }
+            if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+                segments.push(Segment::Marker {
+                    marker,
+                    data: Vec::new(),
+                });
+                offset += 2;
+                continue;
+            }
+
+            let length = data
+                .get(offset + 2..offset + 4)
+                .map(|length| u16::from_be_bytes([length[0], length[1]]) as usize)
+                .filter(|length| *length >= 2)
+                .ok_or(error(JPEGParseError::TruncatedSegment, offset))?;
+            let body = data
+                .get(offset + 4..offset + 2 + length)
+                .ok_or(error(JPEGParseError::TruncatedSegment, offset))?;
+            let segment_offset = offset;
+            offset += 2 + length;
+
+            match marker {
+                DHT => {
+                    let parsed =
+                        HuffmanTable::parse_segment(body).map_err(|e| error(e, segment_offset))?;
+                    for (class, id, table) in parsed {
+                        tables[class as usize][id as usize] = Some(table);
+                    }
+                }
+                DRI => {
+                    restart_interval = match body {
+                        [high, low] => u16::from_be_bytes([*high, *low]),
+                        _ => return Err(error(JPEGParseError::InvalidScan, segment_offset)),
+                    };
+                }
+                0xC0..=0xCF if marker != 0xC8 && marker != 0xCC => {
+                    if frame.is_some() {
+                        return Err(error(JPEGParseError::InvalidFrame, segment_offset));
+                    }
+                    let header =
+                        FrameHeader::parse(marker, body).map_err(|e| error(e, segment_offset))?;
+                    // Every block codes at least one bit of DC difference, so the rest of the file bounds how many blocks there can be.
+                    let blocks: usize = (0..header.components.len())
+                        .map(|component| {
+                            let (wide, high) = header.coded_blocks(component);
+                            wide * high
+                        })
+                        .sum();
+                    if blocks > 8 * (data.len() - offset) {
+                        return Err(error(JPEGParseError::TruncatedScan, segment_offset));
+                    }
+                    coefficients = (0..header.components.len())
+                        .map(|component| {
+                            let (wide, high) = header.blocks(component);
+                            vec![[0; 64]; wide * high]
+                        })
+                        .collect();
+                    frame = Some(header);
+                }
+                SOS => {
+                    let frame = frame
+                        .as_ref()
+                        .ok_or(error(JPEGParseError::MissingFrame, segment_offset))?;
+                    let header =
+                        ScanHeader::parse(body, frame).map_err(|e| error(e, segment_offset))?;
+                    let end = JPEG::scan_end(data, offset);
+                    let scan = Scan {
+                        header,
+                        header_data: body.to_vec(),
+                        data: data[offset..end].to_vec(),
+                        restart_interval,
+                        tables: tables.clone(),
+                    };
+                    scan.decode(frame, &mut coefficients)
+                        .map_err(|e| error(e, offset))?;
+                    segments.push(Segment::Scan(Box::new(scan)));
+                    offset = end;
+                    continue;
+                }
+                _ => {}
+            }
+            segments.push(Segment::Marker {
+                marker,
+                data: body.to_vec(),
+            });
+        }
+
+        Ok(JPEG {
+            frame: frame.ok_or(error(JPEGParseError::MissingFrame, 2))?,
+            segments,
+            coefficients,
+            modified: false,
+            trailing: data[offset.min(data.len())..].to_vec(),
+        })
+    }
+
+    /// Returns where the entropy-coded data starting at `start` ends: at the first marker other than a restart marker.
+    fn scan_end(data: &[u8], start: usize) -> usize {
+        let mut position = start;
+        while position + 1 < data.len() {
+            if data[position] == 0xFF && !matches!(data[position + 1], 0x00 | 0xD0..=0xD7) {
+                return position;
+            }
+            position += 1;
+        }
+        data.len()
+    }
+
+    /// Serializes the image.
+    /// Scans are re-encoded from the coefficients only if any has changed, with the Huffman tables they were read with where those still cover every symbol.
+    /// Otherwise an optimal table is defined just before the scan.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0xFF, SOI];
+        let mut defined = HuffmanTables::default();
+        for segment in &self.segments {
+            match segment {
+                Segment::Marker { marker, data } => {
+                    write_segment(&mut bytes, *marker, data);
+                    if *marker == DHT {
+                        for (class, id, table) in
+                            HuffmanTable::parse_segment(data).unwrap_or_default()
+                        {
+                            defined[class as usize][id as usize] = Some(table);
+                        }
+                    }
+                }
+                Segment::Scan(scan) if !self.modified => {
+                    write_segment(&mut bytes, SOS, &scan.header_data);
+                    bytes.extend(&scan.data);
+                }
+                Segment::Scan(scan) => self.encode_scan(scan, &mut defined, &mut bytes),
+            }
+        }
+        bytes.extend(&self.trailing);
+        bytes
+    }
+
+    /// Writes a scan re-encoded from the coefficients, preceded by a DHT segment for any table that has to change.
+    fn encode_scan(&self, scan: &Scan, defined: &mut HuffmanTables, bytes: &mut Vec<u8>) {
+        let mut frequencies = [[[0u32; 256]; 4]; 2];
+        scan.encode(&self.frame, &self.coefficients, &mut |token| {
+            if let Token::Symbol {
+                class,
+                table,
+                symbol,
+            } = token
+            {
+                frequencies[class as usize][table as usize][symbol as usize] += 1;
+            }
+        });
+
+        let mut tables = scan.tables.clone();
+        let mut redefined = Vec::new();
+        for (class, id) in scan.table_slots() {
+            let (c, i) = (class as usize, id as usize);
+            let counts = &frequencies[c][i];
+            let table = match &scan.tables[c][i] {
+                Some(table)
+                    if (0..=255).all(|s| counts[s as usize] == 0 || table.code(s).is_some()) =>
+                {
+                    table.clone()
+                }
+                _ => HuffmanTable::optimal(counts),
+            };
+            if defined[c][i].as_ref() != Some(&table) {
+                defined[c][i] = Some(table.clone());
+                redefined.push((class, id, table.clone()));
+            }
+            tables[c][i] = Some(table);
+        }
+        if !redefined.is_empty() {
+            let redefined: Vec<(u8, u8, &HuffmanTable)> = redefined
+                .iter()
+                .map(|(class, id, table)| (*class, *id, table))
+                .collect();
+            write_segment(bytes, DHT, &HuffmanTable::to_segment(&redefined));
+        }
+        write_segment(bytes, SOS, &scan.header_data);
+
+        let mut writer = BitWriter::new();
+        let mut restarts = 0;
+        scan.encode(&self.frame, &self.coefficients, &mut |token| match token {
+            Token::Symbol {
+                class,
+                table,
+                symbol,
+            } => {
+                let (code, length) = tables[class as usize][table as usize]
+                    .as_ref()
+                    .and_then(|table| table.code(symbol))
+                    .expect("tables are chosen to cover every symbol of the scan");
+                writer.write_bits(code as u32, length);
+            }
+            Token::Bits { value, length } => writer.write_bits(value, length),
+            Token::Restart => {
+                writer.restart(restarts);
+                restarts = (restarts + 1) % 8;
+            }
+        });
+        writer.flush();
+        bytes.extend(writer.data);
+    }
+
+    /// Returns a coefficient of a block, with `k` in zigzag order.
+    pub fn coefficient(&self, component: usize, block: usize, k: usize) -> i16 {
+        self.coefficients[component][block][k]
+    }
+
+    pub fn set_coefficient(&mut self, component: usize, block: usize, k: usize, value: i16) {
+        self.coefficients[component][block][k] = value;
+        self.modified = true;
+    }
+
+    /// Returns the component and index of every block that covers the image.
+    /// Blocks that only pad the last MCUs are left out, since progressive scans of a single component do not code them.
+    pub fn coded_blocks(&self) -> Vec<(usize, usize)> {
+        (0..self.frame.components.len())
+            .flat_map(|component| {
+                let (wide, high) = self.frame.coded_blocks(component);
+                let stride = self.frame.blocks(component).0;
+                (0..high).flat_map(move |y| (0..wide).map(move |x| (component, y * stride + x)))
+            })
+            .collect()
+    }
+}
+
+fn write_segment(bytes: &mut Vec<u8>, marker: u8, data: &[u8]) {
+    bytes.extend([0xFF, marker]);
+    if marker != 0x01 && !(0xD0..=0xD7).contains(&marker) {
+        bytes.extend((data.len() as u16 + 2).to_be_bytes());
+        bytes.extend(data);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// Returns the made-up coefficient that [`jpeg_bytes`] stores in a block, mostly zeros with magnitudes falling off towards the high frequencies.
+    pub(crate) fn test_coefficient(component: usize, block: usize, k: usize) -> i16 {
+        let hash = (component * 7919 + block * 104_729 + k * 1_299_709) as u32;
+        let hash = hash.wrapping_mul(2_654_435_761) >> 8;
+        match k {
+            0 => (hash % 601) as i16 - 300,
+            _ if block % 5 == 3 => 0,
+            _ if hash.is_multiple_of(2 + k as u32 / 4) => {
+                let range = 96 / (k as u32 / 8 + 1);
+                let value = (hash >> 8) % range + 1;
+                if hash & 0x80 == 0 {
+                    value as i16
+                } else {
+                    -(value as i16)
+                }
+            }
+            _ => 0,
+        }
+    }
+
+    /// Builds a 77 by 45 pixel JPEG with 2x2 subsampled luma, two chroma components and a restart interval of two MCUs.
+    /// The progressive form splits the coefficients over successive approximation and spectral selection scans.
+    /// Its Huffman tables are the optimal ones for its coefficients, defined just before each scan.
+    pub(crate) fn jpeg_bytes(progressive: bool) -> Vec<u8> {
+        let marker = if progressive { 0xC2 } else { 0xC0 };
+        let sof = [8, 0, 45, 0, 77, 3, 1, 0x22, 0, 2, 0x11, 1, 3, 0x11, 1];
+        let frame = FrameHeader::parse(marker, &sof).unwrap();
+        let mut quantization = vec![0];
+        quantization.extend([4; 64]);
+        quantization.push(1);
+        quantization.extend([8; 64]);
+
+        let scans: Vec<Vec<u8>> = match progressive {
+            false => vec![vec![3, 1, 0x00, 2, 0x11, 3, 0x11, 0, 63, 0]],
+            true => {
+                let mut scans = vec![
+                    vec![3, 1, 0x00, 2, 0x10, 3, 0x10, 0, 0, 0x01],
+                    vec![3, 1, 0x00, 2, 0x00, 3, 0x00, 0, 0, 0x10],
+                ];
+                for (id, table) in [(1, 0x00), (2, 0x01), (3, 0x01)] {
+                    scans.push(vec![1, id, table, 1, 5, 0x01]);
+                    scans.push(vec![1, id, table, 6, 63, 0x01]);
+                    scans.push(vec![1, id, table, 1, 63, 0x10]);
+                }
+                scans
+            }
+        };
+
+        let mut segments = vec![
+            Segment::Marker {
+                marker: 0xDB,
+                data: quantization,
+            },
+            Segment::Marker {
+                marker,
+                data: sof.to_vec(),
+            },
+            Segment::Marker {
+                marker: DRI,
+                data: vec![0, 2],
+            },
+        ];
+        for header_data in scans {
+            segments.push(Segment::Scan(Box::new(Scan {
+                header: ScanHeader::parse(&header_data, &frame).unwrap(),
+                header_data,
+                data: Vec::new(),
+                restart_interval: 2,
+                tables: HuffmanTables::default(),
+            })));
+        }
+
+        let mut jpeg = JPEG {
+            coefficients: (0..3)
+                .map(|component| {
+                    vec![[0; 64]; frame.blocks(component).0 * frame.blocks(component).1]
+                })
+                .collect(),
+            frame,
+            segments,
+            modified: true,
+            trailing: vec![0xFF, EOI],
+        };
+        for (component, block) in jpeg.coded_blocks() {
+            for k in 0..64 {
+                jpeg.coefficients[component][block][k] = test_coefficient(component, block, k);
+            }
+        }
+        jpeg.to_bytes()
+    }
+
+    #[test]
+    fn re_encodes_coefficients_byte_for_byte() {
+        for progressive in [false, true] {
+            let bytes = jpeg_bytes(progressive);
+            let mut jpeg = JPEG::parse(&bytes).unwrap();
+            assert_eq!(jpeg.frame.progressive(), progressive);
+            assert_eq!(jpeg.coded_blocks().len(), 10 * 6 + 2 * 5 * 3);
+            for (component, block) in jpeg.coded_blocks() {
+                for k in 0..64 {
+                    assert_eq!(
+                        jpeg.coefficient(component, block, k),
+                        test_coefficient(component, block, k)
+                    );
+                }
+            }
+            assert_eq!(jpeg.to_bytes(), bytes);
+
+            let value = jpeg.coefficient(1, 4, 0);
+            jpeg.set_coefficient(1, 4, 0, value);
+            assert_eq!(jpeg.to_bytes(), bytes);
+        }
+    }
+
+    #[test]
+    fn re_encodes_libjpeg_files_byte_for_byte() {
+        // 32 by 24 pixel images written by libjpeg-turbo 2.1.5 at quality 75. The progressive one uses the library's default scan script,
+        // whose successive approximation scans group end-of-band runs with correction bits.
+        for bytes in [
+            &include_bytes!("jpeg/testdata/baseline.jpg")[..],
+            &include_bytes!("jpeg/testdata/progressive.jpg")[..],
+        ] {
+            let mut jpeg = JPEG::parse(bytes).unwrap();
+            let value = jpeg.coefficient(0, 0, 0);
+            jpeg.set_coefficient(0, 0, 0, value);
+            assert_eq!(jpeg.to_bytes(), bytes);
+        }
+    }
+
+    #[test]
+    fn rejects_frame_larger_than_its_data() {
+        let mut bytes = jpeg_bytes(false);
+        let sof = bytes
+            .windows(2)
+            .position(|marker| marker == [0xFF, 0xC0])
+            .unwrap();
+        bytes[sof + 5..sof + 9].copy_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF]);
+        assert!(matches!(
+            JPEG::parse(&bytes),
+            Err(Error::JPEG {
+                error: JPEGParseError::TruncatedScan,
+                offset,
+            }) if offset == sof
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_scan() {
+        let bytes = jpeg_bytes(false);
+        assert!(matches!(
+            JPEG::parse(&bytes[..bytes.len() - 40]),
+            Err(Error::JPEG {
+                error: JPEGParseError::TruncatedScan,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn writes_changed_coefficients() {
+        for progressive in [false, true] {
+            let mut jpeg = JPEG::parse(&jpeg_bytes(progressive)).unwrap();
+            jpeg.set_coefficient(0, 7, 0, 1000);
+            jpeg.set_coefficient(2, 5, 63, -511);
+            jpeg.set_coefficient(0, 8, 12, 0);
+
+            let parsed = JPEG::parse(&jpeg.to_bytes()).unwrap();
+            assert_eq!(parsed.coefficients, jpeg.coefficients);
+            assert_eq!(parsed.coefficient(2, 5, 63), -511);
+        }
+    }
+}
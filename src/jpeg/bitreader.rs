@@ -0,0 +1,87 @@
+use crate::jpeg::JPEGParseError;
+
+/// Reads entropy-coded data bit by bit, most significant bit first, removing the zero bytes stuffed after each 0xFF.
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    byte: u8,
+    bits: u8,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader {
+            data,
+            position: 0,
+            byte: 0,
+            bits: 0,
+        }
+    }
+
+    pub fn read_bit(&mut self) -> Result<bool, JPEGParseError> {
+        if self.bits == 0 {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or(JPEGParseError::TruncatedScan)?;
+            self.position += 1;
+            if byte == 0xFF {
+                // Anything other than a stuffed zero is a marker, which ends the data of this interval.
+                if self.data.get(self.position) != Some(&0) {
+                    return Err(JPEGParseError::TruncatedScan);
+                }
+                self.position += 1;
+            }
+            self.byte = byte;
+            self.bits = 8;
+        }
+        self.bits -= 1;
+        Ok((self.byte >> self.bits) & 1 == 1)
+    }
+
+    /// Reads an unsigned value of up to 16 bits.
+    pub fn read_bits(&mut self, length: u8) -> Result<u16, JPEGParseError> {
+        (0..length).try_fold(0, |value, _| Ok((value << 1) | self.read_bit()? as u16))
+    }
+
+    /// Discards the rest of the current byte and steps over the restart marker that must follow.
+    pub fn restart(&mut self) -> Result<(), JPEGParseError> {
+        self.bits = 0;
+        while self.data.get(self.position) == Some(&0xFF)
+            && self.data.get(self.position + 1) == Some(&0xFF)
+        {
+            self.position += 1;
+        }
+        match self.data.get(self.position..self.position + 2) {
+            Some([0xFF, 0xD0..=0xD7]) => {
+                self.position += 2;
+                Ok(())
+            }
+            _ => Err(JPEGParseError::TruncatedScan),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::jpeg::bitwriter::BitWriter;
+
+    #[test]
+    fn round_trips_stuffed_bytes_and_restart_markers() {
+        let mut writer = BitWriter::new();
+        writer.write_bits(0xFF, 8);
+        writer.write_bits(0b101, 3);
+        writer.restart(0);
+        writer.write_bits(0x1234, 16);
+        writer.flush();
+        assert_eq!(writer.data, [0xFF, 0x00, 0xBF, 0xFF, 0xD0, 0x12, 0x34]);
+
+        let mut reader = BitReader::new(&writer.data);
+        assert_eq!(reader.read_bits(8), Ok(0xFF));
+        assert_eq!(reader.read_bits(3), Ok(0b101));
+        assert_eq!(reader.restart(), Ok(()));
+        assert_eq!(reader.read_bits(16), Ok(0x1234));
+        assert_eq!(reader.read_bit(), Err(JPEGParseError::TruncatedScan));
+    }
+}
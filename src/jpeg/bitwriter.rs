@@ -0,0 +1,41 @@
+/// Writes entropy-coded data, stuffing a zero byte after each 0xFF and padding incomplete bytes with one bits.
+#[derive(Default)]
+pub struct BitWriter {
+    pub data: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    pub fn new() -> BitWriter {
+        BitWriter::default()
+    }
+
+    /// Writes the low `length` bits of the value, up to 16.
+    pub fn write_bits(&mut self, value: u32, length: u8) {
+        self.buffer = (self.buffer << length) | (value & ((1 << length) - 1));
+        self.bits += length;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            let byte = (self.buffer >> self.bits) as u8;
+            self.data.push(byte);
+            if byte == 0xFF {
+                self.data.push(0);
+            }
+        }
+        self.buffer &= (1 << self.bits) - 1;
+    }
+
+    /// Pads the last byte with one bits.
+    pub fn flush(&mut self) {
+        if self.bits > 0 {
+            self.write_bits(0x7F, 8 - self.bits);
+        }
+    }
+
+    /// Ends a restart interval with the given restart marker number.
+    pub fn restart(&mut self, number: u8) {
+        self.flush();
+        self.data.extend([0xFF, 0xD0 + number % 8]);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::jpeg::JPEG;
+use crate::stego::{
+    bits_to_bytes, bytes_to_bits, decode_header, encode_header, Capacity, Coding, EmbedOptions,
+    Extraction, Fec, HammingCode, Layout, Permutation, StegoError, StegoKey, HEADER_BITS, MAX_K,
+};
+
+/// Share of the coefficients equal to ±1 expected to be lost to shrinkage, from Westfeld's analysis of F5.
+const SHRINKAGE: f64 = 0.51;
+
+/// AC coefficients of a block.
+const AC_COEFFICIENTS: usize = 63;
+
+/// A coefficient, addressed by component, block and zigzag index.
+pub(crate) type Slot = (usize, usize, usize);
+
+/// Walks the AC coefficients of the image in the keyed order, stopping at those that are currently nonzero.
+struct CoefficientWalk {
+    blocks: Vec<(usize, usize)>,
+    permutation: Permutation,
+    next: usize,
+}
+
+impl CoefficientWalk {
+    fn new(jpeg: &JPEG, key: &StegoKey) -> CoefficientWalk {
+        let blocks = jpeg.coded_blocks();
+        let permutation = Permutation::new(key, blocks.len() * AC_COEFFICIENTS);
+        CoefficientWalk {
+            blocks,
+            permutation,
+            next: 0,
+        }
+    }
+
+    fn next(&mut self, jpeg: &JPEG) -> Option<Slot> {
+        while self.next < self.permutation.len() {
+            let index = self.permutation.position(self.next);
+            self.next += 1;
+            let (component, block) = self.blocks[index / AC_COEFFICIENTS];
+            let slot = (component, block, index % AC_COEFFICIENTS + 1);
+            if value(jpeg, slot) != 0 {
+                return Some(slot);
+            }
+        }
+        None
+    }
+}
+
+fn value(jpeg: &JPEG, (component, block, k): Slot) -> i16 {
+    jpeg.coefficient(component, block, k)
+}
+
+/// The bit a coefficient carries: its lowest bit for positive values and the inverse for negative ones, so that lowering the magnitude always flips it.
+pub(crate) fn carried_bit(value: i16) -> bool {
+    (value & 1 == 1) != (value < 0)
+}
+
+/// Lowers the magnitude of a coefficient by one, returning whether it is still nonzero.
+pub(crate) fn decrement(jpeg: &mut JPEG, slot: Slot) -> bool {
+    let (component, block, k) = slot;
+    let changed = value(jpeg, slot) - value(jpeg, slot).signum();
+    jpeg.set_coefficient(component, block, k, changed);
+    changed != 0
+}
+
+/// Counts the nonzero AC coefficients of the image, and those of them equal to ±1.
+fn count_coefficients(jpeg: &JPEG) -> (usize, usize) {
+    let (mut nonzero, mut ones) = (0, 0);
+    for (component, block) in jpeg.coded_blocks() {
+        for k in 1..=AC_COEFFICIENTS {
+            match value(jpeg, (component, block, k)) {
+                0 => {}
+                1 | -1 => {
+                    nonzero += 1;
+                    ones += 1;
+                }
+                _ => nonzero += 1,
+            }
+        }
+    }
+    (nonzero, ones)
+}
+
+/// Estimates how many nonzero AC coefficients are left to carry bits once shrinkage has taken its share.
+fn usable_coefficients(jpeg: &JPEG) -> usize {
+    let (nonzero, ones) = count_coefficients(jpeg);
+    nonzero - (ones as f64 * SHRINKAGE) as usize
+}
+
+/// Embeds sealed data in the image with F5: each nonzero AC coefficient, taken in keyed order, carries one bit, which is changed by lowering the coefficient's magnitude.
+/// Bits are matrix embedded with the largest Hamming code that fits, and a coefficient that shrinks to zero is dropped and its group embedded again, since the extractor skips zeros.
+/// The layout and coding options do not apply; the FEC overhead does.
+pub fn embed_f5(
+    jpeg: &mut JPEG,
+    key: &StegoKey,
+    data: &[u8],
+    options: EmbedOptions,
+) -> Result<(), StegoError> {
+    let fec = Fec::new(options.fec_overhead);
+    let message = bytes_to_bits(&fec.encode(data));
+    let usable = usable_coefficients(jpeg);
+    let too_large = StegoError::PayloadTooLarge {
+        required: HEADER_BITS + message.len(),
+        available: usable,
+    };
+    let code = HammingCode::for_capacity(message.len(), usable.saturating_sub(HEADER_BITS))
+        .ok_or(too_large.clone())?;
+
+    // The header is the one scattered payloads use, copies and all, with the Hamming code parameter in the coding field.
+    let mut walk = CoefficientWalk::new(jpeg, key);
+    for bit in encode_header(data.len(), code.k as u8, fec.overhead) {
+        loop {
+            let slot = walk.next(jpeg).ok_or(too_large.clone())?;
+            if carried_bit(value(jpeg, slot)) == bit || decrement(jpeg, slot) {
+                break;
+            }
+        }
+    }
+
+    for chunk in message.chunks(code.k) {
+        let mut group = (0..code.block_length())
+            .map(|_| walk.next(jpeg))
+            .collect::<Option<Vec<Slot>>>()
+            .ok_or(too_large.clone())?;
+        loop {
+            let bits: Vec<bool> = group
+                .iter()
+                .map(|slot| carried_bit(value(jpeg, *slot)))
+                .collect();
+            let Some(index) = code.change(&bits, chunk) else {
+                break;
+            };
+            if decrement(jpeg, group[index]) {
+                break;
+            }
+            group.remove(index);
+            group.push(walk.next(jpeg).ok_or(too_large.clone())?);
+        }
+    }
+    Ok(())
+}
+
+/// Reads back data written with [`embed_f5`].
+pub fn extract_f5(jpeg: &JPEG, key: &StegoKey) -> Result<Extraction, StegoError> {
+    let mut walk = CoefficientWalk::new(jpeg, key);
+    let mut read = |count: usize| -> Result<Vec<bool>, StegoError> {
+        (0..count)
+            .map(|_| walk.next(jpeg).map(|slot| carried_bit(value(jpeg, slot))))
+            .collect::<Option<Vec<bool>>>()
+            .ok_or(StegoError::NoPayload)
+    };
+
+    let (length, k, fec_overhead) = decode_header(&read(HEADER_BITS)?);
+    let k = k as usize;
+    let fec = Fec::new(fec_overhead);
+    if !(1..=MAX_K).contains(&k) {
+        return Err(StegoError::NoPayload);
+    }
+    let code = HammingCode::new(k);
+    let message_bits = fec
+        .encoded_length(length)
+        .checked_mul(8)
+        .filter(|bits| code.cover_bits(*bits) <= count_coefficients(jpeg).0)
+        .ok_or(StegoError::NoPayload)?;
+
+    let mut message = Vec::with_capacity(message_bits);
+    for _ in 0..message_bits.div_ceil(k) {
+        message.extend(code.extract(&read(code.block_length())?));
+    }
+    message.truncate(message_bits);
+
+    let (payload, corrected_errors) = fec
+        .decode(&bits_to_bytes(&message), length)
+        .ok_or(StegoError::Uncorrectable)?;
+    Ok(Extraction {
+        payload,
+        corrected_errors,
+    })
+}
+
+/// Estimates how much F5 can embed in the image.
+pub fn capacity_f5(jpeg: &JPEG, options: EmbedOptions) -> Capacity {
+    let options = EmbedOptions {
+        layout: Layout::Scattered,
+        coding: Coding::Hamming,
+        ..options
+    };
+    Capacity::estimate(usable_coefficients(jpeg), options)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::jpeg::test::jpeg_bytes;
+
+    #[test]
+    fn embeds_by_lowering_magnitudes() {
+        let key = StegoKey::derive("hunter2");
+        let original = JPEG::parse(&jpeg_bytes(true)).unwrap();
+        let mut jpeg = JPEG::parse(&jpeg_bytes(true)).unwrap();
+        embed_f5(&mut jpeg, &key, b"secret", EmbedOptions::default()).unwrap();
+
+        let stego = JPEG::parse(&jpeg.to_bytes()).unwrap();
+        assert_eq!(extract_f5(&stego, &key).unwrap().payload, b"secret");
+        let mut changed = 0;
+        for (component, block) in original.coded_blocks() {
+            for k in 0..64 {
+                let before = original.coefficient(component, block, k);
+                let after = stego.coefficient(component, block, k);
+                if before != after {
+                    assert!(k > 0 && before.abs() - after.abs() == 1 && before * after >= 0);
+                    changed += 1;
+                }
+            }
+        }
+        assert!(changed > 0);
+        assert!(extract_f5(&original, &key).is_err());
+    }
+
+    #[test]
+    fn reads_header_through_one_damaged_copy() {
+        let mut copies = encode_header(1234, 5, 20);
+        assert_eq!(copies.len(), HEADER_BITS);
+        for bit in &mut copies[..HEADER_BITS / 3] {
+            *bit = !*bit;
+        }
+        assert_eq!(decode_header(&copies), (1234, 5, 20));
+    }
+}
@@ -0,0 +1,134 @@
+use crate::jpeg::JPEGParseError;
+
+/// A component of the frame, such as the luminance or one of the chrominance channels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Component {
+    pub id: u8,
+    /// Horizontal sampling factor, in blocks per MCU.
+    pub horizontal: u8,
+    /// Vertical sampling factor, in blocks per MCU.
+    pub vertical: u8,
+    pub quantization_table: u8,
+}
+
+/// The contents of the SOF segment, describing the image size and its components.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameHeader {
+    /// The SOF marker, which identifies the coding process.
+    pub marker: u8,
+    pub precision: u8,
+    pub height: u16,
+    pub width: u16,
+    pub components: Vec<Component>,
+}
+
+impl FrameHeader {
+    /// Parses the body of an SOF segment. Only Huffman-coded baseline (SOF0), extended sequential (SOF1) and progressive (SOF2) frames are supported.
+    pub fn parse(marker: u8, data: &[u8]) -> Result<FrameHeader, JPEGParseError> {
+        if !matches!(marker, 0xC0..=0xC2) {
+            return Err(JPEGParseError::UnsupportedProcess { marker });
+        }
+        let count = *data.get(5).ok_or(JPEGParseError::InvalidFrame)? as usize;
+        if count == 0 || data.len() != 6 + count * 3 {
+            return Err(JPEGParseError::InvalidFrame);
+        }
+
+        let components: Vec<Component> = data[6..]
+            .chunks(3)
+            .map(|component| Component {
+                id: component[0],
+                horizontal: component[1] >> 4,
+                vertical: component[1] & 0x0F,
+                quantization_table: component[2],
+            })
+            .collect();
+        let frame = FrameHeader {
+            marker,
+            precision: data[0],
+            height: u16::from_be_bytes([data[1], data[2]]),
+            width: u16::from_be_bytes([data[3], data[4]]),
+            components,
+        };
+
+        // A height of zero defers the height to a DNL marker, which is not supported.
+        let valid_sampling = |factor: u8| (1..=4).contains(&factor);
+        if frame.height == 0
+            || frame.width == 0
+            || !frame
+                .components
+                .iter()
+                .all(|c| valid_sampling(c.horizontal) && valid_sampling(c.vertical))
+        {
+            return Err(JPEGParseError::InvalidFrame);
+        }
+        Ok(frame)
+    }
+
+    pub fn progressive(&self) -> bool {
+        self.marker == 0xC2
+    }
+
+    fn max_horizontal(&self) -> usize {
+        self.components
+            .iter()
+            .map(|c| c.horizontal as usize)
+            .max()
+            .unwrap_or(1)
+    }
+
+    fn max_vertical(&self) -> usize {
+        self.components
+            .iter()
+            .map(|c| c.vertical as usize)
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Returns the number of MCUs across and down the image in an interleaved scan.
+    pub fn mcus(&self) -> (usize, usize) {
+        (
+            (self.width as usize).div_ceil(8 * self.max_horizontal()),
+            (self.height as usize).div_ceil(8 * self.max_vertical()),
+        )
+    }
+
+    /// Returns the number of blocks across and down a component, padded to whole MCUs.
+    pub fn blocks(&self, component: usize) -> (usize, usize) {
+        let (mcus_wide, mcus_high) = self.mcus();
+        let component = &self.components[component];
+        (
+            mcus_wide * component.horizontal as usize,
+            mcus_high * component.vertical as usize,
+        )
+    }
+
+    /// Returns the number of blocks across and down a component that cover the image, which are the blocks coded in a scan of that component alone.
+    pub fn coded_blocks(&self, component: usize) -> (usize, usize) {
+        let component = &self.components[component];
+        let width =
+            (self.width as usize * component.horizontal as usize).div_ceil(self.max_horizontal());
+        let height =
+            (self.height as usize * component.vertical as usize).div_ceil(self.max_vertical());
+        (width.div_ceil(8), height.div_ceil(8))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn counts_blocks_of_subsampled_components() {
+        let data = [8, 0, 17, 0, 33, 3, 1, 0x22, 0, 2, 0x11, 1, 3, 0x11, 1];
+        let frame = FrameHeader::parse(0xC0, &data).unwrap();
+        assert_eq!(frame.mcus(), (3, 2));
+        assert_eq!(frame.blocks(0), (6, 4));
+        assert_eq!(frame.coded_blocks(0), (5, 3));
+        assert_eq!(frame.blocks(1), (3, 2));
+        assert_eq!(frame.coded_blocks(1), (3, 2));
+        assert_eq!(
+            FrameHeader::parse(0xC9, &data),
+            Err(JPEGParseError::UnsupportedProcess { marker: 0xC9 })
+        );
+    }
+}
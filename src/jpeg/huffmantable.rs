@@ -0,0 +1,219 @@
+use crate::jpeg::bitreader::BitReader;
+use crate::jpeg::JPEGParseError;
+
+/// Longest code length used while building an optimal table, before lengths are limited to 16 bits.
+const MAX_BUILD_LENGTH: usize = 32;
+
+/// A Huffman table as defined by a DHT segment: the number of codes of each length and the symbols they stand for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HuffmanTable {
+    /// Number of codes of each length from 1 to 16 bits.
+    pub counts: [u8; 16],
+    /// Symbols in order of increasing code length.
+    pub symbols: Vec<u8>,
+    /// Largest code of each length, or -1 if there are none.
+    max_codes: [i32; 17],
+    /// Difference between the index in `symbols` and the code, for codes of each length.
+    offsets: [i32; 17],
+    /// Code and code length of each symbol. A length of zero means the symbol has no code.
+    codes: Vec<(u16, u8)>,
+}
+
+impl HuffmanTable {
+    /// Builds the canonical codes for the table.
+    pub fn new(counts: [u8; 16], symbols: Vec<u8>) -> Result<HuffmanTable, JPEGParseError> {
+        let total: usize = counts.iter().map(|&count| count as usize).sum();
+        if total != symbols.len() || total > 256 {
+            return Err(JPEGParseError::InvalidHuffmanTable);
+        }
+
+        let mut max_codes = [-1; 17];
+        let mut offsets = [0; 17];
+        let mut codes = vec![(0, 0); 256];
+        let (mut code, mut index) = (0i32, 0usize);
+        for length in 1..=16 {
+            let count = counts[length - 1] as usize;
+            if count > 0 {
+                offsets[length] = index as i32 - code;
+                for symbol in &symbols[index..index + count] {
+                    codes[*symbol as usize] = (code as u16, length as u8);
+                    code += 1;
+                }
+                index += count;
+                max_codes[length] = code - 1;
+                if code > 1 << length {
+                    return Err(JPEGParseError::InvalidHuffmanTable);
+                }
+            }
+            code <<= 1;
+        }
+
+        Ok(HuffmanTable {
+            counts,
+            symbols,
+            max_codes,
+            offsets,
+            codes,
+        })
+    }
+
+    /// Parses the body of a DHT segment into its tables, each with its class (0 for DC, 1 for AC) and identifier.
+    pub fn parse_segment(data: &[u8]) -> Result<Vec<(u8, u8, HuffmanTable)>, JPEGParseError> {
+        let mut tables = Vec::new();
+        let mut rest = data;
+        while !rest.is_empty() {
+            let header = rest.get(..17).ok_or(JPEGParseError::InvalidHuffmanTable)?;
+            let (class, id) = (header[0] >> 4, header[0] & 0x0F);
+            if class > 1 || id > 3 {
+                return Err(JPEGParseError::InvalidHuffmanTable);
+            }
+            let counts: [u8; 16] = header[1..17].try_into().expect("header has 17 bytes");
+            let total: usize = counts.iter().map(|&count| count as usize).sum();
+            let symbols = rest
+                .get(17..17 + total)
+                .ok_or(JPEGParseError::InvalidHuffmanTable)?
+                .to_vec();
+            tables.push((class, id, HuffmanTable::new(counts, symbols)?));
+            rest = &rest[17 + total..];
+        }
+        Ok(tables)
+    }
+
+    /// Serializes tables into the body of a DHT segment.
+    pub fn to_segment(tables: &[(u8, u8, &HuffmanTable)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for (class, id, table) in tables {
+            data.push((class << 4) | id);
+            data.extend(table.counts);
+            data.extend(&table.symbols);
+        }
+        data
+    }
+
+    /// Reads one code and returns its symbol.
+    pub fn decode(&self, reader: &mut BitReader) -> Result<u8, JPEGParseError> {
+        let mut code = 0;
+        for length in 1..=16 {
+            code = (code << 1) | reader.read_bit()? as i32;
+            if code <= self.max_codes[length] {
+                return Ok(self.symbols[(code + self.offsets[length]) as usize]);
+            }
+        }
+        Err(JPEGParseError::InvalidCode)
+    }
+
+    /// Returns the code and code length of a symbol, if the table has one for it.
+    pub fn code(&self, symbol: u8) -> Option<(u16, u8)> {
+        Some(self.codes[symbol as usize]).filter(|(_, length)| *length > 0)
+    }
+
+    /// Builds the optimal table for the given symbol frequencies, limited to 16-bit codes, following Annex K.2 of the JPEG standard.
+    /// The construction matches the one used by libjpeg, so tables it optimized come out the same.
+    pub fn optimal(frequencies: &[u32; 256]) -> HuffmanTable {
+        let mut frequencies: Vec<u64> = frequencies.iter().map(|&f| f as u64).collect();
+        // A reserved symbol ensures that no code consists of all one bits.
+        frequencies.push(1);
+        let mut code_sizes = [0usize; 257];
+        let mut others = [None; 257];
+
+        loop {
+            let smallest = |excluded: Option<usize>, frequencies: &[u64]| {
+                (0..257)
+                    .filter(|&i| frequencies[i] > 0 && Some(i) != excluded)
+                    .fold(None, |best: Option<usize>, i| match best {
+                        Some(best) if frequencies[best] < frequencies[i] => Some(best),
+                        _ => Some(i),
+                    })
+            };
+            let Some(mut first) = smallest(None, &frequencies) else {
+                break;
+            };
+            let Some(mut second) = smallest(Some(first), &frequencies) else {
+                break;
+            };
+
+            frequencies[first] += frequencies[second];
+            frequencies[second] = 0;
+            code_sizes[first] += 1;
+            while let Some(next) = others[first] {
+                first = next;
+                code_sizes[first] += 1;
+            }
+            others[first] = Some(second);
+            code_sizes[second] += 1;
+            while let Some(next) = others[second] {
+                second = next;
+                code_sizes[second] += 1;
+            }
+        }
+
+        let mut bits = [0u32; MAX_BUILD_LENGTH + 1];
+        for &size in code_sizes.iter().filter(|&&size| size > 0) {
+            bits[size] += 1;
+        }
+        // Shorten codes longer than 16 bits by moving pairs of them up the tree.
+        for length in (17..=MAX_BUILD_LENGTH).rev() {
+            while bits[length] > 0 {
+                let mut shorter = length - 2;
+                while bits[shorter] == 0 {
+                    shorter -= 1;
+                }
+                bits[length] -= 2;
+                bits[length - 1] += 1;
+                bits[shorter + 1] += 2;
+                bits[shorter] -= 1;
+            }
+        }
+        // Remove the reserved symbol, which has the longest code.
+        let longest = (1..=16).rev().find(|&length| bits[length] > 0).unwrap_or(0);
+        if longest > 0 {
+            bits[longest] -= 1;
+        }
+
+        let mut counts = [0; 16];
+        for length in 1..=16 {
+            counts[length - 1] = bits[length] as u8;
+        }
+        let symbols = (1..=MAX_BUILD_LENGTH)
+            .flat_map(|length| (0..256).filter(move |&symbol| code_sizes[symbol] == length))
+            .map(|symbol| symbol as u8)
+            .collect();
+        HuffmanTable::new(counts, symbols).expect("optimal tables are complete")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::jpeg::bitwriter::BitWriter;
+
+    #[test]
+    fn decodes_what_optimal_table_encodes() {
+        let mut frequencies = [0; 256];
+        for (symbol, frequency) in [(0x00, 50), (0x01, 20), (0x11, 5), (0xF0, 1), (0x23, 1)] {
+            frequencies[symbol] = frequency;
+        }
+        let table = HuffmanTable::optimal(&frequencies);
+        assert_eq!(table.symbols.len(), 5);
+        assert!(table.code(0x00).unwrap().1 <= table.code(0xF0).unwrap().1);
+        assert_eq!(table.code(0x02), None);
+
+        let mut writer = BitWriter::new();
+        let message = [0x11, 0x00, 0xF0, 0x23, 0x01];
+        for symbol in message {
+            let (code, length) = table.code(symbol).unwrap();
+            writer.write_bits(code as u32, length);
+        }
+        writer.flush();
+        let mut reader = BitReader::new(&writer.data);
+        for symbol in message {
+            assert_eq!(table.decode(&mut reader), Ok(symbol));
+        }
+
+        let segment = HuffmanTable::to_segment(&[(1, 2, &table)]);
+        assert_eq!(
+            HuffmanTable::parse_segment(&segment),
+            Ok(vec![(1, 2, table)])
+        );
+    }
+}
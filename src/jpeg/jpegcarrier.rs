@@ -0,0 +1,112 @@
+use crate::carrier::{Carrier, Cover};
+use crate::jpeg::f5::{carried_bit, decrement, Slot};
+use crate::jpeg::{capacity_f5, embed_f5, extract_f5, JPEG};
+use crate::stego::{Capacity, EmbedOptions, Extraction, StegoError, StegoKey};
+use crate::Error;
+
+/// The JPEG format, embedding in the quantized DCT coefficients with F5.
+pub struct JPEGCarrier;
+
+/// A parsed JPEG image.
+/// Its positions are the nonzero AC coefficients found when it was parsed, but embedding goes through [`embed_f5`], which copes with coefficients shrinking to zero.
+pub struct JPEGCover {
+    pub jpeg: JPEG,
+    slots: Vec<Slot>,
+}
+
+impl Carrier for JPEGCarrier {
+    fn name(&self) -> &'static str {
+        "jpeg"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &["f5"]
+    }
+
+    fn parse(&self, data: &[u8], method: &str) -> Result<Box<dyn Cover>, Error> {
+        self.check_method(method)?;
+        let jpeg = JPEG::parse(data)?;
+        let slots = jpeg
+            .coded_blocks()
+            .into_iter()
+            .flat_map(|(component, block)| (1..64).map(move |k| (component, block, k)))
+            .filter(|&(component, block, k)| jpeg.coefficient(component, block, k) != 0)
+            .collect();
+        Ok(Box::new(JPEGCover { jpeg, slots }))
+    }
+
+    /// Accounts for the coefficients F5 is expected to lose to shrinkage.
+    fn capacity(
+        &self,
+        data: &[u8],
+        method: &str,
+        options: EmbedOptions,
+    ) -> Result<Option<Capacity>, Error> {
+        self.check_method(method)?;
+        Ok(Some(capacity_f5(&JPEG::parse(data)?, options)))
+    }
+}
+
+impl JPEGCarrier {
+    fn check_method(&self, method: &str) -> Result<(), Error> {
+        match method {
+            "f5" => Ok(()),
+            _ => Err(Error::UnsupportedMethod {
+                format: self.name(),
+                method: method.to_string(),
+            }),
+        }
+    }
+}
+
+impl Cover for JPEGCover {
+    fn positions(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn read_bit(&self, position: usize) -> bool {
+        let (component, block, k) = self.slots[position];
+        carried_bit(self.jpeg.coefficient(component, block, k))
+    }
+
+    /// Lowers the magnitude of the coefficient if it does not carry the bit, which may leave it at zero.
+    fn write_bit(&mut self, position: usize, bit: bool) {
+        if self.read_bit(position) != bit {
+            decrement(&mut self.jpeg, self.slots[position]);
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.jpeg.to_bytes()
+    }
+
+    fn embed_raw(
+        &mut self,
+        key: &StegoKey,
+        data: &[u8],
+        options: EmbedOptions,
+    ) -> Result<(), StegoError> {
+        embed_f5(&mut self.jpeg, key, data, options)
+    }
+
+    fn extract_raw(&self, key: &StegoKey) -> Result<Extraction, StegoError> {
+        extract_f5(&self.jpeg, key)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::jpeg::test::jpeg_bytes;
+
+    #[test]
+    fn checks_method_before_parsing() {
+        let options = EmbedOptions::default();
+        assert!(matches!(
+            JPEGCarrier.capacity(b"not a jpeg", "lsb", options),
+            Err(Error::UnsupportedMethod { format: "jpeg", .. })
+        ));
+        let capacity = JPEGCarrier.capacity(&jpeg_bytes(false), "f5", options);
+        assert!(capacity.unwrap().unwrap().raw_bits > 0);
+    }
+}
@@ -0,0 +1,59 @@
+/// Error type for JPEG parsing.
+#[derive(Debug, PartialEq)]
+pub enum JPEGParseError {
+    NotJPEG,
+    TruncatedSegment,
+    /// The frame uses a coding process other than Huffman-coded baseline, extended sequential or progressive DCT.
+    UnsupportedProcess {
+        marker: u8,
+    },
+    InvalidFrame,
+    InvalidScan,
+    InvalidHuffmanTable,
+    MissingHuffmanTable {
+        class: u8,
+        id: u8,
+    },
+    MissingFrame,
+    /// The entropy-coded data holds a code that is not in the Huffman table, or a value that does not fit where it is placed.
+    InvalidCode,
+    TruncatedScan,
+}
+
+impl JPEGParseError {
+    pub fn description(&self) -> String {
+        match self {
+            JPEGParseError::NotJPEG => String::from("Input does not start with a JPEG SOI marker."),
+            JPEGParseError::TruncatedSegment => {
+                String::from("Marker segment extends past the end of the data.")
+            }
+            JPEGParseError::UnsupportedProcess { marker } => format!(
+                "Unsupported JPEG coding process (SOF marker {:#04x}).",
+                marker
+            ),
+            JPEGParseError::InvalidFrame => String::from("The frame header is malformed."),
+            JPEGParseError::InvalidScan => String::from("A scan header is malformed."),
+            JPEGParseError::InvalidHuffmanTable => String::from("A Huffman table is malformed."),
+            JPEGParseError::MissingHuffmanTable { class, id } => format!(
+                "A scan uses {} Huffman table {}, which is not defined.",
+                if *class == 0 { "DC" } else { "AC" },
+                id
+            ),
+            JPEGParseError::MissingFrame => String::from("A scan appears before the frame header."),
+            JPEGParseError::InvalidCode => {
+                String::from("The entropy-coded data holds an invalid Huffman code.")
+            }
+            JPEGParseError::TruncatedScan => {
+                String::from("The entropy-coded data ends before the scan is complete.")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for JPEGParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(&self.description())
+    }
+}
+
+impl std::error::Error for JPEGParseError {}
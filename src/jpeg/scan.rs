@@ -0,0 +1,495 @@
+use crate::jpeg::bitreader::BitReader;
+use crate::jpeg::{FrameHeader, HuffmanTable, JPEGParseError, ScanHeader};
+
+/// Huffman tables in effect, indexed by class (0 for DC, 1 for AC) and identifier.
+pub type HuffmanTables = [[Option<HuffmanTable>; 4]; 2];
+
+/// Quantized DCT coefficients of one 8×8 block, in zigzag order.
+pub type Block = [i16; 64];
+
+/// Longest run of empty blocks a single EOB run can cover.
+const MAX_EOB_RUN: u32 = 0x7FFF;
+
+/// Correction bits of an AC refinement scan held back before a pending EOB run is forced out, as in libjpeg.
+const MAX_CORRECTION_BITS: usize = 1000;
+
+/// An item of entropy-coded data.
+/// Encoding a scan produces a stream of these, which is either counted to build Huffman tables or written out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Token {
+    Symbol { class: u8, table: u8, symbol: u8 },
+    Bits { value: u32, length: u8 },
+    Restart,
+}
+
+/// A scan: its header, the entropy-coded data as read, and the coding state in effect where it appears in the file.
+#[derive(Clone, Debug)]
+pub struct Scan {
+    pub header: ScanHeader,
+    /// Body of the SOS segment as read.
+    pub header_data: Vec<u8>,
+    /// Entropy-coded data as read, including restart markers.
+    pub data: Vec<u8>,
+    /// Number of MCUs between restart markers, or zero if there are none.
+    pub restart_interval: u16,
+    /// Huffman tables defined when the scan was read.
+    pub tables: HuffmanTables,
+}
+
+/// Values carried from block to block within a restart interval.
+struct CodingState {
+    predictions: Vec<i32>,
+    eob_run: u32,
+    /// Correction bits of the blocks in the pending EOB run, for AC refinement scans.
+    corrections: Vec<bool>,
+}
+
+impl CodingState {
+    fn new(components: usize) -> CodingState {
+        CodingState {
+            predictions: vec![0; components],
+            eob_run: 0,
+            corrections: Vec::new(),
+        }
+    }
+}
+
+impl Scan {
+    /// Returns the (class, identifier) of every Huffman table the scan codes symbols with.
+    pub fn table_slots(&self) -> Vec<(u8, u8)> {
+        let header = &self.header;
+        let mut slots = Vec::new();
+        for component in &header.components {
+            if header.spectral_start == 0 && header.approximation_high == 0 {
+                slots.push((0, component.dc_table));
+            }
+            if header.spectral_end > 0 {
+                slots.push((1, component.ac_table));
+            }
+        }
+        slots.sort();
+        slots.dedup();
+        slots
+    }
+
+    fn table(&self, class: u8, id: u8) -> Result<&HuffmanTable, JPEGParseError> {
+        self.tables[class as usize][id as usize]
+            .as_ref()
+            .ok_or(JPEGParseError::MissingHuffmanTable { class, id })
+    }
+
+    /// Lists the blocks of each MCU in coding order, as the index of the component in the scan and the index of the block in that component.
+    fn mcus(&self, frame: &FrameHeader) -> Vec<Vec<(usize, usize)>> {
+        if let [component] = self.header.components.as_slice() {
+            let (wide, high) = frame.coded_blocks(component.index);
+            let stride = frame.blocks(component.index).0;
+            return (0..high)
+                .flat_map(|y| (0..wide).map(move |x| vec![(0, y * stride + x)]))
+                .collect();
+        }
+
+        let (mcus_wide, mcus_high) = frame.mcus();
+        let mut mcus = Vec::with_capacity(mcus_wide * mcus_high);
+        for mcu_y in 0..mcus_high {
+            for mcu_x in 0..mcus_wide {
+                let mut mcu = Vec::new();
+                for (index, component) in self.header.components.iter().enumerate() {
+                    let sampling = &frame.components[component.index];
+                    let (horizontal, vertical) =
+                        (sampling.horizontal as usize, sampling.vertical as usize);
+                    let stride = frame.blocks(component.index).0;
+                    for y in 0..vertical {
+                        for x in 0..horizontal {
+                            let block = (mcu_y * vertical + y) * stride + mcu_x * horizontal + x;
+                            mcu.push((index, block));
+                        }
+                    }
+                }
+                mcus.push(mcu);
+            }
+        }
+        mcus
+    }
+
+    /// Returns whether a restart marker precedes the MCU with the given index.
+    fn restarts_at(&self, mcu: usize) -> bool {
+        self.restart_interval > 0 && mcu > 0 && mcu.is_multiple_of(self.restart_interval as usize)
+    }
+
+    /// Decodes the scan into the coefficients of the frame's components.
+    pub fn decode(
+        &self,
+        frame: &FrameHeader,
+        coefficients: &mut [Vec<Block>],
+    ) -> Result<(), JPEGParseError> {
+        for (class, id) in self.table_slots() {
+            self.table(class, id)?;
+        }
+        let mut reader = BitReader::new(&self.data);
+        let mut state = CodingState::new(self.header.components.len());
+        for (index, mcu) in self.mcus(frame).into_iter().enumerate() {
+            if self.restarts_at(index) {
+                reader.restart()?;
+                state = CodingState::new(self.header.components.len());
+            }
+            for (component, block) in mcu {
+                let frame_component = self.header.components[component].index;
+                let block = &mut coefficients[frame_component][block];
+                self.decode_block(
+                    &mut reader,
+                    &mut state,
+                    component,
+                    block,
+                    frame.progressive(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_block(
+        &self,
+        reader: &mut BitReader,
+        state: &mut CodingState,
+        component: usize,
+        block: &mut Block,
+        progressive: bool,
+    ) -> Result<(), JPEGParseError> {
+        let header = &self.header;
+        let low = header.approximation_low;
+        if header.spectral_start == 0 {
+            if header.approximation_high == 0 {
+                let size = self
+                    .table(0, header.components[component].dc_table)?
+                    .decode(reader)?;
+                let difference = receive_extend(reader, size)?;
+                state.predictions[component] += difference;
+                block[0] = (state.predictions[component] << low) as i16;
+            } else if reader.read_bit()? {
+                block[0] |= 1 << low;
+            }
+            if header.spectral_end == 0 {
+                return Ok(());
+            }
+        }
+
+        let table = self.table(1, header.components[component].ac_table)?;
+        let start = header.spectral_start.max(1) as usize;
+        let end = header.spectral_end as usize;
+        match header.approximation_high {
+            0 => decode_ac_first(reader, state, table, block, start..=end, low, progressive),
+            _ => decode_ac_refinement(reader, state, table, block, start..=end, low),
+        }
+    }
+
+    /// Encodes the coefficients as this scan, passing each token of the coded data to `emit`.
+    pub fn encode(
+        &self,
+        frame: &FrameHeader,
+        coefficients: &[Vec<Block>],
+        emit: &mut dyn FnMut(Token),
+    ) {
+        let mut state = CodingState::new(self.header.components.len());
+        for (index, mcu) in self.mcus(frame).into_iter().enumerate() {
+            if self.restarts_at(index) {
+                self.flush_eob_run(&mut state, emit);
+                emit(Token::Restart);
+                state = CodingState::new(self.header.components.len());
+            }
+            for (component, block) in mcu {
+                let frame_component = self.header.components[component].index;
+                let block = &coefficients[frame_component][block];
+                self.encode_block(&mut state, component, block, frame.progressive(), emit);
+            }
+        }
+        self.flush_eob_run(&mut state, emit);
+    }
+
+    fn encode_block(
+        &self,
+        state: &mut CodingState,
+        component: usize,
+        block: &Block,
+        progressive: bool,
+        emit: &mut dyn FnMut(Token),
+    ) {
+        let header = &self.header;
+        let low = header.approximation_low;
+        if header.spectral_start == 0 {
+            if header.approximation_high == 0 {
+                let value = block[0] as i32 >> low;
+                let (size, bits) = magnitude(value - state.predictions[component]);
+                state.predictions[component] = value;
+                emit(Token::Symbol {
+                    class: 0,
+                    table: header.components[component].dc_table,
+                    symbol: size,
+                });
+                emit(Token::Bits {
+                    value: bits,
+                    length: size,
+                });
+            } else {
+                let bit = (block[0] >> low) & 1;
+                emit(Token::Bits {
+                    value: bit as u32,
+                    length: 1,
+                });
+            }
+            if header.spectral_end == 0 {
+                return;
+            }
+        }
+
+        let table = header.components[component].ac_table;
+        let start = header.spectral_start.max(1) as usize;
+        let end = header.spectral_end as usize;
+        let symbol = |symbol| Token::Symbol {
+            class: 1,
+            table,
+            symbol,
+        };
+        let bit = |bit: bool| Token::Bits {
+            value: bit as u32,
+            length: 1,
+        };
+
+        if header.approximation_high == 0 {
+            let mut run = 0;
+            for &coefficient in &block[start..=end] {
+                let value = coefficient.unsigned_abs() as i32 >> low;
+                if value == 0 {
+                    run += 1;
+                    continue;
+                }
+                if progressive {
+                    self.flush_eob_run(state, emit);
+                }
+                while run > 15 {
+                    emit(symbol(0xF0));
+                    run -= 16;
+                }
+                let (size, bits) = magnitude(if coefficient < 0 { -value } else { value });
+                emit(symbol((run << 4) as u8 + size));
+                emit(Token::Bits {
+                    value: bits,
+                    length: size,
+                });
+                run = 0;
+            }
+            if run > 0 {
+                if !progressive {
+                    emit(symbol(0x00));
+                    return;
+                }
+                state.eob_run += 1;
+                if state.eob_run == MAX_EOB_RUN {
+                    self.flush_eob_run(state, emit);
+                }
+            }
+            return;
+        }
+
+        // A refinement scan codes the coefficients that become nonzero at this bit position as run/size symbols.
+        // The next bit of every coefficient that was already nonzero follows the symbol that skips over it.
+        let values: Vec<u16> = block[start..=end]
+            .iter()
+            .map(|coefficient| coefficient.unsigned_abs() >> low)
+            .collect();
+        let last_new = values.iter().rposition(|&value| value == 1);
+        let mut run = 0;
+        let mut corrections = Vec::new();
+        for (k, &value) in values.iter().enumerate() {
+            if value == 0 {
+                run += 1;
+                continue;
+            }
+            while run > 15 && last_new.is_some_and(|last| k <= last) {
+                self.flush_eob_run(state, emit);
+                emit(symbol(0xF0));
+                run -= 16;
+                corrections
+                    .drain(..)
+                    .for_each(|correction| emit(bit(correction)));
+            }
+            if value > 1 {
+                corrections.push(value & 1 == 1);
+                continue;
+            }
+            self.flush_eob_run(state, emit);
+            emit(symbol((run << 4) as u8 + 1));
+            emit(bit(block[start + k] > 0));
+            corrections
+                .drain(..)
+                .for_each(|correction| emit(bit(correction)));
+            run = 0;
+        }
+        if run > 0 || !corrections.is_empty() {
+            state.eob_run += 1;
+            state.corrections.extend(corrections);
+            if state.eob_run == MAX_EOB_RUN || state.corrections.len() > MAX_CORRECTION_BITS - 63 {
+                self.flush_eob_run(state, emit);
+            }
+        }
+    }
+
+    /// Emits the pending EOB run of a progressive AC scan, followed by the correction bits held back for its blocks.
+    fn flush_eob_run(&self, state: &mut CodingState, emit: &mut dyn FnMut(Token)) {
+        if state.eob_run == 0 {
+            return;
+        }
+        let length = (31 - state.eob_run.leading_zeros()) as u8;
+        emit(Token::Symbol {
+            class: 1,
+            table: self.header.components[0].ac_table,
+            symbol: length << 4,
+        });
+        emit(Token::Bits {
+            value: state.eob_run,
+            length,
+        });
+        state.eob_run = 0;
+        for correction in state.corrections.drain(..) {
+            emit(Token::Bits {
+                value: correction as u32,
+                length: 1,
+            });
+        }
+    }
+}
+
+/// Decodes the first scan of a band of AC coefficients, or all of them in a sequential scan.
+fn decode_ac_first(
+    reader: &mut BitReader,
+    state: &mut CodingState,
+    table: &HuffmanTable,
+    block: &mut Block,
+    band: std::ops::RangeInclusive<usize>,
+    low: u8,
+    progressive: bool,
+) -> Result<(), JPEGParseError> {
+    if state.eob_run > 0 {
+        state.eob_run -= 1;
+        return Ok(());
+    }
+    let (mut k, end) = band.into_inner();
+    while k <= end {
+        let symbol = table.decode(reader)?;
+        let (run, size) = ((symbol >> 4) as usize, symbol & 0x0F);
+        if size == 0 {
+            if run == 15 {
+                k += 16;
+                continue;
+            }
+            // Sequential scans only have the end-of-block code; progressive scans code runs of empty blocks.
+            if progressive {
+                state.eob_run = (1 << run) - 1 + reader.read_bits(run as u8)? as u32;
+            }
+            break;
+        }
+        k += run;
+        if k > end {
+            return Err(JPEGParseError::InvalidCode);
+        }
+        block[k] = (receive_extend(reader, size)? << low) as i16;
+        k += 1;
+    }
+    Ok(())
+}
+
+/// Decodes a refinement scan of a band of AC coefficients, adding one bit of precision to each.
+fn decode_ac_refinement(
+    reader: &mut BitReader,
+    state: &mut CodingState,
+    table: &HuffmanTable,
+    block: &mut Block,
+    band: std::ops::RangeInclusive<usize>,
+    low: u8,
+) -> Result<(), JPEGParseError> {
+    let positive = 1i16 << low;
+    let negative = -1i16 << low;
+    let refine = |coefficient: &mut i16, reader: &mut BitReader| -> Result<(), JPEGParseError> {
+        if reader.read_bit()? && *coefficient & positive == 0 {
+            *coefficient += if *coefficient >= 0 {
+                positive
+            } else {
+                negative
+            };
+        }
+        Ok(())
+    };
+
+    let (mut k, end) = band.into_inner();
+    if state.eob_run == 0 {
+        while k <= end {
+            let symbol = table.decode(reader)?;
+            let (mut run, size) = ((symbol >> 4) as i32, symbol & 0x0F);
+            let mut value = 0;
+            match size {
+                0 if run != 15 => {
+                    state.eob_run = (1 << run) + reader.read_bits(run as u8)? as u32;
+                    break;
+                }
+                0 => {}
+                1 => {
+                    value = if reader.read_bit()? {
+                        positive
+                    } else {
+                        negative
+                    }
+                }
+                _ => return Err(JPEGParseError::InvalidCode),
+            }
+
+            // Skip `run` zero coefficients, refining the nonzero ones passed on the way.
+            while k <= end {
+                if block[k] != 0 {
+                    refine(&mut block[k], reader)?;
+                } else {
+                    run -= 1;
+                    if run < 0 {
+                        break;
+                    }
+                }
+                k += 1;
+            }
+            if value != 0 {
+                if k > end {
+                    return Err(JPEGParseError::InvalidCode);
+                }
+                block[k] = value;
+            }
+            k += 1;
+        }
+    }
+
+    if state.eob_run > 0 {
+        for coefficient in block[k.min(end + 1)..=end].iter_mut() {
+            if *coefficient != 0 {
+                refine(coefficient, reader)?;
+            }
+        }
+        state.eob_run -= 1;
+    }
+    Ok(())
+}
+
+/// Reads a value coded in `size` bits, where values below half the range stand for negative numbers.
+fn receive_extend(reader: &mut BitReader, size: u8) -> Result<i32, JPEGParseError> {
+    if size > 16 {
+        return Err(JPEGParseError::InvalidCode);
+    }
+    let value = reader.read_bits(size)? as i32;
+    Ok(match size {
+        0 => 0,
+        _ if value < 1 << (size - 1) => value - (1 << size) + 1,
+        _ => value,
+    })
+}
+
+/// Returns the size category of a value and the bits that code it within that category, the inverse of [`receive_extend`].
+fn magnitude(value: i32) -> (u8, u32) {
+    let size = (32 - value.unsigned_abs().leading_zeros()) as u8;
+    let bits = if value < 0 { value - 1 } else { value };
+    (size, bits as u32 & ((1 << size) - 1))
+}
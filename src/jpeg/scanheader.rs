@@ -0,0 +1,78 @@
+use crate::jpeg::{FrameHeader, JPEGParseError};
+
+/// A component coded in a scan, with the Huffman tables it uses.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanComponent {
+    /// Index of the component in the frame header.
+    pub index: usize,
+    pub dc_table: u8,
+    pub ac_table: u8,
+}
+
+/// The contents of an SOS segment.
+/// Sequential scans code every coefficient; progressive scans code a band of coefficients, possibly one bit plane at a time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanHeader {
+    pub components: Vec<ScanComponent>,
+    /// First coefficient of the band, in zigzag order.
+    pub spectral_start: u8,
+    /// Last coefficient of the band, in zigzag order.
+    pub spectral_end: u8,
+    /// Bit position coded by the previous scan of this band, or zero for the first scan.
+    pub approximation_high: u8,
+    /// Bit position coded by this scan.
+    pub approximation_low: u8,
+}
+
+impl ScanHeader {
+    pub fn parse(data: &[u8], frame: &FrameHeader) -> Result<ScanHeader, JPEGParseError> {
+        let count = *data.first().ok_or(JPEGParseError::InvalidScan)? as usize;
+        if count == 0 || count > 4 || data.len() != 4 + count * 2 {
+            return Err(JPEGParseError::InvalidScan);
+        }
+        let components = data[1..1 + count * 2]
+            .chunks(2)
+            .map(|component| {
+                let index = frame
+                    .components
+                    .iter()
+                    .position(|c| c.id == component[0])
+                    .ok_or(JPEGParseError::InvalidScan)?;
+                Ok(ScanComponent {
+                    index,
+                    dc_table: component[1] >> 4,
+                    ac_table: component[1] & 0x0F,
+                })
+            })
+            .collect::<Result<Vec<ScanComponent>, JPEGParseError>>()?;
+
+        let tail = &data[1 + count * 2..];
+        let header = ScanHeader {
+            components,
+            spectral_start: tail[0],
+            spectral_end: tail[1],
+            approximation_high: tail[2] >> 4,
+            approximation_low: tail[2] & 0x0F,
+        };
+
+        let valid = match frame.progressive() {
+            // DC scans may interleave components; AC scans code a single component.
+            true => {
+                header.spectral_start <= header.spectral_end
+                    && header.spectral_end < 64
+                    && (header.spectral_start == 0) == (header.spectral_end == 0)
+                    && (header.spectral_start == 0 || count == 1)
+                    && header.approximation_low < 14
+            }
+            false => header.spectral_start == 0 && header.spectral_end == 63 && tail[2] == 0,
+        };
+        let tables_valid = header
+            .components
+            .iter()
+            .all(|c| c.dc_table < 4 && c.ac_table < 4);
+        if !valid || !tables_valid {
+            return Err(JPEGParseError::InvalidScan);
+        }
+        Ok(header)
+    }
+}
@@ -0,0 +1,56 @@
+//! Hides encrypted payloads inside media files.
+//!
+//! The [`mp3`] module parses MP3 files and provides the carriers that expose their modifiable bits.
+//! The [`stego`] module spreads a payload over any such carrier under a password-derived key,
+//! optionally compressing, encrypting and error-correcting it on the way.
+//! The [`wav`], [`flac`], [`ogg`], [`aac`], [`mp4`], [`png`] and [`jpeg`] modules do the same for uncompressed RIFF/WAVE audio, FLAC audio, Ogg Opus and Vorbis streams, ADTS AAC streams, MP4 and M4A files, PNG images and the DCT coefficients of JPEG images.
+//! The [`carrier`] module ties the two together behind format-independent traits, with a registry of the supported formats.
+//!
+//! ```no_run
+//! # #[cfg(feature = "crypto")]
+//! # {
+//! use stegocrypt::mp3::{PrivateBitCarrier, MP3};
+//! use stegocrypt::stego::{self, EmbedOptions, StegoKey};
+//!
+//! let mut mp3 = MP3::parse(&std::fs::read("cover.mp3").unwrap()).unwrap();
+//! let key = StegoKey::derive("hunter2");
+//! stego::embed(&mut mp3, &PrivateBitCarrier, &key, b"hello", EmbedOptions::default()).unwrap();
+//! std::fs::write("stego.mp3", mp3.to_bytes()).unwrap();
+//! # }
+//! ```
+//!
+//! # Features
+//!
+//! - `crypto` (default): the encrypted [`stego::Container`] and the [`stego::embed`] and [`stego::extract`] functions built on it.
+//!   Without it, [`stego::embed_raw`] and [`stego::extract_raw`] place caller-sealed data.
+//! - `compression` (default): Deflate and Zstandard compression of payloads before they are encrypted.
+//! - `aac` (default): the [`aac`] format.
+//! - `flac` (default): the [`flac`] format.
+//! - `jpeg` (default): the [`jpeg`] format.
+//! - `mp4` (default): the [`mp4`] format.
+//! - `ogg` (default): the [`ogg`] format.
+//! - `png` (default): the [`png`] format.
+//! - `report` (default): the serializable [`mp3::MP3Report`].
+//! - `cli` (default): the `stegocrypt` command-line tool.
+//! - `wav` (default): the [`wav`] format.
+
+pub use self::error::Error;
+
+#[cfg(feature = "aac")]
+pub mod aac;
+pub mod carrier;
+mod error;
+#[cfg(feature = "flac")]
+pub mod flac;
+#[cfg(feature = "jpeg")]
+pub mod jpeg;
+pub mod mp3;
+#[cfg(feature = "mp4")]
+pub mod mp4;
+#[cfg(feature = "ogg")]
+pub mod ogg;
+#[cfg(feature = "png")]
+pub mod png;
+pub mod stego;
+#[cfg(feature = "wav")]
+pub mod wav;
@@ -1,33 +1,135 @@
-use self::{id3v2::ID3v2, mpegframeheader::MPEGFrameHeader, mpegparserror::MPEGParseError};
+use std::io::Read;
+use std::ops::Range;
 
+use crate::Error;
+
+pub use self::id3v2::{FrameIdentifier, ID3v2, ID3v2Frame, ID3v2ParseError};
+pub use self::mpegframeheader::{
+    CRCProtection, Copyright, FrameBitrate, FramePadding, MP3ChannelMode, MP3Emphasis,
+    MP3ModeExtension, MPEGFrameHeader, MPEGFrameSync, MPEGLayer, MPEGVersion, Original, SampleRate,
+};
+pub use self::mpegparserror::MPEGParseError;
+
+pub use self::capacity::{capacity, Method};
+pub use self::framereader::{FrameReader, Frames};
+pub use self::globalgaincarrier::GlobalGainCarrier;
+pub use self::headerflagcarrier::HeaderFlagCarrier;
+pub use self::mp3carrier::{MP3Carrier, MP3Cover};
+pub use self::mp3ref::{FrameRef, MP3Ref};
+#[cfg(feature = "report")]
+pub use self::mp3report::{Anomaly, Encoding, FrameReport, MP3Report, TagFrameReport, TagReport};
+pub use self::privatebitcarrier::PrivateBitCarrier;
+pub use self::sideinfo::{GranuleChannel, SideInfo};
+#[cfg(feature = "crypto")]
+pub use self::streaming::{embed_stream, extract_stream};
+pub use self::vbrheader::{LameTag, VBRFrame, VBRHeader, VBRIHeader, XingHeader};
+
+mod capacity;
+mod framereader;
+mod globalgaincarrier;
+mod headerflagcarrier;
 mod id3v2;
+mod mp3carrier;
+mod mp3ref;
+#[cfg(feature = "report")]
+mod mp3report;
 mod mpegframeheader;
 mod mpegparserror;
+mod privatebitcarrier;
+mod sideinfo;
+#[cfg(feature = "crypto")]
+mod streaming;
+mod vbrheader;
 
+/// A parsed MP3 file: its optional ID3v2 tag followed by MPEG audio frames.
 pub struct MP3 {
     pub id3v2: Option<ID3v2>,
     // pub id3v1: Option<ID3v1>,
+    /// The first frame, if it holds a Xing/Info or VBRI header instead of audio. It is kept out of `frames`.
+    pub vbr_frame: Option<VBRFrame>,
     pub frames: Vec<MP3Frame>,
+    /// Byte ranges of the input that were skipped while resynchronizing on the next frame.
+    pub skipped: Vec<Range<usize>>,
+    /// The bytes of each skipped range, such as an ID3v1 or APE tag after the last frame, written back in place.
+    pub skipped_data: Vec<Vec<u8>>,
+    /// Length of the parsed input in bytes.
+    pub length: usize,
 }
 
+/// A single MPEG audio frame, header included.
+#[derive(Clone)]
 pub struct MP3Frame {
     pub header: MPEGFrameHeader,
     pub data: Vec<u8>,
+    /// Byte offset of the frame within the parsed input.
+    pub offset: usize,
 }
 
 impl MP3Frame {
-    pub fn parse(data: Vec<u8>) -> Result<MP3Frame, MPEGParseError> {
-        let header = MPEGFrameHeader::parse(&data)?;
-        let data = header.frame_data.clone();
+    /// Parses the frame at the start of the data. Anything after the frame is ignored.
+    pub fn parse(data: &[u8]) -> Result<MP3Frame, MPEGParseError> {
+        let header = MPEGFrameHeader::parse(data)?;
+        let data = data[..header.frame_length as usize].to_vec();
+
+        Ok(MP3Frame {
+            header,
+            data,
+            offset: 0,
+        })
+    }
 
-        Ok(MP3Frame { header, data })
+    /// Changes this frame's header, writing it back to the frame bytes and recomputing the checksum if it was valid before.
+    fn update_header(&mut self, change: impl FnOnce(&mut MPEGFrameHeader)) {
+        let crc_valid = self.header.verify_crc(&self.data) == Some(true);
+        change(&mut self.header);
+        self.data[0..4].copy_from_slice(&self.header.raw_header.to_be_bytes());
+        if crc_valid {
+            self.header.update_crc(&mut self.data);
+        }
+    }
+
+    /// Sets the private bit of this frame's header, keeping the frame bytes and checksum in sync.
+    pub fn set_private_bit(&mut self, private_bit: bool) {
+        self.update_header(|header| header.set_private_bit(private_bit));
+    }
+
+    /// Sets the copyright bit of this frame's header, keeping the frame bytes and checksum in sync.
+    pub fn set_copyright(&mut self, copyright: Copyright) {
+        self.update_header(|header| header.set_copyright(copyright));
+    }
+
+    /// Sets the original bit of this frame's header, keeping the frame bytes and checksum in sync.
+    pub fn set_original(&mut self, original: Original) {
+        self.update_header(|header| header.set_original(original));
+    }
+
+    /// Returns the side information of this frame, if it is a Layer III frame.
+    pub fn side_info(&self) -> Option<SideInfo> {
+        SideInfo::parse(&self.header, &self.data)
+    }
+
+    /// Sets a single bit of the frame body, given as an offset in bits from the start of the frame.
+    /// This must not be used on the four header bytes, which are kept in sync by the header setters.
+    /// A valid checksum is recomputed so that changes to protected bits go unnoticed.
+    pub fn set_bit(&mut self, offset: usize, bit: bool) {
+        let crc_valid = self.header.verify_crc(&self.data) == Some(true);
+        let mask = 0b10000000 >> (offset % 8);
+        if bit {
+            self.data[offset / 8] |= mask;
+        } else {
+            self.data[offset / 8] &= !mask;
+        }
+        if crc_valid {
+            self.header.update_crc(&mut self.data);
+        }
     }
 }
 
 impl MP3 {
-    pub fn parse(data: Vec<u8>) -> Result<MP3, MPEGParseError> {
-        let id3v2 = if ID3v2::has_id3v2_tag(data.clone()) {
-            Some(ID3v2::parse(data.clone()).unwrap())
+    /// Parses a whole MP3 file, skipping over any data between frames that cannot be decoded.
+    pub fn parse(data: &[u8]) -> Result<MP3, Error> {
+        let id3v2 = if ID3v2::has_id3v2_tag(data) {
+            Some(ID3v2::parse(data)?)
         } else {
             None
         };
@@ -38,23 +140,280 @@ impl MP3 {
         //     None
         // };
 
-        let mut frames = Vec::new();
+        let start = id3v2.as_ref().map_or(0, |id3v2| id3v2.size as usize);
+        let mut slices = Frames::new(data, start);
+        let mut frames = slices
+            .by_ref()
+            .map(|(offset, frame)| {
+                MP3Frame::parse(frame)
+                    .map(|frame| MP3Frame { offset, ..frame })
+                    .map_err(|error| Error::MPEG {
+                        error,
+                        offset,
+                        frame: None,
+                    })
+            })
+            .collect::<Result<Vec<MP3Frame>, Error>>()?;
+
+        let mut vbr_frame = None;
+        if let Some(header) = frames
+            .first()
+            .and_then(|frame| VBRHeader::parse(&frame.header, &frame.data))
+        {
+            vbr_frame = Some(VBRFrame {
+                frame: frames.remove(0),
+                header,
+                music_crc_valid: None,
+            });
+        }
+
+        let skipped = slices.skipped().to_vec();
+        let skipped_data = skipped
+            .iter()
+            .map(|range| data[range.clone()].to_vec())
+            .collect();
+        MP3::new(
+            id3v2,
+            vbr_frame,
+            frames,
+            (skipped, skipped_data),
+            data.len(),
+            start,
+        )
+    }
+
+    /// Parses an MP3 file from a reader, copying only the frames rather than the whole input.
+    pub fn read<R: Read>(reader: R) -> Result<MP3, Error> {
+        let mut reader = FrameReader::new(reader)?;
+        let start = reader.id3v2().map_or(0, |id3v2| id3v2.size as usize);
+        let frames = reader.by_ref().collect::<Result<Vec<MP3Frame>, Error>>()?;
+        let skipped = reader.skipped().to_vec();
+        let skipped_data = reader.take_skipped_data();
+        let length = reader.offset();
+        let id3v2 = reader.take_id3v2();
+        let vbr_frame = reader.take_vbr_frame();
+        MP3::new(
+            id3v2,
+            vbr_frame,
+            frames,
+            (skipped, skipped_data),
+            length,
+            start,
+        )
+    }
 
-        let mut current_index = 0;
-        if id3v2.is_some() {
-            current_index = id3v2.as_ref().unwrap().size as usize;
+    fn new(
+        id3v2: Option<ID3v2>,
+        mut vbr_frame: Option<VBRFrame>,
+        frames: Vec<MP3Frame>,
+        (skipped, skipped_data): (Vec<Range<usize>>, Vec<Vec<u8>>),
+        length: usize,
+        start: usize,
+    ) -> Result<MP3, Error> {
+        if frames.is_empty() {
+            return Err(Error::MPEG {
+                error: MPEGParseError::NoFrameSync,
+                offset: start,
+                frame: None,
+            });
         }
 
-        while current_index < data.len() {
-            let parsed_frame = MP3Frame::parse(data[current_index..].to_vec())?;
-            current_index += parsed_frame.header.frame_length as usize;
-            frames.push(parsed_frame);
+        if let Some(vbr_frame) = &mut vbr_frame {
+            vbr_frame.check_music_crc(LameTag::music_crc(&frames));
         }
 
         Ok(MP3 {
             id3v2,
             // id3v1,
+            vbr_frame,
             frames,
+            skipped,
+            skipped_data,
+            length,
         })
     }
+
+    /// Serializes the tag, the VBR header frame and all audio frames back into the bytes of an MP3 file, with the skipped bytes back between them.
+    /// The VBR header is brought in line with the audio frames as they are now, so that its counts and checksums still match after embedding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        if let Some(id3v2) = &self.id3v2 {
+            bytes.extend_from_slice(&id3v2.raw_tag);
+        }
+        let mut skipped = self.skipped.iter().zip(&self.skipped_data).peekable();
+        let mut skip_to = |bytes: &mut Vec<u8>, offset: usize| {
+            while let Some((_, data)) = skipped.next_if(|(range, _)| range.start < offset) {
+                bytes.extend_from_slice(data);
+            }
+        };
+        if let Some(vbr_frame) = &self.vbr_frame {
+            let mut vbr_frame = vbr_frame.clone();
+            vbr_frame.update(&self.frames);
+            skip_to(&mut bytes, vbr_frame.frame.offset);
+            bytes.extend_from_slice(&vbr_frame.frame.data);
+        }
+        for frame in &self.frames {
+            skip_to(&mut bytes, frame.offset);
+            bytes.extend_from_slice(&frame.data);
+        }
+        skip_to(&mut bytes, usize::MAX);
+        bytes
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// Length of a 128 kbps, 44.1 kHz MPEG-1 Layer III frame without padding.
+    pub const FRAME_LENGTH: usize = 417;
+
+    /// Builds an MP3 file of the given number of unpadded, unprotected 128 kbps frames.
+    pub fn mp3_bytes(frames: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for i in 0..frames {
+            let mut frame = vec![0xFF, 0xFB, 0x90, 0x64];
+            frame.extend((4..FRAME_LENGTH).map(|j| (i * 31 + j * 7) as u8));
+            bytes.extend(frame);
+        }
+        bytes
+    }
+
+    /// Builds an MP3 file like [`mp3_bytes`] whose frames are CRC-protected with correct checksums.
+    pub fn protected_mp3_bytes(frames: usize) -> Vec<u8> {
+        let mut bytes = mp3_bytes(frames);
+        for frame in bytes.chunks_mut(FRAME_LENGTH) {
+            frame[1] = 0xFA;
+            let checksum = CRCProtection::compute(frame, 32).unwrap();
+            frame[4..6].copy_from_slice(&checksum.to_be_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn parses_every_frame() {
+        let mp3 = MP3::parse(&mp3_bytes(10)).unwrap();
+        assert_eq!(mp3.frames.len(), 10);
+    }
+
+    #[test]
+    fn resyncs_after_cut_and_spliced_data() {
+        let mut bytes = mp3_bytes(10)[100..].to_vec();
+        bytes.extend(b"ID3 junk between files");
+        bytes.extend(mp3_bytes(5));
+        bytes.truncate(bytes.len() - 50);
+
+        let mp3 = MP3::parse(&bytes).unwrap();
+        assert_eq!(mp3.frames.len(), 9 + 4);
+    }
+
+    #[test]
+    fn serializes_unchanged_file_identically() {
+        let bytes = mp3_bytes(10);
+        let mp3 = MP3::parse(&bytes).unwrap();
+        assert_eq!(mp3.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn writes_skipped_bytes_back_in_place() {
+        let mut bytes = mp3_bytes(10)[100..].to_vec();
+        bytes.extend(b"junk between files");
+        bytes.extend(mp3_bytes(5));
+        bytes.extend(b"TAG");
+        bytes.extend([b' '; 125]);
+
+        let mut mp3 = MP3::parse(&bytes).unwrap();
+        assert_eq!(mp3.skipped.len(), 3);
+        assert_eq!(mp3.to_bytes(), bytes);
+        let read = MP3::read(bytes.as_slice()).unwrap();
+        assert_eq!(read.skipped_data, mp3.skipped_data);
+
+        mp3.frames[0].set_private_bit(true);
+        let written = mp3.to_bytes();
+        assert_eq!(written.len(), bytes.len());
+        assert!(written.ends_with(&bytes[bytes.len() - 128..]));
+    }
+
+    #[test]
+    fn sets_private_bit_in_frame_bytes() {
+        let mut mp3 = MP3::parse(&mp3_bytes(1)).unwrap();
+        mp3.frames[0].set_private_bit(true);
+        assert!(mp3.frames[0].header.private_bit);
+        assert_eq!(mp3.frames[0].data[2], 0x91);
+    }
+
+    /// Builds an ID3v2.3 tag holding a title frame, followed by the given number of padding bytes.
+    fn tag_bytes(padding: usize) -> Vec<u8> {
+        let mut frame = b"TIT2".to_vec();
+        frame.extend(6u32.to_be_bytes());
+        frame.extend([0, 0, 0]);
+        frame.extend(b"title");
+
+        let size = frame.len() + padding;
+        let mut tag = b"ID3\x03\x00\x00".to_vec();
+        tag.extend([0, 0, (size >> 7) as u8, (size & 0x7F) as u8]);
+        tag.extend(frame);
+        tag.extend(vec![0; padding]);
+        tag
+    }
+
+    #[test]
+    fn reports_offset_of_truncated_tag_frame() {
+        let mut bytes = tag_bytes(0);
+        bytes[17] = 0x40;
+        bytes.extend(mp3_bytes(2));
+
+        let error = MP3::parse(&bytes).err().unwrap();
+        assert!(matches!(
+            error,
+            Error::ID3v2 {
+                error: ID3v2ParseError::FrameTooLarge { size: 0x40 },
+                offset: 10,
+                frame: Some(0),
+            }
+        ));
+    }
+
+    /// A small-scale stand-in for the fuzz target: every prefix of a tagged file, and many randomly damaged copies, must parse or fail without panicking.
+    #[test]
+    fn never_panics_on_truncated_or_corrupted_input() {
+        let mut bytes = tag_bytes(20);
+        bytes.extend(mp3_bytes(4));
+
+        for length in 0..bytes.len() {
+            let _ = MP3::parse(&bytes[..length]);
+        }
+
+        let mut state: u32 = 1;
+        for _ in 0..2000 {
+            let mut damaged = bytes.clone();
+            for _ in 0..8 {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                let index = (state >> 8) as usize % damaged.len();
+                damaged[index] = (state >> 24) as u8;
+            }
+            if let Ok(mp3) = MP3::parse(&damaged) {
+                for frame in &mp3.frames {
+                    let _ = frame.side_info();
+                }
+                let _ = mp3.to_bytes();
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_checksum_valid_when_changing_protected_bits() {
+        let mut mp3 = MP3::parse(&protected_mp3_bytes(2)).unwrap();
+        let frame = &mut mp3.frames[0];
+        assert_eq!(frame.header.frame_length as usize, FRAME_LENGTH);
+        assert_eq!(frame.header.verify_crc(&frame.data), Some(true));
+
+        frame.set_private_bit(true);
+        let gain = frame.side_info().unwrap().granules[0].global_gain_lsb_offset();
+        frame.set_bit(gain, !frame.data[gain / 8] & (0x80 >> (gain % 8)) != 0);
+        assert_eq!(frame.header.verify_crc(&frame.data), Some(true));
+
+        let frame = MP3::parse(&mp3.to_bytes()).unwrap().frames.remove(0);
+        assert_eq!(frame.header.verify_crc(&frame.data), Some(true));
+    }
 }
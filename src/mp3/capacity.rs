@@ -0,0 +1,175 @@
+use crate::mp3::{CRCProtection, GlobalGainCarrier, MP3Frame, MP3};
+use crate::stego::{BitCarrier, Capacity, EmbedOptions};
+
+/// Largest meaningful big_values field: the pairs of a granule's 576 spectral values.
+const MAX_BIG_VALUES: usize = 288;
+
+/// The places in an MP3 file that can hold payload bits.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Method {
+    /// The private bit of each frame header.
+    PrivateBit,
+    /// The copyright and original flags of each frame header.
+    HeaderFlags,
+    /// Unused bytes after the Layer III main data of each frame, which decoders ignore.
+    AncillaryBits,
+    /// The signs of the nonzero quantized spectral values.
+    SignBits,
+    /// The least significant bit of each Layer III global_gain field.
+    GlobalGain,
+    /// The padding at the end of the ID3v2 tag.
+    TagPadding,
+}
+
+impl Method {
+    pub const ALL: [Method; 6] = [
+        Method::PrivateBit,
+        Method::HeaderFlags,
+        Method::AncillaryBits,
+        Method::SignBits,
+        Method::GlobalGain,
+        Method::TagPadding,
+    ];
+
+    /// Looks up a method by its name.
+    pub fn parse(name: &str) -> Option<Method> {
+        Method::ALL.into_iter().find(|method| method.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Method::PrivateBit => "private-bit",
+            Method::HeaderFlags => "header-flags",
+            Method::AncillaryBits => "ancillary-bits",
+            Method::SignBits => "sign-bits",
+            Method::GlobalGain => "global-gain",
+            Method::TagPadding => "tag-padding",
+        }
+    }
+
+    /// Counts the bit positions this method has in the file.
+    /// Always `Some` for MP3, whose positions can all be counted from the frame headers and side information.
+    pub fn raw_bits(&self, mp3: &MP3) -> Option<usize> {
+        match self {
+            Method::PrivateBit => Some(mp3.frames.len()),
+            Method::HeaderFlags => Some(2 * mp3.frames.len()),
+            Method::AncillaryBits => Some(8 * ancillary_bytes(&mp3.frames)),
+            Method::SignBits => Some(sign_bits(&mp3.frames)),
+            Method::GlobalGain => Some(GlobalGainCarrier::new(mp3).positions(mp3)),
+            Method::TagPadding => {
+                Some(8 * mp3.id3v2.as_ref().map_or(0, |tag| tag.padding as usize))
+            }
+        }
+    }
+}
+
+/// Estimates how large a payload the method can carry in the file with the given options, and how much of the carrier embedding it would change.
+/// Returns `None` if the method's positions cannot be counted.
+pub fn capacity(mp3: &MP3, method: Method, options: EmbedOptions) -> Option<Capacity> {
+    Some(Capacity::estimate(method.raw_bits(mp3)?, options))
+}
+
+/// Counts the spectral values of the big-values regions, two per pair recorded in the side information, whose signs the sign bit method changes.
+/// The region ends at the last nonzero pair, but a pair may still hold a zero, which has no sign, so this is an upper bound on the nonzero values.
+/// Granules that use no main data bits hold no values at all, whatever their big_values field says.
+fn sign_bits(frames: &[MP3Frame]) -> usize {
+    frames
+        .iter()
+        .filter_map(MP3Frame::side_info)
+        .flat_map(|side_info| side_info.granules)
+        .filter(|granule| granule.part2_3_length > 0)
+        .map(|granule| 2 * (granule.big_values as usize).min(MAX_BIG_VALUES))
+        .sum()
+}
+
+/// Counts the bytes of the Layer III main data area that no granule uses.
+/// Main data can start in earlier frames (the bit reservoir), so the unused bytes are those between the end of one frame's main data and the start of the next.
+fn ancillary_bytes(frames: &[MP3Frame]) -> usize {
+    let mut unused = 0;
+    let mut area_start = 0;
+    // End of the previous frame's main data, as an offset into the concatenated main data areas.
+    let mut previous_end = None;
+    for frame in frames {
+        let (Some(side_info_length), Some(side_info)) =
+            (frame.header.side_info_length(), frame.side_info())
+        else {
+            continue;
+        };
+        let crc = match frame.header.crc_protection {
+            CRCProtection::Enabled { .. } => 2,
+            CRCProtection::Disabled => 0,
+        };
+        let area = frame.data.len().saturating_sub(4 + crc + side_info_length);
+
+        let start = area_start - (side_info.main_data_begin as usize).min(area_start);
+        if let Some(previous_end) = previous_end {
+            unused += start.saturating_sub(previous_end);
+        }
+        let bits: usize = side_info
+            .granules
+            .iter()
+            .map(|granule| granule.part2_3_length as usize)
+            .sum();
+        previous_end = Some(start + bits.div_ceil(8));
+        area_start += area;
+    }
+    unused + previous_end.map_or(0, |end| area_start.saturating_sub(end))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mp3::test::{mp3_bytes, FRAME_LENGTH};
+
+    /// Builds frames whose granules use the given number of main data bits, with each frame's main data starting in its own area.
+    fn frames_using(bits: u16, count: usize) -> Vec<u8> {
+        let mut bytes = mp3_bytes(count);
+        for frame in bytes.chunks_mut(FRAME_LENGTH) {
+            frame[4..36].fill(0);
+            // part2_3_length of the first granule and channel, which starts 20 bits into the side information.
+            frame[6] = (bits >> 8) as u8;
+            frame[7] = bits as u8;
+        }
+        bytes
+    }
+
+    #[test]
+    fn counts_raw_bits_per_method() {
+        let mp3 = MP3::parse(&mp3_bytes(10)).unwrap();
+        let raw_bits = |method: Method| method.raw_bits(&mp3);
+        assert_eq!(raw_bits(Method::PrivateBit), Some(10));
+        assert_eq!(raw_bits(Method::HeaderFlags), Some(20));
+        assert_eq!(raw_bits(Method::GlobalGain), Some(40));
+        assert_eq!(raw_bits(Method::TagPadding), Some(0));
+        assert!(raw_bits(Method::SignBits).is_some_and(|bits| bits > 0));
+    }
+
+    #[test]
+    fn counts_big_values_pairs_as_sign_bits() {
+        let mut bytes = frames_using(800, 3);
+        for frame in bytes.chunks_mut(FRAME_LENGTH) {
+            // big_values of the first granule and channel, the 9 bits after part2_3_length.
+            frame[8] = 100 >> 1;
+        }
+        let mp3 = MP3::parse(&bytes).unwrap();
+        assert_eq!(Method::SignBits.raw_bits(&mp3), Some(3 * 200));
+
+        for frame in bytes.chunks_mut(FRAME_LENGTH) {
+            frame[8] = 0xFF;
+            frame[9] |= 0x80;
+        }
+        let mp3 = MP3::parse(&bytes).unwrap();
+        assert_eq!(Method::SignBits.raw_bits(&mp3), Some(3 * 576));
+    }
+
+    #[test]
+    fn counts_unused_main_data_as_ancillary() {
+        let mp3 = MP3::parse(&frames_using(800, 3)).unwrap();
+        let area = FRAME_LENGTH - 4 - 32;
+        assert_eq!(ancillary_bytes(&mp3.frames), 3 * (area - 100));
+
+        let capacity = capacity(&mp3, Method::AncillaryBits, EmbedOptions::default()).unwrap();
+        assert_eq!(capacity.raw_bits, 24 * (area - 100));
+        assert!(capacity.payload_bytes > 0);
+    }
+}
@@ -0,0 +1,364 @@
+use std::io::Read;
+use std::ops::Range;
+
+use crate::mp3::{ID3v2, MP3Frame, MPEGFrameHeader, MPEGFrameSync, MPEGParseError, VBRFrame};
+use crate::Error;
+
+/// Bytes the streaming reader keeps buffered ahead of the current position.
+/// This is several times the longest frame, so that resynchronization can always look at the frame after a candidate.
+const WINDOW: usize = 16 * 1024;
+
+/// The outcome of looking for a frame at the start of some data.
+#[derive(Debug, PartialEq)]
+enum Scan {
+    /// A frame of this length starts here.
+    Frame(usize),
+    /// This many bytes are not part of any frame.
+    Skip(usize),
+    /// More data is needed to decide.
+    NeedMore,
+}
+
+/// Whether a frame can be confirmed at an index of the data.
+enum Candidate {
+    Frame(usize),
+    NotFrame,
+    NeedMore,
+}
+
+/// Looks for a frame at the start of the data, which is everything left of the input if `end` is set.
+/// Directly after skipped bytes a frame is only accepted if another frame (or the end of the input) follows it, since frame sync patterns also occur by chance inside audio data.
+fn scan(data: &[u8], end: bool, resyncing: bool) -> Scan {
+    match candidate(data, 0, end, resyncing) {
+        Candidate::Frame(length) => return Scan::Frame(length),
+        Candidate::NeedMore => return Scan::NeedMore,
+        Candidate::NotFrame => {}
+    }
+
+    for i in 1..data.len() {
+        match candidate(data, i, end, true) {
+            Candidate::Frame(_) | Candidate::NeedMore => return Scan::Skip(i),
+            Candidate::NotFrame => {}
+        }
+    }
+    Scan::Skip(data.len())
+}
+
+fn candidate(data: &[u8], i: usize, end: bool, confirm: bool) -> Candidate {
+    let rest = &data[i..];
+    if !MPEGFrameSync::has_frame_sync(rest) {
+        return match rest.len() < 4 && !end {
+            true => Candidate::NeedMore,
+            false => Candidate::NotFrame,
+        };
+    }
+
+    let length = match MPEGFrameHeader::parse(rest) {
+        Ok(header) => header.frame_length as usize,
+        Err(MPEGParseError::TruncatedFrame) if !end => return Candidate::NeedMore,
+        Err(_) => return Candidate::NotFrame,
+    };
+    // A free bitrate frame runs up to the next sync, which may not have been read yet.
+    if length == rest.len() && !end {
+        return Candidate::NeedMore;
+    }
+    if !confirm {
+        return Candidate::Frame(length);
+    }
+
+    let next = &rest[length..];
+    if next.len() < 4 && !end {
+        Candidate::NeedMore
+    } else if next.is_empty() || MPEGFrameSync::has_frame_sync(next) {
+        Candidate::Frame(length)
+    } else {
+        Candidate::NotFrame
+    }
+}
+
+/// Appends a skipped range, merging it with the previous one if they touch.
+fn push_skipped(skipped: &mut Vec<Range<usize>>, range: Range<usize>) {
+    match skipped.last_mut() {
+        Some(last) if last.end == range.start => last.end = range.end,
+        _ => skipped.push(range),
+    }
+}
+
+/// Iterates over the frames of MP3 data held in memory, yielding the byte offset and bytes of each frame without copying them.
+/// Data between frames that cannot be decoded is skipped and recorded.
+pub struct Frames<'a> {
+    data: &'a [u8],
+    position: usize,
+    resyncing: bool,
+    skipped: Vec<Range<usize>>,
+}
+
+impl<'a> Frames<'a> {
+    /// Starts iterating at the given offset, usually the end of the ID3v2 tag.
+    pub fn new(data: &'a [u8], start: usize) -> Frames<'a> {
+        Frames {
+            data,
+            position: start.min(data.len()),
+            resyncing: false,
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Byte ranges skipped so far while resynchronizing on the next frame.
+    pub fn skipped(&self) -> &[Range<usize>] {
+        &self.skipped
+    }
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = (usize, &'a [u8]);
+
+    fn next(&mut self) -> Option<(usize, &'a [u8])> {
+        while self.position < self.data.len() {
+            let start = self.position;
+            match scan(&self.data[start..], true, self.resyncing) {
+                Scan::Frame(length) => {
+                    self.position += length;
+                    self.resyncing = false;
+                    return Some((start, &self.data[start..start + length]));
+                }
+                Scan::Skip(length) => {
+                    push_skipped(&mut self.skipped, start..start + length);
+                    self.position += length;
+                    self.resyncing = true;
+                }
+                Scan::NeedMore => unreachable!("complete data never needs more"),
+            }
+        }
+        None
+    }
+}
+
+/// Reads MP3 frames one at a time from any reader, holding only a small window of the input in memory.
+/// The ID3v2 tag, if any, is read when the reader is created, along with the first frame to check whether it holds a VBR header.
+/// A VBR header frame is kept out of the frames the reader yields.
+pub struct FrameReader<R: Read> {
+    reader: R,
+    buffer: Vec<u8>,
+    /// Index in the buffer of the first unconsumed byte.
+    start: usize,
+    /// Offset in the input of the first unconsumed byte.
+    offset: usize,
+    end: bool,
+    resyncing: bool,
+    id3v2: Option<ID3v2>,
+    vbr_frame: Option<VBRFrame>,
+    /// The first frame, read ahead when it turned out not to be a VBR header frame.
+    pending: Option<Result<MP3Frame, Error>>,
+    skipped: Vec<Range<usize>>,
+    /// The bytes of the skipped ranges that have not been taken yet.
+    skipped_data: Vec<Vec<u8>>,
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(reader: R) -> Result<FrameReader<R>, Error> {
+        let mut frames = FrameReader {
+            reader,
+            buffer: Vec::new(),
+            start: 0,
+            offset: 0,
+            end: false,
+            resyncing: false,
+            id3v2: None,
+            vbr_frame: None,
+            pending: None,
+            skipped: Vec::new(),
+            skipped_data: Vec::new(),
+        };
+
+        frames.fill(ID3v2::HEADER_LENGTH)?;
+        if ID3v2::has_id3v2_tag(frames.available()) {
+            let size = ID3v2::tag_size(frames.available()).unwrap_or(0);
+            frames.fill(size)?;
+            let id3v2 = ID3v2::parse(frames.available())?;
+            frames.consume(id3v2.size as usize);
+            frames.id3v2 = Some(id3v2);
+        }
+
+        frames.pending = match frames.read_frame() {
+            Some(Ok(frame)) => match VBRFrame::parse(frame) {
+                Ok(vbr_frame) => {
+                    frames.vbr_frame = Some(vbr_frame);
+                    None
+                }
+                Err(frame) => Some(Ok(frame)),
+            },
+            other => other,
+        };
+        Ok(frames)
+    }
+
+    /// The ID3v2 tag at the start of the input.
+    pub fn id3v2(&self) -> Option<&ID3v2> {
+        self.id3v2.as_ref()
+    }
+
+    /// Takes the ID3v2 tag, leaving `None` in its place.
+    pub fn take_id3v2(&mut self) -> Option<ID3v2> {
+        self.id3v2.take()
+    }
+
+    /// The first frame, if it holds a Xing/Info or VBRI header instead of audio.
+    pub fn vbr_frame(&self) -> Option<&VBRFrame> {
+        self.vbr_frame.as_ref()
+    }
+
+    /// Takes the VBR header frame, leaving `None` in its place.
+    pub fn take_vbr_frame(&mut self) -> Option<VBRFrame> {
+        self.vbr_frame.take()
+    }
+
+    /// Byte ranges skipped so far while resynchronizing on the next frame.
+    pub fn skipped(&self) -> &[Range<usize>] {
+        &self.skipped
+    }
+
+    /// Takes the bytes skipped since the last call, one entry per skipped range.
+    /// Everything skipped before a frame has been read by the time the frame is returned.
+    pub fn take_skipped_data(&mut self) -> Vec<Vec<u8>> {
+        std::mem::take(&mut self.skipped_data)
+    }
+
+    /// Number of bytes read from the input and consumed so far.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn available(&self) -> &[u8] {
+        &self.buffer[self.start..]
+    }
+
+    fn consume(&mut self, length: usize) {
+        self.start += length;
+        self.offset += length;
+    }
+
+    /// Reads until at least `length` unconsumed bytes are buffered or the input ends.
+    fn fill(&mut self, length: usize) -> Result<(), Error> {
+        if self.start >= WINDOW {
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+
+        let mut chunk = [0; 4096];
+        while !self.end && self.available().len() < length {
+            match self.reader.read(&mut chunk) {
+                Ok(0) => self.end = true,
+                Ok(read) => self.buffer.extend_from_slice(&chunk[..read]),
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(error) => {
+                    self.end = true;
+                    self.buffer.clear();
+                    return Err(Error::Io(error));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Option<Result<MP3Frame, Error>> {
+        loop {
+            if let Err(error) = self.fill(WINDOW) {
+                return Some(Err(error));
+            }
+            if self.available().is_empty() {
+                return None;
+            }
+
+            // Once the window is full, a frame that still needs more data is longer than any real frame, so the window is treated as complete.
+            let scan = match scan(self.available(), self.end, self.resyncing) {
+                Scan::NeedMore => scan(self.available(), true, self.resyncing),
+                scan => scan,
+            };
+            match scan {
+                Scan::Frame(length) => {
+                    let frame = MP3Frame::parse(&self.available()[..length]).map(|mut frame| {
+                        frame.offset = self.offset;
+                        frame
+                    });
+                    self.consume(length);
+                    self.resyncing = false;
+                    return Some(frame.map_err(|error| Error::MPEG {
+                        error,
+                        offset: self.offset - length,
+                        frame: None,
+                    }));
+                }
+                Scan::Skip(length) => {
+                    let merged = self
+                        .skipped
+                        .last()
+                        .is_some_and(|last| last.end == self.offset);
+                    push_skipped(&mut self.skipped, self.offset..self.offset + length);
+                    let data = &self.buffer[self.start..self.start + length];
+                    match (merged, self.skipped_data.last_mut()) {
+                        (true, Some(last)) => last.extend_from_slice(data),
+                        _ => self.skipped_data.push(data.to_vec()),
+                    }
+                    self.consume(length);
+                    self.resyncing = true;
+                }
+                Scan::NeedMore => unreachable!("complete data never needs more"),
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for FrameReader<R> {
+    type Item = Result<MP3Frame, Error>;
+
+    fn next(&mut self) -> Option<Result<MP3Frame, Error>> {
+        self.pending.take().or_else(|| self.read_frame())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mp3::test::{mp3_bytes, FRAME_LENGTH};
+
+    /// A reader that returns at most a few bytes per call, like a pipe or socket.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            let length = self.0.len().min(buffer.len()).min(7);
+            buffer[..length].copy_from_slice(&self.0[..length]);
+            self.0 = &self.0[length..];
+            Ok(length)
+        }
+    }
+
+    #[test]
+    fn slices_frames_without_copying() {
+        let bytes = mp3_bytes(3);
+        let frames: Vec<(usize, &[u8])> = Frames::new(&bytes, 0).collect();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1].0, FRAME_LENGTH);
+        assert!(std::ptr::eq(
+            frames[1].1,
+            &bytes[FRAME_LENGTH..2 * FRAME_LENGTH]
+        ));
+    }
+
+    #[test]
+    fn streams_same_frames_as_slice_iterator() {
+        let mut bytes = mp3_bytes(60)[100..].to_vec();
+        bytes.extend(b"junk between files");
+        bytes.extend(mp3_bytes(40));
+
+        let mut sliced = Frames::new(&bytes, 0);
+        let offsets: Vec<usize> = sliced.by_ref().map(|(offset, _)| offset).collect();
+
+        let mut reader = FrameReader::new(Trickle(&bytes)).unwrap();
+        let streamed: Vec<usize> = reader.by_ref().map(|frame| frame.unwrap().offset).collect();
+        assert_eq!(streamed, offsets);
+        assert_eq!(reader.skipped(), sliced.skipped());
+        assert_eq!(reader.offset(), bytes.len());
+    }
+}
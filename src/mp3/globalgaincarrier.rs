@@ -0,0 +1,101 @@
+use crate::mp3::{sideinfo::read_bits, MP3};
+use crate::stego::BitCarrier;
+
+/// Cost of changing the gain of a granule that codes no spectral values, where the gain has no audible effect.
+const SILENT_GRANULE_COST: f64 = 0.01;
+
+/// Carries one payload bit per granule and channel in the least significant bit of the Layer III global_gain field.
+/// Changing that bit scales the whole granule by about 1.5 dB. The cost is a loudness heuristic rather than a psychoacoustic model:
+/// it grows with the granule's gain, and granules that code no spectral values cost almost nothing.
+pub struct GlobalGainCarrier {
+    positions: Vec<GlobalGainPosition>,
+}
+
+struct GlobalGainPosition {
+    frame: usize,
+    bit_offset: usize,
+    cost: f64,
+}
+
+impl GlobalGainCarrier {
+    /// Locates the global_gain field of every granule and channel in the file.
+    pub fn new(mp3: &MP3) -> GlobalGainCarrier {
+        let positions = mp3
+            .frames
+            .iter()
+            .enumerate()
+            .filter_map(|(frame, data)| Some((frame, data.side_info()?)))
+            .flat_map(|(frame, side_info)| {
+                side_info.granules.into_iter().map(move |granule| {
+                    let cost = if granule.big_values == 0 {
+                        SILENT_GRANULE_COST
+                    } else {
+                        granule.global_gain as f64 / 64.0
+                    };
+                    GlobalGainPosition {
+                        frame,
+                        bit_offset: granule.global_gain_lsb_offset(),
+                        cost,
+                    }
+                })
+            })
+            .collect();
+
+        GlobalGainCarrier { positions }
+    }
+}
+
+impl BitCarrier<MP3> for GlobalGainCarrier {
+    fn positions(&self, _cover: &MP3) -> usize {
+        self.positions.len()
+    }
+
+    fn read_bit(&self, cover: &MP3, position: usize) -> bool {
+        let position = &self.positions[position];
+        read_bits(&cover.frames[position.frame].data, position.bit_offset, 1) == 1
+    }
+
+    fn write_bit(&self, cover: &mut MP3, position: usize, bit: bool) {
+        let position = &self.positions[position];
+        cover.frames[position.frame].set_bit(position.bit_offset, bit);
+    }
+
+    fn cost(&self, _cover: &MP3, position: usize) -> f64 {
+        self.positions[position].cost
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mp3::test::mp3_bytes;
+
+    #[test]
+    fn finds_four_positions_per_stereo_frame() {
+        let mp3 = MP3::parse(&mp3_bytes(10)).unwrap();
+        let carrier = GlobalGainCarrier::new(&mp3);
+        assert_eq!(carrier.positions(&mp3), 40);
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn round_trips_payload_with_trellis_coding() {
+        use crate::stego::{self, Coding, EmbedOptions, StegoKey};
+
+        let key = StegoKey::derive("hunter2");
+        let mut mp3 = MP3::parse(&mp3_bytes(200)).unwrap();
+        let carrier = GlobalGainCarrier::new(&mp3);
+        let options = EmbedOptions {
+            coding: Coding::Trellis,
+            ..EmbedOptions::default()
+        };
+        stego::embed(&mut mp3, &carrier, &key, b"secret", options).unwrap();
+
+        let mp3 = MP3::parse(&mp3.to_bytes()).unwrap();
+        let carrier = GlobalGainCarrier::new(&mp3);
+        assert_eq!(
+            stego::extract(&mp3, &carrier, &key).unwrap().payload,
+            b"secret"
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use crate::mp3::{Copyright, Original, MP3};
+use crate::stego::BitCarrier;
+
+/// Carries two payload bits per frame in the copyright and original bits of the frame header.
+/// Decoders ignore both bits, but encoders write the same values into every frame, so a stream whose flags change from frame to frame stands out to anyone comparing headers.
+pub struct HeaderFlagCarrier;
+
+impl BitCarrier<MP3> for HeaderFlagCarrier {
+    fn positions(&self, cover: &MP3) -> usize {
+        2 * cover.frames.len()
+    }
+
+    fn read_bit(&self, cover: &MP3, position: usize) -> bool {
+        let header = &cover.frames[position / 2].header;
+        match position % 2 {
+            0 => header.copyright == Copyright::Protected,
+            _ => header.original == Original::Original,
+        }
+    }
+
+    fn write_bit(&self, cover: &mut MP3, position: usize, bit: bool) {
+        let frame = &mut cover.frames[position / 2];
+        match (position % 2, bit) {
+            (0, true) => frame.set_copyright(Copyright::Protected),
+            (0, false) => frame.set_copyright(Copyright::Unprotected),
+            (_, true) => frame.set_original(Original::Original),
+            (_, false) => frame.set_original(Original::Copy),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mp3::test::mp3_bytes;
+
+    #[test]
+    fn writes_both_flags_to_frame_bytes() {
+        let mut mp3 = MP3::parse(&mp3_bytes(2)).unwrap();
+        let flags = mp3.frames[1].data[3] & 0x0C;
+        for position in 2..4 {
+            let bit = !HeaderFlagCarrier.read_bit(&mp3, position);
+            HeaderFlagCarrier.write_bit(&mut mp3, position, bit);
+        }
+
+        let mp3 = MP3::parse(&mp3.to_bytes()).unwrap();
+        assert_eq!(mp3.frames[1].data[3] & 0x0C, flags ^ 0x0C);
+        assert_eq!(mp3.frames[0].data[3] & 0x0C, flags);
+    }
+}
@@ -1,66 +1,120 @@
+use crate::Error;
+
+/// An ID3v2 tag at the start of a file.
 pub struct ID3v2 {
     pub version: u16,
     pub size: u32,
     pub flags: u8,
     pub frames: Vec<ID3v2Frame>,
+    /// Number of zero bytes between the last frame and the end of the tag.
+    pub padding: u32,
+    pub raw_tag: Vec<u8>,
 }
 
 impl ID3v2 {
-    pub fn parse(data: Vec<u8>) -> Result<ID3v2, ID3v2ParseError> {
-        let identifier = String::from_utf8(data[0..3].to_vec()).unwrap();
-        if identifier != "ID3" {
-            println!("ID3v2 tag did is missing ID3 identifier.");
-            return Err(ID3v2ParseError::InvalidHeader);
+    /// Length of the tag header, which is also the length of each frame header.
+    pub const HEADER_LENGTH: usize = 10;
+
+    pub fn parse(data: &[u8]) -> Result<ID3v2, Error> {
+        if !ID3v2::has_id3v2_tag(data) {
+            return Err(ID3v2::error(ID3v2ParseError::InvalidHeader, 0, None));
+        }
+        if data.len() < ID3v2::HEADER_LENGTH {
+            return Err(ID3v2::error(ID3v2ParseError::Truncated, data.len(), None));
         }
 
         let version = u16::from_be_bytes([data[3], data[4]]);
         let flags = data[5];
+        let size = ID3v2::tag_size(data).unwrap_or(0) as u32;
 
-        // the most significant bit in each Byte is set to 0 and ignored. Only remaining 7 bits are used. The reason is to avoid mismatch with audio frame header which has the first synchro Byte FF).
-        // Eg. TAG len 257 is encoded as 00 00 02 01.
-        let mut size = 0;
-        for i in 0..4 {
-            size <<= 7;
-            size |= (data[6 + i]) as u32;
-        }
-        size += 10;
+        let tag = data
+            .get(0..size as usize)
+            .ok_or_else(|| ID3v2::error(ID3v2ParseError::Truncated, data.len(), None))?;
 
         let mut frames = Vec::new();
-        let mut current_index = 10;
+        let mut current_index = ID3v2::HEADER_LENGTH;
 
-        println!("ID3v2 Metdata:");
-        while current_index < size as usize {
-            let frame = ID3v2Frame::parse(data[current_index..].to_vec());
+        // Frames end where the padding begins, since a frame ID never starts with a zero byte.
+        while current_index < tag.len() && tag[current_index] != 0 {
+            let frame = ID3v2Frame::parse(&tag[current_index..])
+                .map_err(|error| ID3v2::error(error, current_index, Some(frames.len())))?;
             current_index += frame.size as usize;
-
-            println!(
-                " - {:?}: {}",
-                frame.id,
-                String::from_utf8(frame.data.clone()).unwrap(),
-            );
-
             frames.push(frame);
         }
+        let padding = tag.len().saturating_sub(current_index) as u32;
 
         Ok(ID3v2 {
             version,
             size,
             flags,
             frames,
+            padding,
+            raw_tag: tag.to_vec(),
         })
     }
 
-    pub fn has_id3v2_tag(data: Vec<u8>) -> bool {
-        let identifier = String::from_utf8(data[0..3].to_vec()).unwrap();
-        identifier == "ID3"
+    pub fn has_id3v2_tag(data: &[u8]) -> bool {
+        data.starts_with(b"ID3")
+    }
+
+    /// Returns the total length of the tag, header included, from the tag header at the start of the data.
+    pub fn tag_size(data: &[u8]) -> Option<usize> {
+        let header = data.get(0..ID3v2::HEADER_LENGTH)?;
+
+        // the most significant bit in each Byte is set to 0 and ignored. Only remaining 7 bits are used. The reason is to avoid mismatch with audio frame header which has the first synchro Byte FF).
+        // Eg. TAG len 257 is encoded as 00 00 02 01.
+        let mut size = 0;
+        for byte in &header[6..10] {
+            size <<= 7;
+            size |= (byte & 0x7F) as usize;
+        }
+        Some(size + ID3v2::HEADER_LENGTH)
+    }
+
+    fn error(error: ID3v2ParseError, offset: usize, frame: Option<usize>) -> Error {
+        Error::ID3v2 {
+            error,
+            offset,
+            frame,
+        }
     }
 }
 
-#[derive(Debug)]
+/// Error type for ID3v2 parsing.
+#[derive(Debug, PartialEq)]
 pub enum ID3v2ParseError {
     InvalidHeader,
+    Truncated,
+    FrameTooLarge { size: u32 },
+}
+
+impl ID3v2ParseError {
+    pub fn description(&self) -> String {
+        match self {
+            ID3v2ParseError::InvalidHeader => {
+                String::from("ID3v2 tag header expected but not found.")
+            }
+            ID3v2ParseError::Truncated => {
+                String::from("ID3v2 tag extends past the end of the data.")
+            }
+            ID3v2ParseError::FrameTooLarge { size } => {
+                format!(
+                    "ID3v2 frame of {} bytes extends past the end of the tag.",
+                    size
+                )
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ID3v2ParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(&self.description())
+    }
 }
 
+impl std::error::Error for ID3v2ParseError {}
+
 pub struct ID3v2Frame {
     pub id: FrameIdentifier,
     pub size: u32,
@@ -69,18 +123,25 @@ pub struct ID3v2Frame {
 }
 
 impl ID3v2Frame {
-    pub fn parse(data: Vec<u8>) -> ID3v2Frame {
-        let id = FrameIdentifier::parse(data[0..4].to_vec());
-        let size = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) + 10;
+    /// Parses the frame at the start of the data, which must end where the tag does.
+    pub fn parse(data: &[u8]) -> Result<ID3v2Frame, ID3v2ParseError> {
+        if data.len() < ID3v2::HEADER_LENGTH {
+            return Err(ID3v2ParseError::Truncated);
+        }
+
+        let id = FrameIdentifier::parse(&data[0..4]);
+        let size = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
         let flags = u16::from_be_bytes([data[8], data[9]]);
-        let data = data[10..size as usize].to_vec();
+        let body = data
+            .get(ID3v2::HEADER_LENGTH..ID3v2::HEADER_LENGTH + size as usize)
+            .ok_or(ID3v2ParseError::FrameTooLarge { size })?;
 
-        ID3v2Frame {
+        Ok(ID3v2Frame {
             id,
-            size,
+            size: size + ID3v2::HEADER_LENGTH as u32,
             flags,
-            data,
-        }
+            data: body.to_vec(),
+        })
     }
 }
 
@@ -102,8 +163,8 @@ pub enum FrameIdentifier {
 }
 
 impl FrameIdentifier {
-    pub fn parse(bytes: Vec<u8>) -> FrameIdentifier {
-        let id = String::from_utf8(bytes[0..4].to_vec()).unwrap();
+    pub fn parse(bytes: &[u8]) -> FrameIdentifier {
+        let id = String::from_utf8_lossy(bytes).to_string();
         match id.as_str() {
             "TRCK" => FrameIdentifier::TrackNumber,
             "TENC" => FrameIdentifier::EncodedBy,
@@ -120,4 +181,23 @@ impl FrameIdentifier {
             _ => FrameIdentifier::Other(id.to_string()),
         }
     }
+
+    /// Returns the four-character frame ID this identifier was parsed from.
+    pub fn code(&self) -> &str {
+        match self {
+            FrameIdentifier::TrackNumber => "TRCK",
+            FrameIdentifier::EncodedBy => "TENC",
+            FrameIdentifier::URL => "WXXX",
+            FrameIdentifier::FrameIdentifier => "TCOP",
+            FrameIdentifier::OriginalArtist => "TOPE",
+            FrameIdentifier::Composer => "TCOM",
+            FrameIdentifier::Genre => "TCON",
+            FrameIdentifier::Comments => "COMM",
+            FrameIdentifier::Year => "TYER",
+            FrameIdentifier::Album => "TALB",
+            FrameIdentifier::Artist => "TPE1",
+            FrameIdentifier::SongName => "TIT2",
+            FrameIdentifier::Other(id) => id,
+        }
+    }
 }
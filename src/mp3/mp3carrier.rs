@@ -0,0 +1,125 @@
+use crate::carrier::{Carrier, Cover};
+use crate::mp3::{self, HeaderFlagCarrier, Method, PrivateBitCarrier, MP3};
+use crate::stego::{BitCarrier, Capacity, EmbedOptions};
+use crate::Error;
+
+/// The MP3 format, embedding in frame private bits or in the copyright and original bits of frame headers.
+/// The global_gain fields of Layer III granules are only estimated: changing them audibly changes the loudness of the granule, so they are kept out of embedding until a spectral carrier exists.
+pub struct MP3Carrier;
+
+/// A parsed MP3 file with one of its carriers selected.
+pub struct MP3Cover {
+    pub mp3: MP3,
+    carrier: MP3CoverCarrier,
+}
+
+enum MP3CoverCarrier {
+    PrivateBit(PrivateBitCarrier),
+    HeaderFlags(HeaderFlagCarrier),
+}
+
+impl MP3Cover {
+    fn carrier(&self) -> &dyn BitCarrier<MP3> {
+        match &self.carrier {
+            MP3CoverCarrier::PrivateBit(carrier) => carrier,
+            MP3CoverCarrier::HeaderFlags(carrier) => carrier,
+        }
+    }
+}
+
+impl Carrier for MP3Carrier {
+    fn name(&self) -> &'static str {
+        "mp3"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &["private-bit", "header-flags"]
+    }
+
+    fn capacity_methods(&self) -> &'static [&'static str] {
+        &[
+            "private-bit",
+            "header-flags",
+            "ancillary-bits",
+            "sign-bits",
+            "global-gain",
+            "tag-padding",
+        ]
+    }
+
+    fn parse(&self, data: &[u8], method: &str) -> Result<Box<dyn Cover>, Error> {
+        let mp3 = MP3::parse(data)?;
+        let carrier = match Method::parse(method) {
+            Some(Method::PrivateBit) => MP3CoverCarrier::PrivateBit(PrivateBitCarrier),
+            Some(Method::HeaderFlags) => MP3CoverCarrier::HeaderFlags(HeaderFlagCarrier),
+            _ => {
+                return Err(Error::UnsupportedMethod {
+                    format: self.name(),
+                    method: method.to_string(),
+                })
+            }
+        };
+        Ok(Box::new(MP3Cover { mp3, carrier }))
+    }
+
+    /// Covers every method counted by [`mp3::capacity`], including those that cannot embed yet.
+    fn capacity(
+        &self,
+        data: &[u8],
+        method: &str,
+        options: EmbedOptions,
+    ) -> Result<Option<Capacity>, Error> {
+        let method = Method::parse(method).ok_or_else(|| Error::UnsupportedMethod {
+            format: self.name(),
+            method: method.to_string(),
+        })?;
+        Ok(mp3::capacity(&MP3::parse(data)?, method, options))
+    }
+}
+
+impl Cover for MP3Cover {
+    fn positions(&self) -> usize {
+        self.carrier().positions(&self.mp3)
+    }
+
+    fn read_bit(&self, position: usize) -> bool {
+        self.carrier().read_bit(&self.mp3, position)
+    }
+
+    fn write_bit(&mut self, position: usize, bit: bool) {
+        match &self.carrier {
+            MP3CoverCarrier::PrivateBit(carrier) => carrier.write_bit(&mut self.mp3, position, bit),
+            MP3CoverCarrier::HeaderFlags(carrier) => {
+                carrier.write_bit(&mut self.mp3, position, bit)
+            }
+        }
+    }
+
+    fn cost(&self, position: usize) -> f64 {
+        self.carrier().cost(&self.mp3, position)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.mp3.to_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mp3::test::mp3_bytes;
+
+    #[test]
+    fn estimates_capacity_of_every_method() {
+        let names: Vec<&str> = Method::ALL.iter().map(Method::name).collect();
+        assert_eq!(MP3Carrier.capacity_methods(), names);
+
+        let bytes = mp3_bytes(10);
+        let capacity = MP3Carrier.capacity(&bytes, "header-flags", EmbedOptions::default());
+        assert_eq!(capacity.unwrap().unwrap().raw_bits, 20);
+        assert!(matches!(
+            MP3Carrier.parse(&bytes, "global-gain"),
+            Err(Error::UnsupportedMethod { .. })
+        ));
+    }
+}
@@ -0,0 +1,191 @@
+use std::ops::Range;
+
+use crate::mp3::vbrheader::LAME_CRC;
+use crate::mp3::{Frames, ID3v2, MP3Frame, MPEGFrameHeader, MPEGParseError, SideInfo, VBRFrame};
+use crate::Error;
+
+/// A view of an MP3 file that borrows its bytes from one buffer instead of copying each frame.
+/// The buffer can be a memory map, and header changes are patched into it in place, so embedding with a header-bit carrier writes only the bytes that change.
+/// Call [`MP3Ref::update_vbr_frame`] after embedding to keep the LAME checksums in line with the changed frames.
+pub struct MP3Ref<'a> {
+    data: &'a mut [u8],
+    pub id3v2: Option<ID3v2>,
+    /// Byte ranges of the buffer that were skipped while resynchronizing on the next frame.
+    pub skipped: Vec<Range<usize>>,
+    /// Offset and parsed header of each frame.
+    frames: Vec<(usize, MPEGFrameHeader)>,
+    /// A copy of the first frame, if it holds a VBR header. It is kept out of the indexed frames.
+    vbr_frame: Option<VBRFrame>,
+}
+
+/// A frame borrowed from the buffer of an [`MP3Ref`].
+pub struct FrameRef<'a> {
+    pub header: &'a MPEGFrameHeader,
+    pub data: &'a [u8],
+    /// Byte offset of the frame within the buffer.
+    pub offset: usize,
+}
+
+impl FrameRef<'_> {
+    /// Returns the side information of this frame, if it is a Layer III frame.
+    pub fn side_info(&self) -> Option<SideInfo> {
+        SideInfo::parse(self.header, self.data)
+    }
+}
+
+impl<'a> MP3Ref<'a> {
+    /// Indexes the frames of an MP3 file held in the buffer, skipping over any data between frames that cannot be decoded.
+    pub fn parse(data: &'a mut [u8]) -> Result<MP3Ref<'a>, Error> {
+        let id3v2 = if ID3v2::has_id3v2_tag(data) {
+            Some(ID3v2::parse(data)?)
+        } else {
+            None
+        };
+
+        let start = id3v2.as_ref().map_or(0, |id3v2| id3v2.size as usize);
+        let mut slices = Frames::new(data, start);
+        let mut frames = slices
+            .by_ref()
+            .map(|(offset, frame)| {
+                MPEGFrameHeader::parse(frame)
+                    .map(|header| (offset, header))
+                    .map_err(|error| Error::MPEG {
+                        error,
+                        offset,
+                        frame: None,
+                    })
+            })
+            .collect::<Result<Vec<(usize, MPEGFrameHeader)>, Error>>()?;
+        let skipped = slices.skipped().to_vec();
+
+        let mut vbr_frame = frames.first().and_then(|(offset, _)| {
+            let frame = MP3Frame::parse(&data[*offset..]).ok()?;
+            VBRFrame::parse(MP3Frame {
+                offset: *offset,
+                ..frame
+            })
+            .ok()
+        });
+        if vbr_frame.is_some() {
+            frames.remove(0);
+        }
+
+        if frames.is_empty() {
+            return Err(Error::MPEG {
+                error: MPEGParseError::NoFrameSync,
+                offset: start,
+                frame: None,
+            });
+        }
+
+        let mut mp3 = MP3Ref {
+            data,
+            id3v2,
+            skipped,
+            frames,
+            vbr_frame: None,
+        };
+        if let Some(vbr_frame) = &mut vbr_frame {
+            vbr_frame.check_music_crc(mp3.music_crc());
+        }
+        mp3.vbr_frame = vbr_frame;
+        Ok(mp3)
+    }
+
+    /// The first frame, if it holds a Xing/Info or VBRI header instead of audio.
+    pub fn vbr_frame(&self) -> Option<&VBRFrame> {
+        self.vbr_frame.as_ref()
+    }
+
+    /// Recomputes the LAME music CRC and tag CRC, if they were valid, after frames have been changed in place.
+    pub fn update_vbr_frame(&mut self) {
+        let music_crc = self.music_crc();
+        if let Some(vbr_frame) = &mut self.vbr_frame {
+            vbr_frame.update_music_crc(music_crc);
+            let frame = &vbr_frame.frame;
+            self.data[frame.offset..frame.offset + frame.data.len()].copy_from_slice(&frame.data);
+        }
+    }
+
+    fn music_crc(&self) -> u16 {
+        let mut digest = LAME_CRC.digest();
+        for frame in self.frames() {
+            digest.update(frame.data);
+        }
+        digest.finalize()
+    }
+
+    /// Returns the number of frames.
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn frame(&self, index: usize) -> FrameRef<'_> {
+        let (offset, header) = &self.frames[index];
+        FrameRef {
+            header,
+            data: &self.data[*offset..*offset + header.frame_length as usize],
+            offset: *offset,
+        }
+    }
+
+    pub fn frames(&self) -> impl Iterator<Item = FrameRef<'_>> {
+        (0..self.frames.len()).map(|index| self.frame(index))
+    }
+
+    /// Sets the private bit of a frame, writing the changed header (and checksum, if it was valid) straight into the buffer.
+    pub fn set_private_bit(&mut self, index: usize, private_bit: bool) {
+        let (offset, header) = &mut self.frames[index];
+        let frame = &mut self.data[*offset..*offset + header.frame_length as usize];
+        let crc_valid = header.verify_crc(frame) == Some(true);
+        header.set_private_bit(private_bit);
+        frame[0..4].copy_from_slice(&header.raw_header.to_be_bytes());
+        if crc_valid {
+            header.update_crc(frame);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mp3::test::{mp3_bytes, FRAME_LENGTH};
+    use crate::mp3::vbrheader::test::lame_mp3_bytes;
+    use crate::mp3::MP3;
+
+    #[test]
+    fn borrows_frames_from_buffer() {
+        let mut bytes = mp3_bytes(5);
+        let expected = bytes.clone();
+        let mp3 = MP3Ref::parse(&mut bytes).unwrap();
+
+        assert_eq!(mp3.frame_count(), 5);
+        let frame = mp3.frame(2);
+        assert_eq!(frame.offset, 2 * FRAME_LENGTH);
+        assert_eq!(frame.data, &expected[2 * FRAME_LENGTH..3 * FRAME_LENGTH]);
+    }
+
+    #[test]
+    fn patches_header_in_place() {
+        let mut bytes = mp3_bytes(5);
+        let mut mp3 = MP3Ref::parse(&mut bytes).unwrap();
+        mp3.set_private_bit(3, true);
+        assert!(mp3.frame(3).header.private_bit);
+
+        let mut expected = MP3::parse(&mp3_bytes(5)).unwrap();
+        expected.frames[3].set_private_bit(true);
+        assert_eq!(bytes, expected.to_bytes());
+    }
+
+    #[test]
+    fn updates_lame_checksums_in_place() {
+        let mut bytes = lame_mp3_bytes(5);
+        let mut mp3 = MP3Ref::parse(&mut bytes).unwrap();
+        assert_eq!(mp3.frame_count(), 5);
+        mp3.set_private_bit(3, true);
+        mp3.update_vbr_frame();
+
+        let parsed = MP3::parse(&bytes).unwrap();
+        assert_eq!(parsed.vbr_frame.unwrap().music_crc_valid, Some(true));
+    }
+}
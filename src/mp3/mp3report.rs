@@ -0,0 +1,375 @@
+use serde::Serialize;
+
+use crate::mp3::mpegframeheader::{
+    CRCProtection, Copyright, FrameBitrate, FramePadding, MP3ChannelMode, MP3Emphasis, MPEGLayer,
+    MPEGVersion, Original,
+};
+use crate::mp3::{MP3Frame, VBRHeader, XingHeader, MP3};
+
+/// A serializable description of an MP3 file's structure, for scripting against and for spotting anything unusual.
+#[derive(Serialize)]
+pub struct MP3Report {
+    pub length: usize,
+    pub id3v2: Option<TagReport>,
+    pub vbr_header: Option<VBRHeader>,
+    pub frame_count: usize,
+    pub encoding: Encoding,
+    pub duration_seconds: f64,
+    pub frames: Vec<FrameReport>,
+    pub anomalies: Vec<Anomaly>,
+}
+
+#[derive(Serialize)]
+pub struct TagReport {
+    pub version: String,
+    pub size: u32,
+    pub flags: u8,
+    pub padding: u32,
+    pub frames: Vec<TagFrameReport>,
+}
+
+#[derive(Serialize)]
+pub struct TagFrameReport {
+    pub id: String,
+    pub size: u32,
+}
+
+/// Whether every frame uses the same bitrate.
+#[derive(Serialize, Debug, PartialEq)]
+pub enum Encoding {
+    #[serde(rename = "CBR")]
+    ConstantBitrate,
+    #[serde(rename = "VBR")]
+    VariableBitrate,
+}
+
+#[derive(Serialize)]
+pub struct FrameReport {
+    pub index: usize,
+    pub offset: usize,
+    pub length: u32,
+    pub version: &'static str,
+    pub layer: &'static str,
+    /// Bitrate in kbps, or `None` for free-format frames.
+    pub bitrate: Option<u32>,
+    pub sample_rate: u32,
+    pub channel_mode: &'static str,
+    pub padding: bool,
+    pub crc: bool,
+    /// Whether the stored checksum matches the frame, or `None` if the frame is unprotected or the checksum cannot be checked.
+    pub crc_valid: Option<bool>,
+    pub private: bool,
+    pub copyright: bool,
+    pub original: bool,
+}
+
+/// Something in the file that a well-formed encoder would not produce.
+#[derive(Serialize)]
+pub struct Anomaly {
+    pub offset: usize,
+    pub frame: Option<usize>,
+    pub description: String,
+}
+
+impl Encoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::ConstantBitrate => "CBR",
+            Encoding::VariableBitrate => "VBR",
+        }
+    }
+}
+
+impl MP3Report {
+    pub fn new(mp3: &MP3) -> MP3Report {
+        let frames: Vec<FrameReport> = mp3
+            .frames
+            .iter()
+            .enumerate()
+            .map(|(index, frame)| FrameReport::new(index, frame))
+            .collect();
+
+        let encoding = match frames
+            .windows(2)
+            .all(|pair| pair[0].bitrate == pair[1].bitrate)
+        {
+            true => Encoding::ConstantBitrate,
+            false => Encoding::VariableBitrate,
+        };
+        let duration_seconds = mp3
+            .frames
+            .iter()
+            .map(|frame| {
+                frame.header.samples_per_frame() as f64 / frame.header.sample_rate_hz() as f64
+            })
+            .sum();
+
+        MP3Report {
+            length: mp3.length,
+            id3v2: mp3.id3v2.as_ref().map(|id3v2| TagReport {
+                version: format!("2.{}.{}", id3v2.version >> 8, id3v2.version & 0xFF),
+                size: id3v2.size,
+                flags: id3v2.flags,
+                padding: id3v2.padding,
+                frames: id3v2
+                    .frames
+                    .iter()
+                    .map(|frame| TagFrameReport {
+                        id: frame.id.code().to_string(),
+                        size: frame.size,
+                    })
+                    .collect(),
+            }),
+            vbr_header: mp3
+                .vbr_frame
+                .as_ref()
+                .map(|vbr_frame| vbr_frame.header.clone()),
+            frame_count: frames.len(),
+            encoding,
+            duration_seconds,
+            anomalies: MP3Report::anomalies(mp3),
+            frames,
+        }
+    }
+
+    fn anomalies(mp3: &MP3) -> Vec<Anomaly> {
+        let mut anomalies: Vec<Anomaly> = mp3
+            .skipped
+            .iter()
+            .map(|range| Anomaly {
+                offset: range.start,
+                frame: None,
+                description: format!("{} bytes skipped while resynchronizing", range.len()),
+            })
+            .collect();
+
+        let first = mp3.frames.first().map(|frame| &frame.header);
+        for (index, frame) in mp3.frames.iter().enumerate() {
+            let header = &frame.header;
+            let mut anomaly = |description: &str| {
+                anomalies.push(Anomaly {
+                    offset: frame.offset,
+                    frame: Some(index),
+                    description: description.to_string(),
+                })
+            };
+
+            if header.version == MPEGVersion::VersionReserved {
+                anomaly("reserved MPEG version");
+            }
+            if header.layer == MPEGLayer::Reserved {
+                anomaly("reserved layer");
+            }
+            if let MP3Emphasis::Reserved = header.emphasis {
+                anomaly("reserved emphasis");
+            }
+            if header.bitrate == FrameBitrate::Free {
+                anomaly("free-format bitrate");
+            }
+            if header.private_bit {
+                anomaly("private bit set");
+            }
+            if header.verify_crc(&frame.data) == Some(false) {
+                anomaly("checksum does not match frame");
+            }
+            if let Some(first) = first {
+                if header.version != first.version || header.layer != first.layer {
+                    anomaly("MPEG version or layer differs from the first frame");
+                }
+                if header.sample_rate != first.sample_rate {
+                    anomaly("sample rate differs from the first frame");
+                }
+            }
+        }
+
+        anomalies.sort_by_key(|anomaly| anomaly.offset);
+        anomalies
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("reports contain only serializable values")
+    }
+
+    /// Formats the report as human-readable text with one table row per frame.
+    pub fn to_table(&self) -> String {
+        let mut lines = vec![format!("Length:   {} bytes", self.length)];
+        if let Some(tag) = &self.id3v2 {
+            lines.push(format!(
+                "ID3v{}:  {} bytes, {} frames, {} bytes padding",
+                tag.version,
+                tag.size,
+                tag.frames.len(),
+                tag.padding
+            ));
+            for frame in &tag.frames {
+                lines.push(format!("  {} ({} bytes)", frame.id, frame.size));
+            }
+        }
+        if let Some(header) = &self.vbr_header {
+            let name = match header {
+                VBRHeader::Xing(xing) if xing.vbr => "Xing",
+                VBRHeader::Xing(_) => "Info",
+                VBRHeader::VBRI(_) => "VBRI",
+            };
+            let frames = header
+                .frames()
+                .map_or(String::from("?"), |frames| frames.to_string());
+            lines.push(format!("{}:     {} frames recorded", name, frames));
+            if let VBRHeader::Xing(XingHeader {
+                lame: Some(lame), ..
+            }) = header
+            {
+                lines.push(format!(
+                    "  {} (delay {}, padding {})",
+                    lame.encoder, lame.delay, lame.padding
+                ));
+            }
+        }
+        lines.push(format!("Frames:   {}", self.frame_count));
+        lines.push(format!("Encoding: {}", self.encoding.name()));
+        lines.push(format!("Duration: {:.2} s", self.duration_seconds));
+
+        lines.push(String::new());
+        lines.push(format!(
+            "{:>7} {:>10} {:>6} {:<9} {:<9} {:>7} {:>6} {:<14} {:<3} {:<3} {:<3} {:<4} {:<4}",
+            "frame",
+            "offset",
+            "length",
+            "version",
+            "layer",
+            "kbps",
+            "hz",
+            "mode",
+            "pad",
+            "crc",
+            "prv",
+            "copy",
+            "orig"
+        ));
+        let flag = |set: bool| if set { "x" } else { "-" };
+        // Protected frames show whether their checksum is correct, or "?" where it cannot be checked.
+        let crc = |frame: &FrameReport| match (frame.crc, frame.crc_valid) {
+            (false, _) => "-",
+            (true, Some(true)) => "x",
+            (true, Some(false)) => "bad",
+            (true, None) => "?",
+        };
+        for frame in &self.frames {
+            lines.push(format!(
+                "{:>7} {:>10} {:>6} {:<9} {:<9} {:>7} {:>6} {:<14} {:<3} {:<3} {:<3} {:<4} {:<4}",
+                frame.index,
+                frame.offset,
+                frame.length,
+                frame.version,
+                frame.layer,
+                frame
+                    .bitrate
+                    .map_or(String::from("free"), |kbps| kbps.to_string()),
+                frame.sample_rate,
+                frame.channel_mode,
+                flag(frame.padding),
+                crc(frame),
+                flag(frame.private),
+                flag(frame.copyright),
+                flag(frame.original),
+            ));
+        }
+
+        if !self.anomalies.is_empty() {
+            lines.push(String::new());
+            lines.push(String::from("Anomalies:"));
+            for anomaly in &self.anomalies {
+                let frame = anomaly
+                    .frame
+                    .map_or(String::new(), |frame| format!(" (frame {})", frame));
+                lines.push(format!(
+                    "  at {}{}: {}",
+                    anomaly.offset, frame, anomaly.description
+                ));
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+impl FrameReport {
+    fn new(index: usize, frame: &MP3Frame) -> FrameReport {
+        let header = &frame.header;
+        FrameReport {
+            index,
+            offset: frame.offset,
+            length: header.frame_length,
+            version: match header.version {
+                MPEGVersion::Version1 => "MPEG-1",
+                MPEGVersion::Version2 => "MPEG-2",
+                MPEGVersion::Version2_5 => "MPEG-2.5",
+                MPEGVersion::VersionReserved => "reserved",
+            },
+            layer: match header.layer {
+                MPEGLayer::Layer1 => "Layer I",
+                MPEGLayer::Layer2 => "Layer II",
+                MPEGLayer::Layer3 => "Layer III",
+                MPEGLayer::Reserved => "reserved",
+            },
+            bitrate: match header.bitrate {
+                FrameBitrate::Bitrate(kbps) => Some(kbps),
+                FrameBitrate::Free => None,
+            },
+            sample_rate: header.sample_rate_hz(),
+            channel_mode: match header.channel_mode {
+                MP3ChannelMode::Stereo => "stereo",
+                MP3ChannelMode::JointStereo => "joint stereo",
+                MP3ChannelMode::DualChannel => "dual channel",
+                MP3ChannelMode::SingleChannel => "single channel",
+            },
+            padding: header.padding == FramePadding::Enabled,
+            crc: matches!(header.crc_protection, CRCProtection::Enabled { .. }),
+            crc_valid: header.verify_crc(&frame.data),
+            private: header.private_bit,
+            copyright: header.copyright == Copyright::Protected,
+            original: matches!(header.original, Original::Original),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mp3::test::{mp3_bytes, protected_mp3_bytes, FRAME_LENGTH};
+
+    #[test]
+    fn summarizes_constant_bitrate_file() {
+        let mp3 = MP3::parse(&mp3_bytes(38)).unwrap();
+        let report = MP3Report::new(&mp3);
+        assert_eq!(report.frame_count, 38);
+        assert_eq!(report.encoding, Encoding::ConstantBitrate);
+        assert!((report.duration_seconds - 0.9927).abs() < 0.001);
+        assert_eq!(report.frames[1].offset, FRAME_LENGTH);
+        assert!(report.anomalies.is_empty());
+    }
+
+    #[test]
+    fn reports_skipped_bytes_and_private_bits() {
+        let mut bytes = mp3_bytes(3);
+        bytes.splice(FRAME_LENGTH..FRAME_LENGTH, [0; 20]);
+        let mut mp3 = MP3::parse(&bytes).unwrap();
+        mp3.frames[2].set_private_bit(true);
+
+        let json = MP3Report::new(&mp3).to_json();
+        assert!(json.contains("\"description\": \"20 bytes skipped while resynchronizing\""));
+        assert!(json.contains("\"description\": \"private bit set\""));
+    }
+
+    #[test]
+    fn reports_bad_checksums() {
+        let mut bytes = protected_mp3_bytes(3);
+        bytes[FRAME_LENGTH + 10] ^= 0xFF;
+        let mp3 = MP3::parse(&bytes).unwrap();
+
+        let report = MP3Report::new(&mp3);
+        let valid: Vec<Option<bool>> = report.frames.iter().map(|frame| frame.crc_valid).collect();
+        assert_eq!(valid, [Some(true), Some(false), Some(true)]);
+        assert!(report.to_table().contains("checksum does not match frame"));
+    }
+}
@@ -1,11 +1,11 @@
-use self::copyright::Copyright;
-use self::crcprotection::CRCProtection;
-use self::framebitrate::FrameBitrate;
-use self::framepadding::FramePadding;
-use self::mpegframesync::MPEGFrameSync;
-use self::mpeglayer::MPEGLayer;
-use self::mpegversion::MPEGVersion;
-use self::samplerate::SampleRate;
+pub use self::copyright::Copyright;
+pub use self::crcprotection::CRCProtection;
+pub use self::framebitrate::FrameBitrate;
+pub use self::framepadding::FramePadding;
+pub use self::mpegframesync::MPEGFrameSync;
+pub use self::mpeglayer::MPEGLayer;
+pub use self::mpegversion::MPEGVersion;
+pub use self::samplerate::SampleRate;
 
 use super::mpegparserror::MPEGParseError;
 
@@ -52,9 +52,11 @@ const EMPHASIS_TABLE: [MP3Emphasis; 4] = [
     MP3Emphasis::CCITJ17,
 ];
 
+/// A parsed MPEG audio frame header.
+/// The header does not hold the frame bytes, so it is cheap to copy and can describe a frame in a borrowed buffer.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct MPEGFrameHeader {
     pub raw_header: u32,
-    pub frame_data: Vec<u8>,
     pub frame_length: u32,
     pub frame_sync: MPEGFrameSync,
     pub version: MPEGVersion,
@@ -72,7 +74,11 @@ pub struct MPEGFrameHeader {
 }
 
 impl MPEGFrameHeader {
-    pub fn parse(data: &Vec<u8>) -> Result<MPEGFrameHeader, MPEGParseError> {
+    pub fn parse(data: &[u8]) -> Result<MPEGFrameHeader, MPEGParseError> {
+        if data.len() < 6 {
+            return Err(MPEGParseError::TruncatedFrame);
+        }
+
         let raw_header = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
 
         let frame_sync = MPEGFrameSync::parse(raw_header)?;
@@ -101,11 +107,9 @@ impl MPEGFrameHeader {
                 // This iterates through the bytes until a valid frame sync is found, marking the frame length.
                 // Note that padding and CRC checksum byte count is not necessary if the frame length is calculated this way.
                 FrameBitrate::Free => {
-                    let i = 5;
-                    while i < data.len() {
-                        if MPEGFrameSync::has_frame_sync(&data[i..i + 4]) {
-                            break;
-                        }
+                    let mut i = 5;
+                    while i < data.len() && !MPEGFrameSync::has_frame_sync(&data[i..]) {
+                        i += 1;
                     }
                     i as u32
                 }
@@ -121,21 +125,18 @@ impl MPEGFrameHeader {
                         SampleRate::Hz32000 => 32000,
                     };
 
-                    let crc_checksum = match crc_protection {
-                        CRCProtection::Enabled { checksum } => 2,
-                        CRCProtection::Disabled => 0,
-                    };
-
-                    ((144 * bitrate * 1000) / sample_rate) + padding + crc_checksum
+                    // The checksum, if any, is part of the frame rather than added to it.
+                    ((144 * bitrate * 1000) / sample_rate) + padding
                 }
             }
         };
 
-        let frame_data = data[0..frame_length as usize].to_vec();
+        if frame_length as usize > data.len() {
+            return Err(MPEGParseError::TruncatedFrame);
+        }
 
         Ok(MPEGFrameHeader {
             raw_header,
-            frame_data,
             frame_length,
             frame_sync,
             version,
@@ -152,10 +153,98 @@ impl MPEGFrameHeader {
             original,
         })
     }
-}
 
-#[derive(Copy, Clone)]
+    /// Returns the sampling rate in Hz.
+    /// The sample rate index gives MPEG-1 rates, which are halved for MPEG-2 and quartered for MPEG-2.5.
+    pub fn sample_rate_hz(&self) -> u32 {
+        let hz = match self.sample_rate {
+            SampleRate::Hz44100 => 44100,
+            SampleRate::Hz48000 => 48000,
+            SampleRate::Hz32000 => 32000,
+        };
+        match self.version {
+            MPEGVersion::Version1 | MPEGVersion::VersionReserved => hz,
+            MPEGVersion::Version2 => hz / 2,
+            MPEGVersion::Version2_5 => hz / 4,
+        }
+    }
+
+    /// Returns the number of audio samples per channel coded in this frame.
+    pub fn samples_per_frame(&self) -> u32 {
+        match (self.layer, self.version) {
+            (MPEGLayer::Layer1, _) => 384,
+            (MPEGLayer::Layer3, MPEGVersion::Version2 | MPEGVersion::Version2_5) => 576,
+            _ => 1152,
+        }
+    }
+
+    /// Returns the length in bytes of the Layer III side information, which follows the header and checksum.
+    /// Returns `None` for Layers I and II, whose side information length depends on the bit allocation.
+    pub fn side_info_length(&self) -> Option<usize> {
+        if self.layer != MPEGLayer::Layer3 {
+            return None;
+        }
+        let mono = self.channel_mode == MP3ChannelMode::SingleChannel;
+        Some(match (self.version, mono) {
+            (MPEGVersion::Version1, true) => 17,
+            (MPEGVersion::Version1, false) => 32,
+            (_, true) => 9,
+            (_, false) => 17,
+        })
+    }
+
+    /// Checks the stored checksum against the frame bytes.
+    /// Returns `None` if the frame is not protected or its protected region is unknown.
+    pub fn verify_crc(&self, frame: &[u8]) -> Option<bool> {
+        match self.crc_protection {
+            CRCProtection::Enabled { checksum } => {
+                let computed = CRCProtection::compute(frame, self.side_info_length()?)?;
+                Some(computed == checksum)
+            }
+            CRCProtection::Disabled => None,
+        }
+    }
 
+    /// Recomputes the checksum after protected bits of the frame changed, writing it to the frame bytes.
+    /// Frames whose checksum cannot be computed are left unchanged.
+    pub fn update_crc(&mut self, frame: &mut [u8]) {
+        if let CRCProtection::Enabled { .. } = self.crc_protection {
+            let computed = self
+                .side_info_length()
+                .and_then(|protected| CRCProtection::compute(frame, protected));
+            if let Some(checksum) = computed {
+                self.crc_protection = CRCProtection::Enabled { checksum };
+                self.crc_protection.apply(self.raw_header, frame);
+            }
+        }
+    }
+
+    /// Sets the private bit, updating both the parsed field and the raw header.
+    /// The caller writes `raw_header` back to the frame bytes.
+    pub fn set_private_bit(&mut self, private_bit: bool) {
+        let result = self.raw_header & !PRIVATE_BIT;
+        self.raw_header = result | ((private_bit as u32) << PRIVATE_BIT_OFFSET);
+        self.private_bit = private_bit;
+    }
+
+    /// Sets the copyright bit, updating both the parsed field and the raw header.
+    /// The caller writes `raw_header` back to the frame bytes.
+    pub fn set_copyright(&mut self, copyright: Copyright) {
+        self.raw_header = copyright.apply(self.raw_header);
+        self.copyright = copyright;
+    }
+
+    /// Sets the original bit, updating both the parsed field and the raw header.
+    /// The caller writes `raw_header` back to the frame bytes.
+    pub fn set_original(&mut self, original: Original) {
+        let bit = ORIGINAL_TABLE.iter().position(|entry| *entry == original);
+        let result = self.raw_header & !ORIGINAL;
+        self.raw_header = result | (bit.unwrap() as u32) << ORIGINAL_OFFSET;
+        self.original = original;
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum MP3ModeExtension {
     Bands4To31,
     Bands8To31,
@@ -163,15 +252,13 @@ pub enum MP3ModeExtension {
     Bands16To31,
 }
 
-#[derive(Copy, Clone)]
-
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Original {
     Original,
     Copy,
 }
 
-#[derive(Copy, Clone)]
-
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum MP3Emphasis {
     None,
     FiftyFifteen,
@@ -179,8 +266,7 @@ pub enum MP3Emphasis {
     CCITJ17,
 }
 
-#[derive(Copy, Clone)]
-
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum MP3ChannelMode {
     Stereo,
     JointStereo,
@@ -18,8 +18,9 @@ impl Copyright {
         match copy {
             0b0 => Ok(Copyright::Unprotected),
             0b1 => Ok(Copyright::Protected),
-            _ => Err(MPEGParseError::GenericInvalidFrameHeader {
-                info: format!("Invalid copy permitted: {}", copy),
+            _ => Err(MPEGParseError::InvalidField {
+                field: "copyright bit",
+                value: copy,
             }),
         }
     }
@@ -35,6 +36,7 @@ impl Copyright {
     }
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
 
@@ -1,5 +1,10 @@
+use crc::{Crc, CRC_16_CMS};
+
 use crate::mp3::mpegparserror::MPEGParseError;
 
+/// CRC-16 with polynomial 0x8005 and initial value 0xFFFF, computed most significant bit first, as MPEG audio specifies.
+const MPEG_CRC: Crc<u16> = Crc::<u16>::new(&CRC_16_CMS);
+
 const CRC_PROTECTION_MASK: u32 = 0b00000000_00000001_00000000_00000000;
 const CRC_PROTECTION_MASK_OFFSET: u32 = 16;
 
@@ -16,33 +21,47 @@ impl CRCProtection {
     /// Given a 32-bit frame header and a reference to the frame data, parse the CRC protection and checksum, or throw an error if the CRC protection is invalid.
     pub fn parse(header: u32, frame_data: &[u8]) -> Result<CRCProtection, MPEGParseError> {
         let crc_protection = (header & CRC_PROTECTION_MASK) >> CRC_PROTECTION_MASK_OFFSET;
-        let checksum: u16 = u16::from_be_bytes([frame_data[4], frame_data[5]]);
         match crc_protection {
             0b1 => Ok(CRCProtection::Disabled),
-            0b0 => Ok(CRCProtection::Enabled { checksum }),
-            _ => Err(MPEGParseError::GenericInvalidFrameHeader {
-                info: format!("Invalid CRC protection: {}", crc_protection),
+            0b0 => match frame_data.get(4..6) {
+                Some(checksum) => Ok(CRCProtection::Enabled {
+                    checksum: u16::from_be_bytes([checksum[0], checksum[1]]),
+                }),
+                None => Err(MPEGParseError::TruncatedFrame),
+            },
+            _ => Err(MPEGParseError::InvalidField {
+                field: "CRC protection bit",
+                value: crc_protection,
             }),
         }
     }
 
+    /// Computes the checksum of a frame, which covers the last two header bytes and the given number of bytes after the checksum.
+    /// Returns `None` if the frame is too short to hold them.
+    pub fn compute(frame: &[u8], protected: usize) -> Option<u16> {
+        let covered = frame.get(6..6 + protected)?;
+        let mut digest = MPEG_CRC.digest();
+        digest.update(&frame[2..4]);
+        digest.update(covered);
+        Some(digest.finalize())
+    }
+
     /// Returns updated frame data and a new 32-bit frame header with the CRC protection bit set.
     /// If the CRC protection is enabled, the checksum is stored in the two bytes following the frame header.
-    pub fn apply(&self, header: u32, data: &mut Vec<u8>) -> u32 {
+    pub fn apply(&self, header: u32, data: &mut [u8]) -> u32 {
         let result = header & !CRC_PROTECTION_MASK;
         result
             | match self {
                 CRCProtection::Disabled => 0b00000000_00000001_00000000_00000000,
                 CRCProtection::Enabled { checksum } => {
-                    let length = data.len();
-                    data[0] = checksum.to_be_bytes()[0];
-                    data[1] = checksum.to_be_bytes()[1];
+                    data[4..6].copy_from_slice(&checksum.to_be_bytes());
                     0b00000000_00000000_00000000_00000000
                 }
             }
     }
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
 
@@ -83,14 +102,22 @@ mod test {
     fn applies_enabled_crc_protection() {
         let header = 0b00000000_00000000_00000000_00000000;
         let mut data = vec![0; 10];
-        data[0] = 0b00000000;
-        data[1] = 0b00000000;
         let result = CRCProtection::Enabled {
             checksum: 0b10000000_00000001,
         }
         .apply(header, &mut data);
         assert_eq!(result, 0b00000000_00000000_00000000_00000000);
-        assert_eq!(data[0], 0b10000000);
-        assert_eq!(data[1], 0b00000001);
+        assert_eq!(data[0..4], [0, 0, 0, 0]);
+        assert_eq!(data[4], 0b10000000);
+        assert_eq!(data[5], 0b00000001);
+    }
+
+    #[test]
+    fn computes_crc_over_header_and_protected_bytes() {
+        let mut frame = vec![0xFF, 0xFA, 0x90, 0x64, 0xAB, 0xCD];
+        frame.extend(b"123456789");
+        let expected = MPEG_CRC.checksum(&[&[0x90, 0x64][..], b"123456789"].concat());
+        assert_eq!(CRCProtection::compute(&frame, 9), Some(expected));
+        assert_eq!(CRCProtection::compute(&frame, 10), None);
     }
 }
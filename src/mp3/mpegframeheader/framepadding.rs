@@ -18,8 +18,9 @@ impl FramePadding {
         match padding {
             0b0 => Ok(FramePadding::Disabled),
             0b1 => Ok(FramePadding::Enabled),
-            _ => Err(MPEGParseError::GenericInvalidFrameHeader {
-                info: format!("Invalid padding: {}", padding),
+            _ => Err(MPEGParseError::InvalidField {
+                field: "padding bit",
+                value: padding,
             }),
         }
     }
@@ -35,6 +36,7 @@ impl FramePadding {
     }
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
 
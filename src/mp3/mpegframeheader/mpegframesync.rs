@@ -4,7 +4,7 @@ const FRAME_SYNC_MASK: u32 = 0b11111111_11100000_00000000_00000000;
 
 /// An enum that represents the MPEG frame sync.
 /// This is an 11-bit sequence of 1s that indicates the start of a frame.
-#[derive(Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum MPEGFrameSync {
     Sync,
 }
@@ -12,7 +12,7 @@ pub enum MPEGFrameSync {
 impl MPEGFrameSync {
     /// Given a 32-bit frame header, parse the MPEG frame sync, or throw an error if the frame sync is not found.
     pub fn parse(data: u32) -> Result<MPEGFrameSync, MPEGParseError> {
-        if ((data & FRAME_SYNC_MASK) != FRAME_SYNC_MASK) {
+        if (data & FRAME_SYNC_MASK) != FRAME_SYNC_MASK {
             return Err(MPEGParseError::NoFrameSync);
         }
 
@@ -31,10 +31,11 @@ impl MPEGFrameSync {
         }
 
         let header = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
-        ((header & FRAME_SYNC_MASK) == FRAME_SYNC_MASK)
+        (header & FRAME_SYNC_MASK) == FRAME_SYNC_MASK
     }
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
 
@@ -22,8 +22,9 @@ impl MPEGLayer {
             0b01 => Ok(MPEGLayer::Layer3),
             0b10 => Ok(MPEGLayer::Layer2),
             0b11 => Ok(MPEGLayer::Layer1),
-            _ => Err(MPEGParseError::GenericInvalidFrameHeader {
-                info: format!("Invalid MPEG layer ID: {}", layer_id),
+            _ => Err(MPEGParseError::InvalidField {
+                field: "layer ID",
+                value: layer_id,
             }),
         }
     }
@@ -42,6 +43,7 @@ impl MPEGLayer {
     }
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
 
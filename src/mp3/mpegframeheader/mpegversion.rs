@@ -22,8 +22,9 @@ impl MPEGVersion {
             0b01 => Ok(MPEGVersion::VersionReserved),
             0b10 => Ok(MPEGVersion::Version2),
             0b11 => Ok(MPEGVersion::Version1),
-            _ => Err(MPEGParseError::GenericInvalidFrameHeader {
-                info: format!("Invalid MPEG version ID: {}", version_id),
+            _ => Err(MPEGParseError::InvalidField {
+                field: "version ID",
+                value: version_id,
             }),
         }
     }
@@ -42,6 +43,7 @@ impl MPEGVersion {
     }
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
 
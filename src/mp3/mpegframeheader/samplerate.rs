@@ -22,8 +22,9 @@ impl SampleRate {
             0b00 => Ok(SampleRate::Hz44100),
             0b01 => Ok(SampleRate::Hz48000),
             0b10 => Ok(SampleRate::Hz32000),
-            _ => Err(MPEGParseError::GenericInvalidFrameHeader {
-                info: format!("Invalid sample rate index: {}", sample_rate_index),
+            _ => Err(MPEGParseError::InvalidField {
+                field: "sample rate index",
+                value: sample_rate_index,
             }),
         }
     }
@@ -41,6 +42,7 @@ impl SampleRate {
     }
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
 
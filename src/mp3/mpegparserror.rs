@@ -2,8 +2,9 @@
 #[derive(Debug, PartialEq)]
 pub enum MPEGParseError {
     NoFrameSync,
-    GenericInvalidFrameHeader { info: String },
+    InvalidField { field: &'static str, value: u32 },
     BadFrameBitrate,
+    TruncatedFrame,
 }
 
 impl MPEGParseError {
@@ -12,12 +13,23 @@ impl MPEGParseError {
             MPEGParseError::NoFrameSync => {
                 String::from("Header frame sync expected but not found.")
             }
-            MPEGParseError::GenericInvalidFrameHeader { info } => {
-                format!("Error parsing MPEG frame: {}", info)
+            MPEGParseError::InvalidField { field, value } => {
+                format!("Invalid {} in MPEG frame header: {}.", field, value)
             }
             MPEGParseError::BadFrameBitrate => {
                 String::from("Frame header bitrate indicated as bad.")
             }
+            MPEGParseError::TruncatedFrame => {
+                String::from("Frame extends past the end of the data.")
+            }
         }
     }
 }
+
+impl std::fmt::Display for MPEGParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(&self.description())
+    }
+}
+
+impl std::error::Error for MPEGParseError {}
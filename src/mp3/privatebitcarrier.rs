@@ -0,0 +1,128 @@
+use crate::mp3::{MP3Ref, MP3};
+use crate::stego::BitCarrier;
+
+/// Carries one payload bit per frame in the private bit of the frame header.
+/// Decoders ignore the private bit, so changing it has no effect on the audio.
+pub struct PrivateBitCarrier;
+
+impl BitCarrier<MP3> for PrivateBitCarrier {
+    fn positions(&self, cover: &MP3) -> usize {
+        cover.frames.len()
+    }
+
+    fn read_bit(&self, cover: &MP3, position: usize) -> bool {
+        cover.frames[position].header.private_bit
+    }
+
+    fn write_bit(&self, cover: &mut MP3, position: usize, bit: bool) {
+        cover.frames[position].set_private_bit(bit);
+    }
+}
+
+impl BitCarrier<MP3Ref<'_>> for PrivateBitCarrier {
+    fn positions(&self, cover: &MP3Ref) -> usize {
+        cover.frame_count()
+    }
+
+    fn read_bit(&self, cover: &MP3Ref, position: usize) -> bool {
+        cover.frame(position).header.private_bit
+    }
+
+    fn write_bit(&self, cover: &mut MP3Ref, position: usize, bit: bool) {
+        cover.set_private_bit(position, bit);
+    }
+}
+
+#[cfg(all(test, feature = "crypto"))]
+mod test {
+    use super::*;
+    use crate::mp3::test::{mp3_bytes, FRAME_LENGTH};
+    use crate::stego::{self, EmbedOptions, Layout, StegoKey};
+
+    #[test]
+    fn round_trips_payload_through_serialized_file() {
+        let key = StegoKey::derive("hunter2");
+        let mut mp3 = MP3::parse(&mp3_bytes(500)).unwrap();
+        stego::embed(
+            &mut mp3,
+            &PrivateBitCarrier,
+            &key,
+            b"secret",
+            EmbedOptions::default(),
+        )
+        .unwrap();
+
+        let mp3 = MP3::parse(&mp3.to_bytes()).unwrap();
+        let payload = stego::extract(&mp3, &PrivateBitCarrier, &key).unwrap();
+        assert_eq!(payload.payload, b"secret");
+    }
+
+    #[test]
+    fn spreads_payload_over_whole_file() {
+        let key = StegoKey::derive("hunter2");
+        let mut mp3 = MP3::parse(&mp3_bytes(1000)).unwrap();
+        stego::embed(
+            &mut mp3,
+            &PrivateBitCarrier,
+            &key,
+            &[0xFF; 16],
+            EmbedOptions::default(),
+        )
+        .unwrap();
+
+        let set_in_last_half = mp3.frames[500..]
+            .iter()
+            .filter(|frame| frame.header.private_bit)
+            .count();
+        assert!(set_in_last_half > 20);
+    }
+
+    #[test]
+    fn finds_packets_after_trimming_and_splicing() {
+        let key = StegoKey::derive("hunter2");
+        let mut mp3 = MP3::parse(&mp3_bytes(1200)).unwrap();
+        let options = EmbedOptions {
+            layout: Layout::Packets,
+            ..EmbedOptions::default()
+        };
+        stego::embed(&mut mp3, &PrivateBitCarrier, &key, b"secret", options).unwrap();
+
+        let mut bytes = mp3_bytes(50);
+        bytes.extend(&mp3.to_bytes()[300 * FRAME_LENGTH + 123..]);
+        let mp3 = MP3::parse(&bytes).unwrap();
+        let extraction = stego::extract(&mp3, &PrivateBitCarrier, &key).unwrap();
+        assert_eq!(extraction.payload, b"secret");
+    }
+
+    #[test]
+    fn embeds_in_place_through_memory_map() {
+        let path = std::env::temp_dir().join(format!("stegocrypt-{}.mp3", std::process::id()));
+        std::fs::write(&path, mp3_bytes(500)).unwrap();
+
+        let key = StegoKey::derive("hunter2");
+        {
+            let file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&path)
+                .unwrap();
+            // The file is private to this test, so nothing else changes it while it is mapped.
+            let mut map = unsafe { memmap2::MmapMut::map_mut(&file).unwrap() };
+            let mut mp3 = MP3Ref::parse(&mut map).unwrap();
+            stego::embed(
+                &mut mp3,
+                &PrivateBitCarrier,
+                &key,
+                b"secret",
+                EmbedOptions::default(),
+            )
+            .unwrap();
+            map.flush().unwrap();
+        }
+
+        let mp3 = MP3::parse(&std::fs::read(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let extraction = stego::extract(&mp3, &PrivateBitCarrier, &key).unwrap();
+        assert_eq!(extraction.payload, b"secret");
+    }
+}
@@ -0,0 +1,112 @@
+use crate::mp3::mpegframeheader::{
+    CRCProtection, MP3ChannelMode, MPEGFrameHeader, MPEGLayer, MPEGVersion,
+};
+
+/// Bits per granule and channel in MPEG-1 side information.
+const GRANULE_BITS_MPEG1: usize = 59;
+/// Bits per granule and channel in MPEG-2 and 2.5 side information, which have a wider scalefac_compress and no preflag.
+const GRANULE_BITS_MPEG2: usize = 63;
+
+/// The Layer III side information that follows the frame header (and CRC, if present).
+/// It describes how the main data of each granule and channel is coded.
+pub struct SideInfo {
+    pub main_data_begin: u16,
+    pub granules: Vec<GranuleChannel>,
+}
+
+/// Side information for one channel of one granule.
+pub struct GranuleChannel {
+    pub granule: usize,
+    pub channel: usize,
+    /// Offset in bits from the start of the frame to this entry.
+    pub bit_offset: usize,
+    pub part2_3_length: u16,
+    pub big_values: u16,
+    pub global_gain: u8,
+}
+
+impl GranuleChannel {
+    /// Offset in bits from the start of the frame to the least significant bit of global_gain.
+    pub fn global_gain_lsb_offset(&self) -> usize {
+        self.bit_offset + 12 + 9 + 7
+    }
+}
+
+impl SideInfo {
+    /// Parses the side information of a Layer III frame.
+    /// Returns `None` for other layers or if the frame is too short to hold the side information.
+    pub fn parse(header: &MPEGFrameHeader, frame: &[u8]) -> Option<SideInfo> {
+        if header.layer != MPEGLayer::Layer3 {
+            return None;
+        }
+
+        let channels = match header.channel_mode {
+            MP3ChannelMode::SingleChannel => 1,
+            _ => 2,
+        };
+        let start = match header.crc_protection {
+            CRCProtection::Enabled { .. } => 48,
+            CRCProtection::Disabled => 32,
+        };
+        let (main_data_begin_bits, private_bits, granules, granule_bits) = match header.version {
+            MPEGVersion::Version1 => (9, if channels == 1 { 5 } else { 3 }, 2, GRANULE_BITS_MPEG1),
+            _ => (8, channels, 1, GRANULE_BITS_MPEG2),
+        };
+        let scfsi_bits = if granules == 2 { 4 * channels } else { 0 };
+
+        let first_granule = start + main_data_begin_bits + private_bits + scfsi_bits;
+        let end = first_granule + granules * channels * granule_bits;
+        if frame.len() * 8 < end {
+            return None;
+        }
+
+        let main_data_begin = read_bits(frame, start, main_data_begin_bits) as u16;
+        let granules = (0..granules * channels)
+            .map(|i| {
+                let bit_offset = first_granule + i * granule_bits;
+                GranuleChannel {
+                    granule: i / channels,
+                    channel: i % channels,
+                    bit_offset,
+                    part2_3_length: read_bits(frame, bit_offset, 12) as u16,
+                    big_values: read_bits(frame, bit_offset + 12, 9) as u16,
+                    global_gain: read_bits(frame, bit_offset + 21, 8) as u8,
+                }
+            })
+            .collect();
+
+        Some(SideInfo {
+            main_data_begin,
+            granules,
+        })
+    }
+}
+
+/// Reads `count` bits starting at the given bit offset, most significant bit first.
+pub fn read_bits(data: &[u8], offset: usize, count: usize) -> u32 {
+    (offset..offset + count).fold(0, |value, i| {
+        (value << 1) | ((data[i / 8] >> (7 - i % 8)) & 1) as u32
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mp3::test::mp3_bytes;
+
+    #[test]
+    fn reads_bits_across_byte_boundaries() {
+        let data = [0b1010_1100, 0b0101_0011];
+        assert_eq!(read_bits(&data, 4, 8), 0b1100_0101);
+    }
+
+    #[test]
+    fn finds_two_granules_per_channel_in_mpeg1_stereo() {
+        let data = mp3_bytes(1);
+        let header = MPEGFrameHeader::parse(&data).unwrap();
+        let side_info = SideInfo::parse(&header, &data).unwrap();
+        assert_eq!(side_info.granules.len(), 4);
+        assert_eq!(side_info.granules[0].bit_offset, 32 + 9 + 3 + 8);
+        assert_eq!(side_info.granules[3].bit_offset, 52 + 3 * 59);
+    }
+}
@@ -0,0 +1,208 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::mp3::vbrheader::LAME_CRC;
+use crate::mp3::{FrameReader, MP3Frame};
+use crate::stego::{Compression, Container, Packet, PacketScanner, StegoError, StegoKey};
+use crate::Error;
+
+/// Embeds a payload while copying an MP3 file from `input` to `output`, holding only a window of the file in memory.
+/// The payload is sealed and written as packets in the frame private bits, as [`crate::stego::embed`] does with the packet layout and [`crate::mp3::PrivateBitCarrier`].
+/// The input is read twice, first to count its frames, so it must be seekable. A file with a valid LAME music CRC is read a third time to recompute it before the header is written.
+/// Bytes skipped between frames, such as an ID3v1 tag after the last one, are copied through unchanged.
+pub fn embed_stream<R: Read + Seek, W: Write>(
+    mut input: R,
+    mut output: W,
+    key: &StegoKey,
+    payload: &[u8],
+    compression: Compression,
+) -> Result<(), Error> {
+    let start = SeekFrom::Start(input.stream_position()?);
+    let mut frames = FrameReader::new(&mut input)?;
+    let mut vbr_frame = frames.take_vbr_frame();
+    let mut music_crc = LAME_CRC.digest();
+    let available = copy_frames(frames, std::iter::empty(), |piece| {
+        if let Piece::Frame(frame) = piece {
+            music_crc.update(&frame.data);
+        }
+        Ok(())
+    })?;
+    if let Some(vbr_frame) = &mut vbr_frame {
+        vbr_frame.check_music_crc(music_crc.finalize());
+    }
+
+    let sealed = Container::new(payload, compression).seal(key);
+    let round = Packet::bitstream(key, &sealed)?;
+    if round.len() > available {
+        return Err(Error::Stego(StegoError::PayloadTooLarge {
+            required: round.len(),
+            available,
+        }));
+    }
+    let bits = || {
+        round
+            .iter()
+            .copied()
+            .cycle()
+            .take(available / round.len() * round.len())
+    };
+
+    if let Some(vbr_frame) = &mut vbr_frame {
+        if vbr_frame.music_crc_valid == Some(true) {
+            input.seek(start)?;
+            let mut music_crc = LAME_CRC.digest();
+            copy_frames(FrameReader::new(&mut input)?, bits(), |piece| {
+                if let Piece::Frame(frame) = piece {
+                    music_crc.update(&frame.data);
+                }
+                Ok(())
+            })?;
+            vbr_frame.update_music_crc(music_crc.finalize());
+        }
+    }
+
+    input.seek(start)?;
+    let mut frames = FrameReader::new(&mut input)?;
+    if let Some(id3v2) = frames.id3v2() {
+        output.write_all(&id3v2.raw_tag)?;
+    }
+    for data in frames.take_skipped_data() {
+        output.write_all(&data)?;
+    }
+    if let Some(vbr_frame) = &vbr_frame {
+        output.write_all(&vbr_frame.frame.data)?;
+    }
+    copy_frames(frames, bits(), |piece| {
+        Ok(output.write_all(match piece {
+            Piece::Skipped(data) => data,
+            Piece::Frame(frame) => &frame.data,
+        })?)
+    })?;
+    Ok(())
+}
+
+/// A piece of the input handed on by [`copy_frames`].
+enum Piece<'a> {
+    /// Bytes skipped before the next frame or at the end of the input.
+    Skipped(&'a [u8]),
+    Frame(&'a MP3Frame),
+}
+
+/// Reads the audio frames, setting the private bit of each to the next payload bit while there are any, and hands each frame on after the bytes skipped before it.
+/// Returns the number of frames read.
+fn copy_frames<R: Read>(
+    mut frames: FrameReader<R>,
+    mut bits: impl Iterator<Item = bool>,
+    mut write: impl FnMut(Piece) -> Result<(), Error>,
+) -> Result<usize, Error> {
+    let mut count = 0;
+    while let Some(frame) = frames.next() {
+        let mut frame = frame?;
+        for data in frames.take_skipped_data() {
+            write(Piece::Skipped(&data))?;
+        }
+        if let Some(bit) = bits.next() {
+            frame.set_private_bit(bit);
+        }
+        write(Piece::Frame(&frame))?;
+        count += 1;
+    }
+    for data in frames.take_skipped_data() {
+        write(Piece::Skipped(&data))?;
+    }
+    Ok(count)
+}
+
+/// Extracts a payload written with the packet layout in the frame private bits, reading the file frame by frame.
+/// Reading stops as soon as every packet has been found.
+pub fn extract_stream<R: Read>(input: R, key: &StegoKey) -> Result<Vec<u8>, Error> {
+    let mut scanner = PacketScanner::new(key);
+    for frame in FrameReader::new(input)? {
+        scanner.push(frame?.header.private_bit);
+        if scanner.is_complete() {
+            break;
+        }
+    }
+    let sealed = scanner.finish()?;
+    Ok(Container::open(key, &sealed)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mp3::test::mp3_bytes;
+    use crate::mp3::vbrheader::test::lame_mp3_bytes;
+    use crate::mp3::{PrivateBitCarrier, MP3};
+    use crate::stego::{self, EmbedOptions, Layout};
+    use std::io::Cursor;
+
+    #[test]
+    fn streamed_embedding_matches_parsed_extraction() {
+        let key = StegoKey::derive("hunter2");
+        let mut output = Vec::new();
+        embed_stream(
+            Cursor::new(mp3_bytes(1200)),
+            &mut output,
+            &key,
+            b"secret",
+            Compression::None,
+        )
+        .unwrap();
+
+        let mp3 = MP3::parse(&output).unwrap();
+        let extraction = stego::extract(&mp3, &PrivateBitCarrier, &key).unwrap();
+        assert_eq!(extraction.payload, b"secret");
+        assert_eq!(extract_stream(output.as_slice(), &key).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn copies_skipped_bytes_through() {
+        let key = StegoKey::derive("hunter2");
+        let mut input = b"junk".to_vec();
+        input.extend(mp3_bytes(1200));
+        input.extend(b"TAG");
+        input.extend([b' '; 125]);
+        let mut output = Vec::new();
+        embed_stream(
+            Cursor::new(&input),
+            &mut output,
+            &key,
+            b"secret",
+            Compression::None,
+        )
+        .unwrap();
+
+        assert_eq!(output.len(), input.len());
+        assert_eq!(output[..4], input[..4]);
+        assert_eq!(output[output.len() - 128..], input[input.len() - 128..]);
+        assert_eq!(extract_stream(output.as_slice(), &key).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn extracts_parsed_embedding_from_stream() {
+        let key = StegoKey::derive("hunter2");
+        let mut mp3 = MP3::parse(&mp3_bytes(1200)).unwrap();
+        let options = EmbedOptions {
+            layout: Layout::Packets,
+            ..EmbedOptions::default()
+        };
+        stego::embed(&mut mp3, &PrivateBitCarrier, &key, b"secret", options).unwrap();
+
+        let bytes = mp3.to_bytes();
+        assert_eq!(extract_stream(bytes.as_slice(), &key).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn keeps_lame_music_crc_valid() {
+        let key = StegoKey::derive("hunter2");
+        let mut output = Vec::new();
+        let input = Cursor::new(lame_mp3_bytes(1200));
+        embed_stream(input, &mut output, &key, b"secret", Compression::None).unwrap();
+
+        let mp3 = MP3::parse(&output).unwrap();
+        let vbr_frame = mp3.vbr_frame.as_ref().unwrap();
+        assert_eq!(vbr_frame.music_crc_valid, Some(true));
+        let lame = vbr_frame.lame().unwrap();
+        assert_eq!(lame.tag_crc(&vbr_frame.frame.data), Some(lame.tag_crc));
+        assert_eq!(extract_stream(output.as_slice(), &key).unwrap(), b"secret");
+    }
+}
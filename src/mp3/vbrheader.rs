@@ -0,0 +1,282 @@
+#[cfg(feature = "report")]
+use serde::Serialize;
+
+use crate::mp3::{CRCProtection, MP3Frame, MPEGFrameHeader};
+
+pub use self::lametag::LameTag;
+pub(crate) use self::lametag::LAME_CRC;
+pub use self::vbriheader::VBRIHeader;
+pub use self::xingheader::XingHeader;
+
+mod lametag;
+mod vbriheader;
+mod xingheader;
+
+/// Offset of the VBRI header within its frame, which is fixed regardless of the side information length.
+const VBRI_OFFSET: usize = 4 + 32;
+
+/// The metadata header that encoders write in place of audio in the first frame of a file.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "report", derive(Serialize))]
+pub enum VBRHeader {
+    Xing(XingHeader),
+    VBRI(VBRIHeader),
+}
+
+/// The first frame of a file when it holds a [`VBRHeader`] rather than audio.
+#[derive(Clone)]
+pub struct VBRFrame {
+    pub frame: MP3Frame,
+    pub header: VBRHeader,
+    /// Whether the music CRC of the LAME tag matched the audio when the file was read, or `None` if it has not been checked or there is no LAME tag.
+    /// Only a CRC that matched is recomputed after embedding, so a file that already had a wrong one keeps it.
+    pub music_crc_valid: Option<bool>,
+}
+
+impl VBRHeader {
+    /// Parses the Xing/Info or VBRI header held by a frame, if it has one.
+    pub fn parse(header: &MPEGFrameHeader, frame: &[u8]) -> Option<VBRHeader> {
+        if let Some(offset) = VBRHeader::xing_offset(header) {
+            if let Some(xing) = XingHeader::parse(frame, offset) {
+                return Some(VBRHeader::Xing(xing));
+            }
+        }
+        VBRIHeader::parse(frame, VBRI_OFFSET).map(VBRHeader::VBRI)
+    }
+
+    /// Returns the offset within the frame at which a Xing/Info header would start: right after the side information.
+    pub fn xing_offset(header: &MPEGFrameHeader) -> Option<usize> {
+        let crc = match header.crc_protection {
+            CRCProtection::Enabled { .. } => 2,
+            CRCProtection::Disabled => 0,
+        };
+        Some(4 + crc + header.side_info_length()?)
+    }
+
+    /// Number of audio frames in the file, if the header records it.
+    pub fn frames(&self) -> Option<u32> {
+        match self {
+            VBRHeader::Xing(xing) => xing.frames,
+            VBRHeader::VBRI(vbri) => Some(vbri.frames),
+        }
+    }
+
+    /// Number of bytes of audio in the file, if the header records it.
+    pub fn bytes(&self) -> Option<u32> {
+        match self {
+            VBRHeader::Xing(xing) => xing.bytes,
+            VBRHeader::VBRI(vbri) => Some(vbri.bytes),
+        }
+    }
+}
+
+impl VBRFrame {
+    /// Returns the frame as a [`VBRFrame`] if it holds a VBR header.
+    pub fn parse(frame: MP3Frame) -> Result<VBRFrame, MP3Frame> {
+        match VBRHeader::parse(&frame.header, &frame.data) {
+            Some(header) => Ok(VBRFrame {
+                frame,
+                header,
+                music_crc_valid: None,
+            }),
+            None => Err(frame),
+        }
+    }
+
+    /// The LAME tag of the header, if it has one.
+    pub fn lame(&self) -> Option<&LameTag> {
+        match &self.header {
+            VBRHeader::Xing(xing) => xing.lame.as_ref(),
+            VBRHeader::VBRI(_) => None,
+        }
+    }
+
+    /// Records whether the LAME music CRC matches the given CRC of the original audio.
+    pub fn check_music_crc(&mut self, music_crc: u16) {
+        self.music_crc_valid = self.lame().map(|lame| lame.music_crc == music_crc);
+    }
+
+    /// Brings the header in line with the audio frames after embedding.
+    /// The Xing frame and byte counts, seek table and LAME music length are updated if frames were added, removed or resized, the music CRC is recomputed if it was valid, and then the tag CRC is recomputed if it was valid.
+    pub fn update(&mut self, frames: &[MP3Frame]) {
+        let vbr_length = self.frame.data.len();
+        let music_crc = LameTag::music_crc(frames);
+        self.patch(|xing| {
+            if xing.update(vbr_length, frames) {
+                if let (Some(lame), Some(bytes)) = (&mut xing.lame, xing.bytes) {
+                    lame.music_length = bytes;
+                }
+            }
+            music_crc
+        });
+    }
+
+    /// Sets the LAME music CRC, if it was valid, for embedding that changes frame bytes but not frame lengths, and recomputes the tag CRC if it was valid.
+    pub fn update_music_crc(&mut self, music_crc: u16) {
+        self.patch(|_| music_crc);
+    }
+
+    /// Applies a change to the Xing header, which returns the new music CRC, and writes the result into the frame.
+    fn patch(&mut self, change: impl FnOnce(&mut XingHeader) -> u16) {
+        let VBRHeader::Xing(xing) = &mut self.header else {
+            return;
+        };
+        let data = &mut self.frame.data;
+        let tag_crc_valid = xing
+            .lame
+            .as_ref()
+            .is_some_and(|lame| lame.tag_crc(data) == Some(lame.tag_crc));
+
+        let music_crc = change(xing);
+        xing.write(data);
+        if let Some(lame) = &mut xing.lame {
+            if self.music_crc_valid == Some(true) {
+                lame.music_crc = music_crc;
+            }
+            lame.write(data);
+            if tag_crc_valid {
+                lame.tag_crc = lame.tag_crc(data).unwrap_or(lame.tag_crc);
+                lame.write(data);
+            }
+        }
+    }
+}
+
+/// Reads a big-endian integer of up to four bytes, returning `None` if the data is too short.
+fn read_be(data: &[u8], offset: usize, length: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + length)?;
+    Some(
+        bytes
+            .iter()
+            .fold(0, |value, byte| (value << 8) | *byte as u32),
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::mp3::test::{mp3_bytes, FRAME_LENGTH};
+    use crate::mp3::{FrameReader, MP3Ref, PrivateBitCarrier, MP3};
+    use crate::stego::BitCarrier;
+
+    /// Builds an Info frame as LAME writes it for a constant bitrate file, recording the given frame count and audio length.
+    pub fn info_frame(frames: u32, bytes: u32) -> Vec<u8> {
+        let mut frame = vec![0xFF, 0xFB, 0x90, 0x64];
+        frame.extend([0; 32]);
+        frame.extend(b"Info");
+        frame.extend(0x0Fu32.to_be_bytes());
+        frame.extend(frames.to_be_bytes());
+        frame.extend(bytes.to_be_bytes());
+        frame.extend((0..100).map(|i| (i * 256 / 100) as u8));
+        frame.extend(50u32.to_be_bytes());
+
+        frame.extend(b"LAME3.100");
+        frame.extend([0x00, 0x9E]);
+        frame.extend(0.5f32.to_be_bytes());
+        frame.extend([0x2E, 0x2E, 0x00, 0x00]);
+        frame.extend([0x00, 0x80]);
+        frame.extend([0x24, 0x05, 0x40]);
+        frame.extend([0x00, 0x00, 0x00, 0x00]);
+        frame.extend(bytes.to_be_bytes());
+        frame.extend([0x12, 0x34, 0x56, 0x78]);
+        frame.resize(FRAME_LENGTH, 0);
+        frame
+    }
+
+    /// Builds an MP3 file of the given number of frames behind an Info frame whose counts and LAME checksums all match.
+    pub fn lame_mp3_bytes(frames: usize) -> Vec<u8> {
+        let audio = mp3_bytes(frames);
+        let mut bytes = info_frame(frames as u32, (FRAME_LENGTH + audio.len()) as u32);
+        bytes[188..190].copy_from_slice(&LAME_CRC.checksum(&audio).to_be_bytes());
+        let tag_crc = LAME_CRC.checksum(&bytes[..190]);
+        bytes[190..192].copy_from_slice(&tag_crc.to_be_bytes());
+        bytes.extend(audio);
+        bytes
+    }
+
+    fn lame_tag(mp3: &MP3) -> &LameTag {
+        mp3.vbr_frame.as_ref().unwrap().lame().unwrap()
+    }
+
+    #[test]
+    fn parses_info_header_with_lame_tag() {
+        let frame = info_frame(5, 5 * FRAME_LENGTH as u32);
+        let header = MPEGFrameHeader::parse(&frame).unwrap();
+        let Some(VBRHeader::Xing(xing)) = VBRHeader::parse(&header, &frame) else {
+            panic!("expected an Info header");
+        };
+        assert!(!xing.vbr);
+        assert_eq!(xing.frames, Some(5));
+        assert_eq!(xing.quality, Some(50));
+        assert_eq!(xing.toc.as_ref().map(Vec::len), Some(100));
+
+        let lame = xing.lame.unwrap();
+        assert_eq!(lame.encoder, "LAME3.100");
+        assert_eq!(lame.lowpass, 15800);
+        assert_eq!(lame.radio_replay_gain, Some(-4.6));
+        assert_eq!((lame.delay, lame.padding), (576, 1344));
+        assert_eq!(lame.music_crc, 0x1234);
+    }
+
+    #[test]
+    fn keeps_vbr_frame_out_of_audio_frames() {
+        let mut bytes = lame_mp3_bytes(5);
+        let mp3 = MP3::parse(&bytes).unwrap();
+        assert_eq!(mp3.frames.len(), 5);
+        assert_eq!(mp3.vbr_frame.as_ref().unwrap().header.frames(), Some(5));
+        assert_eq!(mp3.to_bytes(), bytes);
+        assert_eq!(PrivateBitCarrier.positions(&mp3), 5);
+
+        let mut reader = FrameReader::new(bytes.as_slice()).unwrap();
+        assert!(reader.vbr_frame().is_some());
+        assert_eq!(reader.by_ref().count(), 5);
+        assert_eq!(MP3Ref::parse(&mut bytes).unwrap().frame_count(), 5);
+    }
+
+    #[test]
+    fn recomputes_lame_checksums_after_embedding() {
+        let bytes = lame_mp3_bytes(5);
+        let mut mp3 = MP3::parse(&bytes).unwrap();
+        assert_eq!(mp3.vbr_frame.as_ref().unwrap().music_crc_valid, Some(true));
+        assert_eq!(mp3.to_bytes(), bytes);
+
+        PrivateBitCarrier.write_bit(&mut mp3, 2, true);
+        let mp3 = MP3::parse(&mp3.to_bytes()).unwrap();
+        let vbr_frame = mp3.vbr_frame.as_ref().unwrap();
+        let lame = lame_tag(&mp3);
+        assert_eq!(lame.music_crc, LameTag::music_crc(&mp3.frames));
+        assert_eq!(lame.tag_crc(&vbr_frame.frame.data), Some(lame.tag_crc));
+    }
+
+    #[test]
+    fn updates_counts_and_seek_table_when_frames_change() {
+        let mut mp3 = MP3::parse(&lame_mp3_bytes(5)).unwrap();
+        mp3.frames.truncate(4);
+
+        let mp3 = MP3::parse(&mp3.to_bytes()).unwrap();
+        let Some(VBRHeader::Xing(xing)) = mp3.vbr_frame.as_ref().map(|frame| &frame.header) else {
+            panic!("expected an Info header");
+        };
+        assert_eq!(xing.frames, Some(4));
+        assert_eq!(xing.bytes, Some(5 * FRAME_LENGTH as u32));
+        assert_eq!(&xing.toc.as_ref().unwrap()[..2], [0, 0]);
+        assert_eq!(xing.toc.as_ref().unwrap()[25], 64);
+        assert_eq!(lame_tag(&mp3).music_length, 5 * FRAME_LENGTH as u32);
+        assert_eq!(lame_tag(&mp3).music_crc, LameTag::music_crc(&mp3.frames));
+    }
+
+    #[test]
+    fn recomputes_seek_table_when_only_frame_count_changes() {
+        let frame = info_frame(5, 5 * FRAME_LENGTH as u32);
+        let header = MPEGFrameHeader::parse(&frame).unwrap();
+        let Some(VBRHeader::Xing(mut xing)) = VBRHeader::parse(&header, &frame) else {
+            panic!("expected an Info header");
+        };
+        xing.bytes = None;
+
+        let mp3 = MP3::parse(&mp3_bytes(4)).unwrap();
+        assert!(xing.update(FRAME_LENGTH, &mp3.frames));
+        assert_eq!(xing.frames, Some(4));
+        assert_eq!(&xing.toc.as_ref().unwrap()[..2], [0, 0]);
+    }
+}
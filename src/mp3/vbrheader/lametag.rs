@@ -0,0 +1,125 @@
+#[cfg(feature = "report")]
+use serde::Serialize;
+
+use crc::{Crc, CRC_16_ARC};
+
+use super::read_be;
+use crate::mp3::MP3Frame;
+
+/// Length of the LAME extension, from the encoder string to the tag checksum.
+const LAME_TAG_LENGTH: usize = 36;
+
+/// Offset within the LAME tag of the music length.
+const MUSIC_LENGTH_OFFSET: usize = 28;
+
+/// Offset within the LAME tag of the music CRC, which covers the audio frames after the tag's own frame.
+const MUSIC_CRC_OFFSET: usize = 32;
+
+/// Offset within the LAME tag of the tag CRC, which covers everything in the frame before it.
+const TAG_CRC_OFFSET: usize = 34;
+
+/// The checksum LAME uses for both the music CRC and the tag CRC.
+pub(crate) const LAME_CRC: Crc<u16> = Crc::<u16>::new(&CRC_16_ARC);
+
+/// Encoders known to write a LAME extension after the Xing/Info header.
+const ENCODERS: [&[u8]; 4] = [b"LAME", b"L3.9", b"Lavc", b"Lavf"];
+
+/// The LAME extension of a Xing/Info header, describing how the file was encoded.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "report", derive(Serialize))]
+pub struct LameTag {
+    /// Offset of the tag within its frame.
+    pub offset: usize,
+    /// Encoder name and version, such as "LAME3.100".
+    pub encoder: String,
+    pub revision: u8,
+    pub vbr_method: u8,
+    /// Lowpass filter frequency in Hz.
+    pub lowpass: u32,
+    pub peak_amplitude: f32,
+    /// Track ReplayGain adjustment in dB.
+    pub radio_replay_gain: Option<f32>,
+    /// Album ReplayGain adjustment in dB.
+    pub audiophile_replay_gain: Option<f32>,
+    pub encoding_flags: u8,
+    /// Average bitrate for ABR files, or the minimum bitrate for VBR files, in kbps.
+    pub bitrate: u8,
+    /// Samples of silence the encoder added at the start.
+    pub delay: u16,
+    /// Samples of silence the encoder added at the end.
+    pub padding: u16,
+    pub misc: u8,
+    pub mp3_gain: u8,
+    pub preset: u16,
+    /// Length in bytes of the file from the start of this frame to the end of the audio.
+    pub music_length: u32,
+    pub music_crc: u16,
+    pub tag_crc: u16,
+}
+
+impl LameTag {
+    /// Parses a LAME tag starting at the given offset of the frame.
+    pub fn parse(frame: &[u8], offset: usize) -> Option<LameTag> {
+        let tag = frame.get(offset..offset + LAME_TAG_LENGTH)?;
+        if !ENCODERS.iter().any(|encoder| tag.starts_with(encoder)) {
+            return None;
+        }
+
+        let field = |start: usize, length: usize| read_be(tag, start, length).unwrap_or(0);
+        let delay_and_padding = field(21, 3);
+        Some(LameTag {
+            offset,
+            encoder: String::from_utf8_lossy(&tag[0..9])
+                .trim_end_matches(['\0', ' '])
+                .to_string(),
+            revision: tag[9] >> 4,
+            vbr_method: tag[9] & 0x0F,
+            lowpass: tag[10] as u32 * 100,
+            peak_amplitude: f32::from_bits(field(11, 4)),
+            radio_replay_gain: replay_gain(field(15, 2) as u16),
+            audiophile_replay_gain: replay_gain(field(17, 2) as u16),
+            encoding_flags: tag[19],
+            bitrate: tag[20],
+            delay: (delay_and_padding >> 12) as u16,
+            padding: (delay_and_padding & 0xFFF) as u16,
+            misc: tag[24],
+            mp3_gain: tag[25],
+            preset: field(26, 2) as u16,
+            music_length: field(MUSIC_LENGTH_OFFSET, 4),
+            music_crc: field(MUSIC_CRC_OFFSET, 2) as u16,
+            tag_crc: field(TAG_CRC_OFFSET, 2) as u16,
+        })
+    }
+
+    /// Computes the music CRC of the audio frames that follow the tag's own frame.
+    pub fn music_crc(frames: &[MP3Frame]) -> u16 {
+        let mut digest = LAME_CRC.digest();
+        for frame in frames {
+            digest.update(&frame.data);
+        }
+        digest.finalize()
+    }
+
+    /// Computes the tag CRC, which covers everything in the frame before it.
+    pub fn tag_crc(&self, frame: &[u8]) -> Option<u16> {
+        Some(LAME_CRC.checksum(frame.get(..self.offset + TAG_CRC_OFFSET)?))
+    }
+
+    /// Writes the music length and both checksums back into the frame. The tag CRC should be recomputed after the other fields are written.
+    pub fn write(&self, frame: &mut [u8]) {
+        let tag = &mut frame[self.offset..self.offset + LAME_TAG_LENGTH];
+        tag[MUSIC_LENGTH_OFFSET..MUSIC_LENGTH_OFFSET + 4]
+            .copy_from_slice(&self.music_length.to_be_bytes());
+        tag[MUSIC_CRC_OFFSET..MUSIC_CRC_OFFSET + 2].copy_from_slice(&self.music_crc.to_be_bytes());
+        tag[TAG_CRC_OFFSET..TAG_CRC_OFFSET + 2].copy_from_slice(&self.tag_crc.to_be_bytes());
+    }
+}
+
+/// Decodes a ReplayGain field: a 3-bit name code (zero if unset), a 3-bit originator, a sign bit and the adjustment in tenths of a dB.
+fn replay_gain(field: u16) -> Option<f32> {
+    if field >> 13 == 0 {
+        return None;
+    }
+    let gain = (field & 0x1FF) as f32 / 10.0;
+    Some(if field & 0x200 != 0 { -gain } else { gain })
+}
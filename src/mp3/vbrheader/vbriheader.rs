@@ -0,0 +1,79 @@
+#[cfg(feature = "report")]
+use serde::Serialize;
+
+use super::read_be;
+
+/// Length of the VBRI header before its seek table.
+const VBRI_HEADER_LENGTH: usize = 26;
+
+/// A VBRI header, written by the Fraunhofer encoder in place of a Xing header.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "report", derive(Serialize))]
+pub struct VBRIHeader {
+    /// Offset of the header within its frame.
+    pub offset: usize,
+    pub version: u16,
+    pub delay: u16,
+    pub quality: u16,
+    pub bytes: u32,
+    pub frames: u32,
+    pub frames_per_entry: u16,
+    /// Seek table: the number of bytes covered by each run of `frames_per_entry` frames.
+    pub table: Vec<u32>,
+}
+
+impl VBRIHeader {
+    /// Parses a VBRI header starting at the given offset of the frame.
+    pub fn parse(frame: &[u8], offset: usize) -> Option<VBRIHeader> {
+        if frame.get(offset..offset + 4)? != b"VBRI" {
+            return None;
+        }
+        let field = |start: usize, length: usize| read_be(frame, offset + start, length);
+
+        let entries = field(18, 2)? as usize;
+        let scale = field(20, 2)?;
+        let entry_size = field(22, 2)? as usize;
+        if !(1..=4).contains(&entry_size) {
+            return None;
+        }
+        let table_start = offset + VBRI_HEADER_LENGTH;
+        let table = frame
+            .get(table_start..table_start + entries * entry_size)?
+            .chunks(entry_size)
+            .map(|entry| read_be(entry, 0, entry_size).map(|size| size.wrapping_mul(scale)))
+            .collect::<Option<Vec<u32>>>()?;
+
+        Some(VBRIHeader {
+            offset,
+            version: field(4, 2)? as u16,
+            delay: field(6, 2)? as u16,
+            quality: field(8, 2)? as u16,
+            bytes: field(10, 4)?,
+            frames: field(14, 4)?,
+            frames_per_entry: field(24, 2)? as u16,
+            table,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_vbri_header_and_seek_table() {
+        let mut frame = vec![0; 36];
+        frame.extend(b"VBRI");
+        frame.extend([0, 1, 0x1F, 0x40, 0, 75]);
+        frame.extend(100_000u32.to_be_bytes());
+        frame.extend(240u32.to_be_bytes());
+        frame.extend([0, 3, 0, 2, 0, 2, 0, 80]);
+        frame.extend([0x10, 0x00, 0x20, 0x00, 0x30, 0x00]);
+
+        let vbri = VBRIHeader::parse(&frame, 36).unwrap();
+        assert_eq!(vbri.frames, 240);
+        assert_eq!(vbri.bytes, 100_000);
+        assert_eq!(vbri.table, [0x2000, 0x4000, 0x6000]);
+        assert!(VBRIHeader::parse(&frame[..50], 36).is_none());
+    }
+}
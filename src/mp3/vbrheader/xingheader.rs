@@ -0,0 +1,127 @@
+#[cfg(feature = "report")]
+use serde::Serialize;
+
+use super::{read_be, LameTag};
+use crate::mp3::MP3Frame;
+
+const FRAMES_FLAG: u32 = 0x1;
+const BYTES_FLAG: u32 = 0x2;
+const TOC_FLAG: u32 = 0x4;
+const QUALITY_FLAG: u32 = 0x8;
+
+/// Number of entries in the seek table.
+const TOC_LENGTH: usize = 100;
+
+/// A Xing or Info header, which records the length of the file and a seek table so that players can seek in variable bitrate files.
+/// Encoders write "Info" instead of "Xing" in constant bitrate files.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "report", derive(Serialize))]
+pub struct XingHeader {
+    /// Whether the header is a "Xing" header for a variable bitrate file rather than an "Info" header.
+    pub vbr: bool,
+    /// Offset of the header within its frame.
+    pub offset: usize,
+    pub frames: Option<u32>,
+    pub bytes: Option<u32>,
+    /// Seek table mapping each percent of the duration to a position in the file, in 256ths of its length.
+    pub toc: Option<Vec<u8>>,
+    /// Encoder quality indicator, from 0 (best) to 100 (worst).
+    pub quality: Option<u32>,
+    pub lame: Option<LameTag>,
+}
+
+impl XingHeader {
+    /// Parses a Xing/Info header starting at the given offset of the frame.
+    pub fn parse(frame: &[u8], offset: usize) -> Option<XingHeader> {
+        let vbr = match frame.get(offset..offset + 4)? {
+            b"Xing" => true,
+            b"Info" => false,
+            _ => return None,
+        };
+        let flags = read_be(frame, offset + 4, 4)?;
+
+        let mut position = offset + 8;
+        let mut field = |flag: u32, length: usize| -> Option<Option<usize>> {
+            if flags & flag == 0 {
+                return Some(None);
+            }
+            frame.get(position..position + length)?;
+            position += length;
+            Some(Some(position - length))
+        };
+        let frames = field(FRAMES_FLAG, 4)?;
+        let bytes = field(BYTES_FLAG, 4)?;
+        let toc = field(TOC_FLAG, TOC_LENGTH)?;
+        let quality = field(QUALITY_FLAG, 4)?;
+        let lame = LameTag::parse(frame, position);
+
+        let value = |start: Option<usize>| start.and_then(|start| read_be(frame, start, 4));
+        Some(XingHeader {
+            vbr,
+            offset,
+            frames: value(frames),
+            bytes: value(bytes),
+            toc: toc.map(|start| frame[start..start + TOC_LENGTH].to_vec()),
+            quality: value(quality),
+            lame,
+        })
+    }
+
+    /// Updates the recorded frame count, byte count and seek table if the audio frames no longer match them, returning whether anything changed.
+    /// The byte count covers the header's own frame, of the given length, as well as the audio frames.
+    pub fn update(&mut self, vbr_length: usize, frames: &[MP3Frame]) -> bool {
+        let count = frames.len() as u32;
+        let bytes =
+            (vbr_length + frames.iter().map(|frame| frame.data.len()).sum::<usize>()) as u32;
+        let mut changed = false;
+
+        if self.frames.is_some_and(|frames| frames != count) {
+            self.frames = Some(count);
+            changed = true;
+        }
+        if self.bytes.is_some_and(|recorded| recorded != bytes) {
+            self.bytes = Some(bytes);
+            changed = true;
+        }
+        // The seek table indexes frames by percent of the count and points at byte offsets, so either change moves its entries.
+        if changed && self.toc.is_some() {
+            self.toc = Some(XingHeader::seek_table(frames));
+        }
+        changed
+    }
+
+    /// Builds a seek table giving, for each percent of the frames, the position of that frame as a fraction of the audio bytes.
+    fn seek_table(frames: &[MP3Frame]) -> Vec<u8> {
+        let mut positions = Vec::with_capacity(frames.len());
+        let mut total = 0;
+        for frame in frames {
+            positions.push(total);
+            total += frame.data.len();
+        }
+
+        (0..TOC_LENGTH)
+            .map(|percent| {
+                let position = positions
+                    .get(percent * frames.len() / TOC_LENGTH)
+                    .unwrap_or(&0);
+                (position * 256 / total.max(1)).min(255) as u8
+            })
+            .collect()
+    }
+
+    /// Writes the recorded fields back into the frame, at the positions they were parsed from.
+    pub fn write(&self, frame: &mut [u8]) {
+        let mut position = self.offset + 8;
+        let fields = [
+            self.frames.map(u32::to_be_bytes),
+            self.bytes.map(u32::to_be_bytes),
+        ];
+        for field in fields.into_iter().flatten() {
+            frame[position..position + 4].copy_from_slice(&field);
+            position += 4;
+        }
+        if let Some(toc) = &self.toc {
+            frame[position..position + TOC_LENGTH].copy_from_slice(toc);
+        }
+    }
+}
@@ -0,0 +1,496 @@
+use std::ops::Range;
+
+use crate::Error;
+
+pub use self::boxcover::{BoxCover, BoxPlace};
+pub use self::mp4box::MP4Box;
+pub use self::mp4carrier::MP4Carrier;
+pub use self::mp4parseerror::MP4ParseError;
+pub use self::slackcover::SlackCover;
+
+mod boxcover;
+mod mp4box;
+mod mp4carrier;
+mod mp4parseerror;
+mod sampletable;
+mod slackcover;
+
+/// Where a chunk offset points: into the payload of an `mdat` box, counted in file order, or somewhere outside of any.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ChunkOffset {
+    Media { mdat: usize, offset: u64 },
+    Absolute(u64),
+}
+
+impl ChunkOffset {
+    /// Returns the offset in the file, given where the payload of every `mdat` box lies.
+    fn resolve(&self, media: &[Range<u64>]) -> u64 {
+        match self {
+            ChunkOffset::Media { mdat, offset } => media[*mdat].start + offset,
+            ChunkOffset::Absolute(offset) => *offset,
+        }
+    }
+}
+
+/// A range of bytes in the payload of an `mdat` box, counted in file order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MediaRange {
+    pub mdat: usize,
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// A parsed ISO base media file, such as MP4 or M4A: its tree of boxes, with the chunk offsets of its tracks tied to the media data they point at.
+/// Writing the file recomputes every `stco` and `co64` entry, so boxes can grow, shrink or be added anywhere without breaking playback.
+pub struct MP4 {
+    pub boxes: Vec<MP4Box>,
+    /// The entries of every `stco` and `co64` box, in file order.
+    chunk_offsets: Vec<Vec<ChunkOffset>>,
+}
+
+impl MP4 {
+    pub fn parse(data: &[u8]) -> Result<MP4, Error> {
+        let error = |error, offset| Error::MP4 { error, offset };
+        let (boxes, _) =
+            MP4Box::parse_all(data, 0, false).map_err(|(e, offset)| error(e, offset))?;
+        if boxes.is_empty() {
+            return Err(error(MP4ParseError::TruncatedBox, 0));
+        }
+
+        let mut mp4 = MP4 {
+            boxes,
+            chunk_offsets: Vec::new(),
+        };
+        let media = mp4.media_data_positions();
+        let tables = mp4
+            .chunk_offset_boxes()
+            .into_iter()
+            .map(sampletable::read_chunk_offsets)
+            .collect::<Result<Vec<Vec<u64>>, MP4ParseError>>()
+            .map_err(|e| error(e, 0))?;
+        for offsets in tables {
+            let offsets = offsets
+                .into_iter()
+                .map(|offset| {
+                    match media
+                        .iter()
+                        .position(|range| range.contains(&offset) || range.end == offset)
+                    {
+                        Some(mdat) => ChunkOffset::Media {
+                            mdat,
+                            offset: offset - media[mdat].start,
+                        },
+                        None => ChunkOffset::Absolute(offset),
+                    }
+                })
+                .collect();
+            mp4.chunk_offsets.push(offsets);
+        }
+        Ok(mp4)
+    }
+
+    /// Serializes the file, pointing every chunk offset at where its media data ends up.
+    /// Offsets past 4 GiB need a `co64` box, which [`MP4::widen_chunk_offsets`] switches to after the media data has moved.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let media = self.media_data_positions();
+        let mut index = 0;
+        let mut replace = |parsed: &MP4Box| {
+            if !is_chunk_offset_box(parsed) {
+                return None;
+            }
+            let offsets: Vec<u64> = self.chunk_offsets[index]
+                .iter()
+                .map(|offset| offset.resolve(&media))
+                .collect();
+            index += 1;
+            Some(sampletable::write_chunk_offsets(parsed, &offsets))
+        };
+
+        let mut bytes = Vec::new();
+        for parsed in &self.boxes {
+            parsed.write(&mut bytes, &mut replace);
+        }
+        bytes
+    }
+
+    /// Turns every `stco` box with an offset that no longer fits in 32 bits, as the file would be written now, into a `co64` box.
+    /// A wider box moves the media data behind it, so this repeats until every offset fits.
+    pub fn widen_chunk_offsets(&mut self) {
+        loop {
+            let media = self.media_data_positions();
+            let wide: Vec<bool> = self
+                .chunk_offsets
+                .iter()
+                .map(|offsets| {
+                    offsets
+                        .iter()
+                        .any(|offset| offset.resolve(&media) > u32::MAX as u64)
+                })
+                .collect();
+            let mut index = 0;
+            let mut changed = false;
+            for parsed in &mut self.boxes {
+                parsed.for_each_mut(&mut |parsed| {
+                    if !is_chunk_offset_box(parsed) {
+                        return;
+                    }
+                    if &parsed.kind == b"stco" && wide[index] {
+                        parsed.kind = *b"co64";
+                        parsed
+                            .data
+                            .resize(8 + 8 * self.chunk_offsets[index].len(), 0);
+                        changed = true;
+                    }
+                    index += 1;
+                });
+            }
+            if !changed {
+                return;
+            }
+        }
+    }
+
+    /// Returns whether the media data is located by movie fragments, which this crate cannot rewrite.
+    pub fn is_fragmented(&self) -> bool {
+        self.boxes.iter().any(|parsed| &parsed.kind == b"moof")
+            || self
+                .top_level(b"moov")
+                .is_some_and(|moov| moov.child(b"mvex").is_some())
+    }
+
+    /// Returns the first top-level box of the given type.
+    pub fn top_level(&self, kind: &[u8; 4]) -> Option<&MP4Box> {
+        self.boxes.iter().find(|parsed| &parsed.kind == kind)
+    }
+
+    pub fn top_level_mut(&mut self, kind: &[u8; 4]) -> Option<&mut MP4Box> {
+        self.boxes.iter_mut().find(|parsed| &parsed.kind == kind)
+    }
+
+    /// Returns the indices in `boxes` of the `mdat` boxes.
+    pub fn media_data_boxes(&self) -> Vec<usize> {
+        (0..self.boxes.len())
+            .filter(|index| &self.boxes[*index].kind == b"mdat")
+            .collect()
+    }
+
+    /// Returns where the payload of every `mdat` box lies in the file as it would be written now.
+    fn media_data_positions(&self) -> Vec<Range<u64>> {
+        let mut positions = Vec::new();
+        let mut offset = 0;
+        for parsed in &self.boxes {
+            if &parsed.kind == b"mdat" {
+                let start = (offset + parsed.header_length()) as u64;
+                positions.push(start..start + parsed.data.len() as u64);
+            }
+            offset += parsed.length();
+        }
+        positions
+    }
+
+    fn chunk_offset_boxes(&self) -> Vec<&MP4Box> {
+        let descendants = self.boxes.iter().flat_map(MP4Box::descendants);
+        descendants
+            .filter(|parsed| is_chunk_offset_box(parsed))
+            .collect()
+    }
+
+    /// Returns the chunks of every track that lie in the media data, in track order.
+    pub fn chunks(&self) -> Result<Vec<MediaRange>, MP4ParseError> {
+        let file_length = self.boxes.iter().map(MP4Box::length).sum::<usize>() as u64;
+        let mut chunks = Vec::new();
+        let mut index = 0;
+        for parsed in self.boxes.iter().flat_map(MP4Box::descendants) {
+            if is_chunk_offset_box(parsed) {
+                index += 1;
+            }
+            if &parsed.kind != b"stbl" || !parsed.children.iter().flatten().any(is_chunk_offset_box)
+            {
+                continue;
+            }
+            let Some(offsets) = self.chunk_offsets.get(index) else {
+                continue;
+            };
+            let lengths = sampletable::chunk_lengths(parsed, offsets.len(), file_length)?;
+            for (offset, length) in offsets.iter().zip(lengths) {
+                if let ChunkOffset::Media { mdat, offset } = offset {
+                    chunks.push(MediaRange {
+                        mdat: *mdat,
+                        offset: *offset,
+                        length,
+                    });
+                }
+            }
+        }
+        Ok(chunks)
+    }
+
+    /// Inserts runs of zero bytes into the payload of an `mdat` box, counted in file order, moving the chunks behind each of them.
+    /// Insertions are given as an offset in the payload and a length, in increasing order of offset. Chunk offsets are widened as needed.
+    pub fn insert_media_data(&mut self, mdat: usize, insertions: &[(u64, usize)]) {
+        let index = self.media_data_boxes()[mdat];
+        let old = std::mem::take(&mut self.boxes[index].data);
+        let mut data = Vec::with_capacity(
+            old.len() + insertions.iter().map(|(_, length)| length).sum::<usize>(),
+        );
+        let mut copied = 0;
+        for (offset, length) in insertions {
+            data.extend(&old[copied..*offset as usize]);
+            data.resize(data.len() + length, 0);
+            copied = *offset as usize;
+        }
+        data.extend(&old[copied..]);
+        self.boxes[index].data = data;
+
+        for offsets in &mut self.chunk_offsets {
+            for offset in offsets {
+                if let ChunkOffset::Media {
+                    mdat: target,
+                    offset,
+                } = offset
+                {
+                    if *target == mdat {
+                        let before = insertions.partition_point(|(at, _)| at <= offset);
+                        *offset += insertions[..before]
+                            .iter()
+                            .map(|(_, length)| *length as u64)
+                            .sum::<u64>();
+                    }
+                }
+            }
+        }
+        self.widen_chunk_offsets();
+    }
+}
+
+fn is_chunk_offset_box(parsed: &MP4Box) -> bool {
+    &parsed.kind == b"stco" || &parsed.kind == b"co64"
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// Samples per chunk in the files built by [`mp4_bytes`].
+    const CHUNK_SAMPLES: usize = 5;
+
+    fn full_box(kind: &[u8; 4], payload: Vec<u8>) -> MP4Box {
+        let mut data = vec![0; 4];
+        data.extend(payload);
+        MP4Box::new(kind, data)
+    }
+
+    /// Returns the samples of the single audio track built by [`mp4_bytes`].
+    pub(crate) fn samples(count: usize) -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|number| vec![number as u8; 20 + number * 29 % 150])
+            .collect()
+    }
+
+    /// Builds an M4A file with one track of `count` samples in chunks of five, its `moov` box ahead of or behind the media data.
+    /// Chunk offsets are written to a `co64` box if `large_offsets` is set, and to a `stco` box otherwise.
+    pub(crate) fn mp4_bytes(count: usize, moov_first: bool, large_offsets: bool) -> Vec<u8> {
+        let samples = samples(count);
+        let chunks: Vec<&[Vec<u8>]> = samples.chunks(CHUNK_SAMPLES).collect();
+
+        let mut stsc = 1u32.to_be_bytes().to_vec();
+        stsc.extend(
+            [1u32, CHUNK_SAMPLES as u32, 1]
+                .iter()
+                .flat_map(|value| value.to_be_bytes()),
+        );
+        let mut stsz = 0u32.to_be_bytes().to_vec();
+        stsz.extend((count as u32).to_be_bytes());
+        stsz.extend(
+            samples
+                .iter()
+                .flat_map(|sample| (sample.len() as u32).to_be_bytes()),
+        );
+        let mut offsets = (chunks.len() as u32).to_be_bytes().to_vec();
+        offsets.resize(4 + chunks.len() * if large_offsets { 8 } else { 4 }, 0);
+        let kind = if large_offsets { b"co64" } else { b"stco" };
+
+        let stbl = MP4Box::container(
+            b"stbl",
+            vec![
+                full_box(b"stsd", 0u32.to_be_bytes().to_vec()),
+                full_box(b"stts", 0u32.to_be_bytes().to_vec()),
+                full_box(b"stsc", stsc),
+                full_box(b"stsz", stsz),
+                full_box(kind, offsets),
+            ],
+        );
+        let minf = MP4Box::container(b"minf", vec![full_box(b"smhd", vec![0; 4]), stbl]);
+        let mdia = MP4Box::container(b"mdia", vec![full_box(b"mdhd", vec![0; 20]), minf]);
+        let trak = MP4Box::container(b"trak", vec![full_box(b"tkhd", vec![0; 80]), mdia]);
+        let mut moov = MP4Box::container(b"moov", vec![full_box(b"mvhd", vec![0; 96]), trak]);
+        let ftyp = MP4Box::new(b"ftyp", b"M4A \0\0\0\0M4A isom".to_vec());
+        // A few bytes of slack between the chunks, as some muxers leave.
+        let mut mdat = MP4Box::new(b"mdat", Vec::new());
+        let mut positions = Vec::new();
+        for chunk in &chunks {
+            positions.push(mdat.data.len() as u64);
+            mdat.data.extend(chunk.concat());
+            mdat.data.extend([0xEE; 3]);
+        }
+
+        let mut boxes = vec![ftyp];
+        let start = match moov_first {
+            true => boxes[0].length() + moov.length() + 8,
+            false => boxes[0].length() + 8,
+        } as u64;
+        let moov_data = match large_offsets {
+            true => positions
+                .iter()
+                .flat_map(|position| (start + position).to_be_bytes())
+                .collect::<Vec<u8>>(),
+            false => positions
+                .iter()
+                .flat_map(|position| (start as u32 + *position as u32).to_be_bytes())
+                .collect(),
+        };
+        let stbl = moov
+            .child_mut(b"trak")
+            .and_then(|trak| trak.child_mut(b"mdia"))
+            .and_then(|mdia| mdia.child_mut(b"minf"))
+            .and_then(|minf| minf.child_mut(b"stbl"))
+            .unwrap();
+        stbl.child_mut(kind).unwrap().data.splice(8.., moov_data);
+        match moov_first {
+            true => boxes.extend([moov, mdat]),
+            false => boxes.extend([mdat, moov]),
+        }
+
+        let mut bytes = Vec::new();
+        for parsed in &boxes {
+            parsed.write(&mut bytes, &mut |_| None);
+        }
+        bytes
+    }
+
+    /// Reads back the samples of a file built by [`mp4_bytes`] through its chunk offsets.
+    pub(crate) fn read_samples(mp4: &MP4) -> Vec<Vec<u8>> {
+        let bytes = mp4.to_bytes();
+        let stbl = mp4
+            .top_level(b"moov")
+            .unwrap()
+            .descendants()
+            .into_iter()
+            .find(|parsed| &parsed.kind == b"stbl")
+            .unwrap()
+            .clone();
+        let offsets =
+            sampletable::read_chunk_offsets(stbl.child(b"stco").or(stbl.child(b"co64")).unwrap())
+                .unwrap();
+        let lengths = sampletable::sample_sizes(&stbl, bytes.len() as u64).unwrap();
+        let mut samples = Vec::new();
+        for (chunk, offset) in offsets.iter().enumerate() {
+            let mut position = *offset as usize;
+            for length in lengths
+                .iter()
+                .skip(chunk * CHUNK_SAMPLES)
+                .take(CHUNK_SAMPLES)
+            {
+                samples.push(bytes[position..position + *length as usize].to_vec());
+                position += *length as usize;
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn writes_boxes_back_byte_for_byte() {
+        for (moov_first, large_offsets) in [(true, false), (false, true)] {
+            let bytes = mp4_bytes(23, moov_first, large_offsets);
+            let mp4 = MP4::parse(&bytes).unwrap();
+            assert_eq!(mp4.to_bytes(), bytes);
+            assert_eq!(read_samples(&mp4), samples(23));
+            assert!(!mp4.is_fragmented());
+            let chunks = mp4.chunks().unwrap();
+            assert_eq!(chunks.len(), 5);
+            assert_eq!(chunks[1].offset, chunks[0].length + 3);
+        }
+
+        let mut truncated = mp4_bytes(23, true, false);
+        truncated.truncate(truncated.len() - 1);
+        assert!(matches!(
+            MP4::parse(&truncated),
+            Err(Error::MP4 {
+                error: MP4ParseError::TruncatedBox,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn widens_chunk_offsets_past_four_gibibytes() {
+        let mut mp4 = MP4::parse(&mp4_bytes(23, true, false)).unwrap();
+        let chunks = mp4.chunks().unwrap();
+        let start = mp4.media_data_positions()[0].start;
+        // Pointing a chunk past 4 GiB stands in for moving it there, which would take that much media data.
+        mp4.chunk_offsets[0][4] = ChunkOffset::Media {
+            mdat: 0,
+            offset: u32::MAX as u64,
+        };
+        mp4.widen_chunk_offsets();
+
+        let mp4 = MP4::parse(&mp4.to_bytes()).unwrap();
+        let co64 = mp4.chunk_offset_boxes()[0];
+        assert_eq!(&co64.kind, b"co64");
+        // The wider box comes before the media data, which moves along with the other chunks.
+        let moved = start + 4 * chunks.len() as u64;
+        assert_eq!(mp4.media_data_positions()[0].start, moved);
+        let offsets = sampletable::read_chunk_offsets(co64).unwrap();
+        assert_eq!(offsets[1], moved + chunks[1].offset);
+        assert_eq!(offsets[4], moved + u32::MAX as u64);
+    }
+
+    #[test]
+    fn rejects_counts_larger_than_their_table() {
+        let bytes = mp4_bytes(23, true, false);
+        let mp4 = MP4::parse(&bytes).unwrap();
+        let mut stbl = mp4
+            .top_level(b"moov")
+            .unwrap()
+            .descendants()
+            .into_iter()
+            .find(|parsed| &parsed.kind == b"stbl")
+            .unwrap()
+            .clone();
+
+        let stco = stbl.child_mut(b"stco").unwrap();
+        stco.data[4..8].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert_eq!(
+            sampletable::read_chunk_offsets(stco),
+            Err(MP4ParseError::InvalidBox { kind: *b"stco" })
+        );
+
+        // A single sample size, repeated more times than the file could hold.
+        let stsz = stbl.child_mut(b"stsz").unwrap();
+        stsz.data.truncate(12);
+        stsz.data[4..8].copy_from_slice(&16u32.to_be_bytes());
+        stsz.data[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert_eq!(
+            sampletable::sample_sizes(&stbl, bytes.len() as u64),
+            Err(MP4ParseError::InvalidBox { kind: *b"stsz" })
+        );
+        stbl.child_mut(b"stsz").unwrap().data[8..12].copy_from_slice(&10u32.to_be_bytes());
+        assert_eq!(
+            sampletable::sample_sizes(&stbl, bytes.len() as u64),
+            Ok(vec![16; 10])
+        );
+    }
+
+    #[test]
+    fn moves_chunk_offsets_with_media_data() {
+        let mut mp4 = MP4::parse(&mp4_bytes(23, true, false)).unwrap();
+        mp4.boxes.insert(1, MP4Box::new(b"free", vec![0; 1000]));
+        let chunks = mp4.chunks().unwrap();
+        mp4.insert_media_data(0, &[(0, 7), (chunks[2].offset, 11)]);
+        assert_eq!(mp4.chunks().unwrap()[2].offset, chunks[2].offset + 18);
+
+        let mp4 = MP4::parse(&mp4.to_bytes()).unwrap();
+        assert_eq!(read_samples(&mp4), samples(23));
+        assert_eq!(mp4.top_level(b"mdat").unwrap().data[..7], [0; 7]);
+    }
+}
@@ -0,0 +1,160 @@
+use crate::carrier::{embed_growing, Cover};
+use crate::mp4::{MP4Box, MP4ParseError, MP4};
+use crate::stego::{EmbedOptions, StegoError, StegoKey};
+
+/// Where a [`BoxCover`] keeps its payload.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoxPlace {
+    /// The first top-level `free` or `skip` box, or a new `free` box ahead of the media data.
+    TopLevel,
+    /// A `free` box in the `udta` box of the movie, which is added if the movie has none.
+    UserData,
+}
+
+/// An ISO base media file carrying payload bits in the body of a free space box.
+/// Readers skip free space wherever it is, and muxers often leave some to make room for later edits, but its content is there for anyone who looks.
+/// The box is sized to fit the payload, and chunk offsets are moved with the media data it displaces.
+pub struct BoxCover {
+    pub mp4: MP4,
+    place: BoxPlace,
+}
+
+fn is_free_space(parsed: &MP4Box) -> bool {
+    &parsed.kind == b"free" || &parsed.kind == b"skip"
+}
+
+impl BoxCover {
+    pub fn new(mp4: MP4, place: BoxPlace) -> Result<BoxCover, MP4ParseError> {
+        if mp4.is_fragmented() {
+            return Err(MP4ParseError::Fragmented);
+        }
+        if place == BoxPlace::UserData {
+            let moov = mp4
+                .top_level(b"moov")
+                .ok_or(MP4ParseError::MissingBox { kind: *b"moov" })?;
+            if moov
+                .child(b"udta")
+                .is_some_and(|udta| udta.children.is_none())
+            {
+                return Err(MP4ParseError::InvalidBox { kind: *b"udta" });
+            }
+        }
+        Ok(BoxCover { mp4, place })
+    }
+
+    fn find(&self) -> Option<&MP4Box> {
+        match self.place {
+            BoxPlace::TopLevel => self.mp4.boxes.iter().find(|parsed| is_free_space(parsed)),
+            BoxPlace::UserData => self.mp4.top_level(b"moov")?.child(b"udta")?.child(b"free"),
+        }
+    }
+
+    fn find_mut(&mut self) -> Option<&mut MP4Box> {
+        match self.place {
+            BoxPlace::TopLevel => self
+                .mp4
+                .boxes
+                .iter_mut()
+                .find(|parsed| is_free_space(parsed)),
+            BoxPlace::UserData => self
+                .mp4
+                .top_level_mut(b"moov")?
+                .child_mut(b"udta")?
+                .child_mut(b"free"),
+        }
+    }
+
+    /// Replaces the body of the box with `length` zero bytes, adding the box if there is none.
+    pub fn reserve(&mut self, length: usize) {
+        if let Some(parsed) = self.find_mut() {
+            parsed.data = vec![0; length];
+            self.mp4.widen_chunk_offsets();
+            return;
+        }
+        let free = MP4Box::new(b"free", vec![0; length]);
+        match self.place {
+            BoxPlace::TopLevel => {
+                let index = self.mp4.media_data_boxes().first().copied();
+                self.mp4
+                    .boxes
+                    .insert(index.unwrap_or(self.mp4.boxes.len()), free);
+            }
+            BoxPlace::UserData => {
+                let moov = self.mp4.top_level_mut(b"moov").expect("checked by new");
+                let children = moov.children.get_or_insert_with(Vec::new);
+                match children.iter_mut().find(|child| &child.kind == b"udta") {
+                    Some(udta) => udta.children.get_or_insert_with(Vec::new).push(free),
+                    None => children.push(MP4Box::container(b"udta", vec![free])),
+                }
+            }
+        }
+        self.mp4.widen_chunk_offsets();
+    }
+}
+
+impl Cover for BoxCover {
+    fn positions(&self) -> usize {
+        self.find().map_or(0, |parsed| parsed.data.len() * 8)
+    }
+
+    fn read_bit(&self, position: usize) -> bool {
+        let parsed = self.find().expect("positions exist only in a box");
+        parsed.data[position / 8] >> (7 - position % 8) & 1 == 1
+    }
+
+    fn write_bit(&mut self, position: usize, bit: bool) {
+        let parsed = self.find_mut().expect("positions exist only in a box");
+        let mask = 1 << (7 - position % 8);
+        let byte = &mut parsed.data[position / 8];
+        *byte = (*byte & !mask) | if bit { mask } else { 0 };
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.mp4.to_bytes()
+    }
+
+    /// The box is emptied and then grown to the payload.
+    fn embed_raw(
+        &mut self,
+        key: &StegoKey,
+        data: &[u8],
+        options: EmbedOptions,
+    ) -> Result<(), StegoError> {
+        self.reserve(0);
+        embed_growing(self, key, data, options, |cover, length| {
+            cover.reserve(length);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mp4::test::{mp4_bytes, read_samples, samples};
+
+    #[test]
+    fn adds_boxes_and_moves_media_data() {
+        let mut cover = BoxCover::new(
+            MP4::parse(&mp4_bytes(40, true, false)).unwrap(),
+            BoxPlace::UserData,
+        )
+        .unwrap();
+        assert_eq!(cover.positions(), 0);
+        cover.reserve(300);
+        cover.write_bit(2399, true);
+
+        let mp4 = MP4::parse(&cover.to_bytes()).unwrap();
+        assert_eq!(read_samples(&mp4), samples(40));
+        let mut cover = BoxCover::new(mp4, BoxPlace::TopLevel).unwrap();
+        cover.reserve(100);
+        let mp4 = MP4::parse(&cover.to_bytes()).unwrap();
+        let kinds: Vec<&[u8; 4]> = mp4.boxes.iter().map(|parsed| &parsed.kind).collect();
+        assert_eq!(kinds, [b"ftyp", b"moov", b"free", b"mdat"]);
+        assert_eq!(read_samples(&mp4), samples(40));
+
+        let cover = BoxCover::new(mp4, BoxPlace::UserData).unwrap();
+        assert_eq!(cover.positions(), 2400);
+        assert!(cover.read_bit(2399));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::mp4::MP4ParseError;
+
+/// Boxes whose payload is a sequence of boxes.
+const CONTAINERS: [&[u8; 4]; 12] = [
+    b"moov", b"trak", b"mdia", b"minf", b"stbl", b"edts", b"dinf", b"udta", b"mvex", b"moof",
+    b"traf", b"mfra",
+];
+
+/// A box of an ISO base media file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MP4Box {
+    pub kind: [u8; 4],
+    /// Whether the size is written in the 64-bit field.
+    pub large: bool,
+    /// The child boxes of a container box, or `None` for a box whose payload is kept as bytes.
+    pub children: Option<Vec<MP4Box>>,
+    /// The payload of a box without children, or the bytes after the children of a container, such as the terminator that QuickTime writes at the end of `udta`.
+    pub data: Vec<u8>,
+}
+
+impl MP4Box {
+    pub fn new(kind: &[u8; 4], data: Vec<u8>) -> MP4Box {
+        MP4Box {
+            kind: *kind,
+            large: false,
+            children: None,
+            data,
+        }
+    }
+
+    pub fn container(kind: &[u8; 4], children: Vec<MP4Box>) -> MP4Box {
+        MP4Box {
+            children: Some(children),
+            ..MP4Box::new(kind, Vec::new())
+        }
+    }
+
+    /// Parses a sequence of boxes filling the data. `base` is the offset of the data in the file, for error reporting.
+    /// Bytes too short to be a box at the end of a container's payload are returned as well.
+    pub fn parse_all(
+        data: &[u8],
+        base: usize,
+        container: bool,
+    ) -> Result<(Vec<MP4Box>, Vec<u8>), (MP4ParseError, usize)> {
+        let mut boxes = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            if container && data.len() - offset < 8 {
+                break;
+            }
+            let (parsed, length) = MP4Box::parse(&data[offset..], base + offset)?;
+            boxes.push(parsed);
+            offset += length;
+        }
+        Ok((boxes, data[offset..].to_vec()))
+    }
+
+    /// Parses the box at the start of the data and returns it with its length.
+    fn parse(data: &[u8], base: usize) -> Result<(MP4Box, usize), (MP4ParseError, usize)> {
+        let truncated = || (MP4ParseError::TruncatedBox, base);
+        let header = data.get(..8).ok_or_else(truncated)?;
+        let kind: [u8; 4] = header[4..8].try_into().unwrap();
+        let (length, header_length, large) =
+            match u32::from_be_bytes(header[..4].try_into().unwrap()) {
+                0 => (data.len(), 8, false),
+                1 => {
+                    let size = data.get(8..16).ok_or_else(truncated)?;
+                    let size = u64::from_be_bytes(size.try_into().unwrap());
+                    (usize::try_from(size).unwrap_or(usize::MAX), 16, true)
+                }
+                size => (size as usize, 8, false),
+            };
+        if length < header_length || length > data.len() {
+            return Err(truncated());
+        }
+
+        let payload = &data[header_length..length];
+        let mut parsed = MP4Box {
+            large,
+            ..MP4Box::new(&kind, Vec::new())
+        };
+        if CONTAINERS.contains(&&kind) {
+            match MP4Box::parse_all(payload, base + header_length, true) {
+                Ok((children, rest)) => {
+                    parsed.children = Some(children);
+                    parsed.data = rest;
+                }
+                // QuickTime user data may hold items that are not boxes, which are kept as they are.
+                Err(_) if &kind == b"udta" => parsed.data = payload.to_vec(),
+                Err(error) => return Err(error),
+            }
+        } else {
+            parsed.data = payload.to_vec();
+        }
+        Ok((parsed, length))
+    }
+
+    /// Returns the length of the box header: 8 bytes, or 16 with a 64-bit size.
+    pub fn header_length(&self) -> usize {
+        match self.large || self.payload_length() > (u32::MAX - 8) as usize {
+            true => 16,
+            false => 8,
+        }
+    }
+
+    fn payload_length(&self) -> usize {
+        let children = self.children.iter().flatten();
+        children.map(MP4Box::length).sum::<usize>() + self.data.len()
+    }
+
+    pub fn length(&self) -> usize {
+        self.header_length() + self.payload_length()
+    }
+
+    /// Writes the box. `replace` is given every box in file order and may return a payload to write instead of the box's own.
+    pub fn write(&self, bytes: &mut Vec<u8>, replace: &mut dyn FnMut(&MP4Box) -> Option<Vec<u8>>) {
+        let replaced = replace(self);
+        let length = match &replaced {
+            Some(payload) => self.header_length() + payload.len(),
+            None => self.length(),
+        };
+        match self.header_length() {
+            16 => {
+                bytes.extend(1u32.to_be_bytes());
+                bytes.extend(self.kind);
+                bytes.extend((length as u64).to_be_bytes());
+            }
+            _ => {
+                bytes.extend((length as u32).to_be_bytes());
+                bytes.extend(self.kind);
+            }
+        }
+        if let Some(payload) = replaced {
+            bytes.extend(payload);
+            return;
+        }
+        for child in self.children.iter().flatten() {
+            child.write(bytes, replace);
+        }
+        bytes.extend(&self.data);
+    }
+
+    /// Returns the first child box of the given type.
+    pub fn child(&self, kind: &[u8; 4]) -> Option<&MP4Box> {
+        self.children
+            .iter()
+            .flatten()
+            .find(|child| &child.kind == kind)
+    }
+
+    pub fn child_mut(&mut self, kind: &[u8; 4]) -> Option<&mut MP4Box> {
+        self.children
+            .iter_mut()
+            .flatten()
+            .find(|child| &child.kind == kind)
+    }
+
+    /// Calls `f` on the box and all boxes inside it, in file order.
+    pub fn for_each_mut(&mut self, f: &mut dyn FnMut(&mut MP4Box)) {
+        f(self);
+        for child in self.children.iter_mut().flatten() {
+            child.for_each_mut(f);
+        }
+    }
+
+    /// Returns the box and all boxes inside it, in file order.
+    pub fn descendants(&self) -> Vec<&MP4Box> {
+        let mut boxes = vec![self];
+        for child in self.children.iter().flatten() {
+            boxes.extend(child.descendants());
+        }
+        boxes
+    }
+}
@@ -0,0 +1,33 @@
+use crate::carrier::{Carrier, Cover};
+use crate::mp4::{BoxCover, BoxPlace, SlackCover, MP4};
+use crate::Error;
+
+/// The ISO base media format of MP4 and M4A files, embedding in a free space box at the top level or in the movie's user data, or in the slack of the media data.
+pub struct MP4Carrier;
+
+impl Carrier for MP4Carrier {
+    fn name(&self) -> &'static str {
+        "mp4"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &["free", "udta", "mdat"]
+    }
+
+    fn parse(&self, data: &[u8], method: &str) -> Result<Box<dyn Cover>, Error> {
+        let error = |error| Error::MP4 { error, offset: 0 };
+        match method {
+            "free" => Ok(Box::new(
+                BoxCover::new(MP4::parse(data)?, BoxPlace::TopLevel).map_err(error)?,
+            )),
+            "udta" => Ok(Box::new(
+                BoxCover::new(MP4::parse(data)?, BoxPlace::UserData).map_err(error)?,
+            )),
+            "mdat" => Ok(Box::new(SlackCover::new(MP4::parse(data)?).map_err(error)?)),
+            _ => Err(Error::UnsupportedMethod {
+                format: self.name(),
+                method: method.to_string(),
+            }),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+/// Error type for ISO base media file parsing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MP4ParseError {
+    /// A box is shorter than its header or runs past the end of its parent.
+    TruncatedBox,
+    /// A box does not have the layout its type requires.
+    InvalidBox { kind: [u8; 4] },
+    /// A box that the chosen method needs is missing.
+    MissingBox { kind: [u8; 4] },
+    /// The file is fragmented, so its media data is located by movie fragments rather than by chunk offsets.
+    Fragmented,
+}
+
+impl MP4ParseError {
+    pub fn description(&self) -> String {
+        match self {
+            MP4ParseError::TruncatedBox => {
+                String::from("A box extends past the end of its parent.")
+            }
+            MP4ParseError::InvalidBox { kind } => {
+                format!("Invalid {} box.", String::from_utf8_lossy(kind))
+            }
+            MP4ParseError::MissingBox { kind } => {
+                format!("The file has no {} box.", String::from_utf8_lossy(kind))
+            }
+            MP4ParseError::Fragmented => String::from("Fragmented files cannot be rearranged."),
+        }
+    }
+}
+
+impl std::fmt::Display for MP4ParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(&self.description())
+    }
+}
+
+impl std::error::Error for MP4ParseError {}
@@ -0,0 +1,129 @@
+use crate::mp4::{MP4Box, MP4ParseError};
+
+/// Reads a big-endian field of up to eight bytes from a box payload.
+fn field(data: &[u8], offset: usize, width: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset + width)?;
+    Some(
+        bytes
+            .iter()
+            .fold(0, |value, byte| value << 8 | *byte as u64),
+    )
+}
+
+/// Reads the entry count of a table box, checking that entries of `entry_bits` bits each fit in the payload after it.
+/// Counts are read from the file, so they must be checked against the box before anything is allocated for them.
+fn entry_count(data: &[u8], offset: usize, entry_bits: usize) -> Option<usize> {
+    let count = field(data, offset, 4)? as usize;
+    let available = data.len().checked_sub(offset + 4)?;
+    (count.checked_mul(entry_bits)? <= available * 8).then_some(count)
+}
+
+/// Returns the width of the entries of a chunk offset box: 4 bytes in `stco`, 8 in `co64`.
+fn offset_width(chunk_offsets: &MP4Box) -> usize {
+    match &chunk_offsets.kind {
+        b"co64" => 8,
+        _ => 4,
+    }
+}
+
+/// Reads the entries of a `stco` or `co64` box.
+pub fn read_chunk_offsets(chunk_offsets: &MP4Box) -> Result<Vec<u64>, MP4ParseError> {
+    let invalid = MP4ParseError::InvalidBox {
+        kind: chunk_offsets.kind,
+    };
+    let data = &chunk_offsets.data;
+    let width = offset_width(chunk_offsets);
+    let count = entry_count(data, 4, 8 * width).ok_or(invalid.clone())?;
+    (0..count)
+        .map(|index| field(data, 8 + index * width, width).ok_or(invalid.clone()))
+        .collect()
+}
+
+/// Returns the payload of a `stco` or `co64` box with its entries replaced, keeping its version and flags.
+pub fn write_chunk_offsets(chunk_offsets: &MP4Box, offsets: &[u64]) -> Vec<u8> {
+    let width = offset_width(chunk_offsets);
+    let mut data = chunk_offsets.data[..8].to_vec();
+    for offset in offsets {
+        data.extend(&offset.to_be_bytes()[8 - width..]);
+    }
+    data
+}
+
+/// Reads the sample sizes of a sample table from its `stsz` or `stz2` box.
+/// Samples of a single size are not listed, so their count is checked against the length of the file, which they must fit in.
+pub fn sample_sizes(stbl: &MP4Box, file_length: u64) -> Result<Vec<u64>, MP4ParseError> {
+    if let Some(stsz) = stbl.child(b"stsz") {
+        let invalid = MP4ParseError::InvalidBox { kind: *b"stsz" };
+        let size = field(&stsz.data, 4, 4).ok_or(invalid.clone())?;
+        if size != 0 {
+            let count = field(&stsz.data, 8, 4).ok_or(invalid.clone())?;
+            if count * size > file_length {
+                return Err(invalid);
+            }
+            return Ok(vec![size; count as usize]);
+        }
+        let count = entry_count(&stsz.data, 8, 32).ok_or(invalid.clone())?;
+        return (0..count)
+            .map(|index| field(&stsz.data, 12 + 4 * index, 4).ok_or(invalid.clone()))
+            .collect();
+    }
+
+    let stz2 = stbl
+        .child(b"stz2")
+        .ok_or(MP4ParseError::MissingBox { kind: *b"stsz" })?;
+    let invalid = MP4ParseError::InvalidBox { kind: *b"stz2" };
+    let width = field(&stz2.data, 7, 1).ok_or(invalid.clone())? as usize;
+    let count = entry_count(&stz2.data, 8, width).ok_or(invalid.clone())?;
+    (0..count)
+        .map(|index| match width {
+            4 => {
+                let byte = field(&stz2.data, 12 + index / 2, 1)?;
+                Some(match index % 2 {
+                    0 => byte >> 4,
+                    _ => byte & 0x0F,
+                })
+            }
+            8 | 16 => field(&stz2.data, 12 + index * width / 8, width / 8),
+            _ => None,
+        })
+        .collect::<Option<Vec<u64>>>()
+        .ok_or(invalid)
+}
+
+/// Returns the length in bytes of each of the chunks of a sample table, from its sample-to-chunk and sample size boxes.
+pub fn chunk_lengths(
+    stbl: &MP4Box,
+    chunks: usize,
+    file_length: u64,
+) -> Result<Vec<u64>, MP4ParseError> {
+    let stsc = stbl
+        .child(b"stsc")
+        .ok_or(MP4ParseError::MissingBox { kind: *b"stsc" })?;
+    let invalid = MP4ParseError::InvalidBox { kind: *b"stsc" };
+    let count = entry_count(&stsc.data, 4, 96).ok_or(invalid.clone())?;
+    let runs = (0..count)
+        .map(|index| {
+            let first_chunk = field(&stsc.data, 8 + 12 * index, 4)?;
+            let samples = field(&stsc.data, 12 + 12 * index, 4)?;
+            Some((first_chunk, samples))
+        })
+        .collect::<Option<Vec<(u64, u64)>>>()
+        .ok_or(invalid)?;
+
+    let sizes = sample_sizes(stbl, file_length)?;
+    let mut sample = 0;
+    let lengths = (1..=chunks as u64)
+        .map(|chunk| {
+            let run = runs.partition_point(|(first_chunk, _)| *first_chunk <= chunk);
+            let samples = match run {
+                0 => 0,
+                run => runs[run - 1].1 as usize,
+            };
+            let end = (sample + samples).min(sizes.len());
+            let length = sizes[sample.min(end)..end].iter().sum();
+            sample = end;
+            length
+        })
+        .collect();
+    Ok(lengths)
+}
@@ -0,0 +1,161 @@
+use crate::carrier::{embed_growing, Cover};
+use crate::mp4::{MP4ParseError, MediaRange, MP4};
+use crate::stego::{EmbedOptions, StegoError, StegoKey};
+
+/// Returns the bytes of the media data that no chunk covers, in `mdat` boxes that hold at least one chunk.
+fn find_slack(mp4: &MP4) -> Result<Vec<MediaRange>, MP4ParseError> {
+    let mut chunks = mp4.chunks()?;
+    chunks.sort_by_key(|chunk| (chunk.mdat, chunk.offset));
+    let boxes = mp4.media_data_boxes();
+    let mut slack = Vec::new();
+    for (mdat, index) in boxes.iter().enumerate() {
+        let mut covered = chunks.iter().filter(|chunk| chunk.mdat == mdat).peekable();
+        if covered.peek().is_none() {
+            continue;
+        }
+        let mut cursor = 0;
+        let length = mp4.boxes[*index].data.len() as u64;
+        for chunk in covered.chain([&MediaRange {
+            mdat,
+            offset: length,
+            length: 0,
+        }]) {
+            if chunk.offset > cursor {
+                slack.push(MediaRange {
+                    mdat,
+                    offset: cursor,
+                    length: chunk.offset - cursor,
+                });
+            }
+            cursor = cursor.max(chunk.offset + chunk.length);
+        }
+    }
+    Ok(slack)
+}
+
+/// An ISO base media file carrying payload bits in the slack of its media data: bytes inside `mdat` boxes that no chunk of any track points at.
+/// Players only read the samples, so the slack is never decoded, though a file whose media data has gaps is unusual for some muxers.
+/// When the slack is too small, gaps are opened between chunks spread over the media data, and the chunk offsets move with them.
+pub struct SlackCover {
+    pub mp4: MP4,
+    slack: Vec<MediaRange>,
+    /// Index of the first slack byte in each range, followed by the total.
+    starts: Vec<usize>,
+    /// Indices in `boxes` of the `mdat` boxes.
+    media: Vec<usize>,
+}
+
+impl SlackCover {
+    pub fn new(mp4: MP4) -> Result<SlackCover, MP4ParseError> {
+        if mp4.is_fragmented() {
+            return Err(MP4ParseError::Fragmented);
+        }
+        let slack = find_slack(&mp4)?;
+        let mut cover = SlackCover {
+            media: mp4.media_data_boxes(),
+            mp4,
+            slack,
+            starts: Vec::new(),
+        };
+        cover.index();
+        Ok(cover)
+    }
+
+    fn index(&mut self) {
+        self.starts = vec![0];
+        for range in &self.slack {
+            self.starts
+                .push(self.starts[self.starts.len() - 1] + range.length as usize);
+        }
+    }
+
+    /// Returns the index in `boxes` of the `mdat` box holding a byte of slack, and its offset in the box payload.
+    fn locate(&self, byte: usize) -> (usize, usize) {
+        let range = self.starts.partition_point(|start| *start <= byte) - 1;
+        let slack = &self.slack[range];
+        (
+            self.media[slack.mdat],
+            slack.offset as usize + byte - self.starts[range],
+        )
+    }
+
+    /// Opens `length` more bytes of slack, in gaps after chunks taken at even intervals over the media data.
+    pub fn reserve(&mut self, length: usize) {
+        let mut chunks = self.mp4.chunks().expect("chunks were read by new");
+        chunks.sort_by_key(|chunk| (chunk.mdat, chunk.offset + chunk.length));
+        let gaps = chunks.len().min(length);
+        for mdat in 0..self.media.len() {
+            let insertions: Vec<(u64, usize)> = (0..gaps)
+                .filter(|gap| chunks[gap * chunks.len() / gaps].mdat == mdat)
+                .map(|gap| {
+                    let chunk = chunks[gap * chunks.len() / gaps];
+                    let share = length / gaps + (gap < length % gaps) as usize;
+                    (chunk.offset + chunk.length, share)
+                })
+                .collect();
+            if !insertions.is_empty() {
+                self.mp4.insert_media_data(mdat, &insertions);
+            }
+        }
+        self.slack = find_slack(&self.mp4).expect("chunks were read by new");
+        self.index();
+    }
+}
+
+impl Cover for SlackCover {
+    fn positions(&self) -> usize {
+        self.starts[self.starts.len() - 1] * 8
+    }
+
+    fn read_bit(&self, position: usize) -> bool {
+        let (index, offset) = self.locate(position / 8);
+        self.mp4.boxes[index].data[offset] >> (7 - position % 8) & 1 == 1
+    }
+
+    fn write_bit(&mut self, position: usize, bit: bool) {
+        let (index, offset) = self.locate(position / 8);
+        let mask = 1 << (7 - position % 8);
+        let byte = &mut self.mp4.boxes[index].data[offset];
+        *byte = (*byte & !mask) | if bit { mask } else { 0 };
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.mp4.to_bytes()
+    }
+
+    /// Existing slack is used first, and only the shortfall is opened up.
+    fn embed_raw(
+        &mut self,
+        key: &StegoKey,
+        data: &[u8],
+        options: EmbedOptions,
+    ) -> Result<(), StegoError> {
+        embed_growing(self, key, data, options, |cover, length| {
+            cover.reserve(length);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mp4::test::{mp4_bytes, read_samples, samples};
+
+    #[test]
+    fn opens_slack_between_chunks() {
+        let mut cover = SlackCover::new(MP4::parse(&mp4_bytes(40, false, true)).unwrap()).unwrap();
+        assert_eq!(cover.positions(), 8 * 3 * 8);
+        cover.reserve(21);
+        assert_eq!(cover.positions(), 8 * (3 * 8 + 21));
+        for position in 0..cover.positions() {
+            cover.write_bit(position, true);
+        }
+
+        let mp4 = MP4::parse(&cover.to_bytes()).unwrap();
+        assert_eq!(read_samples(&mp4), samples(40));
+        let cover = SlackCover::new(mp4).unwrap();
+        assert_eq!(cover.slack.len(), 8);
+        assert!((0..cover.positions()).all(|position| cover.read_bit(position)));
+    }
+}
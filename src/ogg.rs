@@ -0,0 +1,308 @@
+use crate::Error;
+
+pub use self::commentcover::CommentCover;
+pub use self::oggcarrier::OggCarrier;
+pub use self::oggpage::OggPage;
+pub use self::oggparseerror::OggParseError;
+pub use self::pagecover::PageCover;
+
+mod commentcover;
+mod oggcarrier;
+mod oggpage;
+mod oggparseerror;
+mod pagecover;
+
+/// A parsed Ogg bitstream: its pages in file order, with the packets of every logical stream left in place.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ogg {
+    pub pages: Vec<OggPage>,
+    /// Bytes after the last page, written back unchanged.
+    pub trailing: Vec<u8>,
+}
+
+/// A packet to lay out on pages by [`paginate`].
+pub struct PacketLayout<'a> {
+    pub data: &'a [u8],
+    /// Granule position at the end of the packet.
+    pub granule_position: u64,
+    /// Whether the page must end after this packet.
+    pub page_break: bool,
+}
+
+impl Ogg {
+    /// Parses an Ogg bitstream, checking the CRC of every page.
+    pub fn parse(data: &[u8]) -> Result<Ogg, Error> {
+        let mut pages = Vec::new();
+        let mut offset = 0;
+        while offset == 0 || data[offset..].starts_with(b"OggS") {
+            let (page, length) =
+                OggPage::parse(&data[offset..]).map_err(|error| Error::Ogg { error, offset })?;
+            pages.push(page);
+            offset += length;
+        }
+        Ok(Ogg {
+            pages,
+            trailing: data[offset..].to_vec(),
+        })
+    }
+
+    /// Serializes the bitstream, recomputing the CRC of every page.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for page in &self.pages {
+            bytes.extend(page.to_bytes());
+        }
+        bytes.extend(&self.trailing);
+        bytes
+    }
+
+    /// Returns the serial number of the first logical stream whose first packet starts with `magic`.
+    pub fn find_stream(&self, magic: &[u8]) -> Option<u32> {
+        self.pages
+            .iter()
+            .find(|page| page.header_type & OggPage::BEGIN != 0 && page.data.starts_with(magic))
+            .map(|page| page.serial)
+    }
+
+    /// Returns the indices of the pages of a logical stream.
+    pub fn stream_pages(&self, serial: u32) -> Vec<usize> {
+        (0..self.pages.len())
+            .filter(|index| self.pages[*index].serial == serial)
+            .collect()
+    }
+
+    /// Reassembles the packets carried by the given pages, which must not start in the middle of a packet.
+    /// A packet left unfinished on the last page is dropped.
+    pub fn packets(&self, pages: &[usize]) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
+        let mut packet = Vec::new();
+        for page in pages.iter().map(|index| &self.pages[*index]) {
+            let mut offset = 0;
+            for (length, complete) in page.pieces() {
+                packet.extend(&page.data[offset..offset + length]);
+                offset += length;
+                if complete {
+                    packets.push(std::mem::take(&mut packet));
+                }
+            }
+        }
+        packets
+    }
+
+    /// Replaces the given pages of a logical stream, which must be in file order, with new ones.
+    /// The new pages take the place of the old ones in turn, and the sequence numbers of the stream are renumbered from the first of them on.
+    pub fn replace_pages(&mut self, slots: &[usize], mut new: Vec<OggPage>) {
+        let (Some(first), Some(last)) = (slots.first(), slots.last()) else {
+            return;
+        };
+        let serial = self.pages[*first].serial;
+        let end = self.pages[*last].header_type & OggPage::END;
+        if let Some(page) = new.last_mut() {
+            page.header_type |= end;
+        }
+
+        let mut sequence = self.pages[*first].sequence;
+        for page in &mut new {
+            page.sequence = sequence;
+            sequence = sequence.wrapping_add(1);
+        }
+        for page in &mut self.pages[last + 1..] {
+            if page.serial == serial {
+                page.sequence = sequence;
+                sequence = sequence.wrapping_add(1);
+            }
+        }
+
+        let mut new = new.into_iter();
+        let old = std::mem::take(&mut self.pages);
+        for (index, page) in old.into_iter().enumerate() {
+            if index == *last {
+                self.pages.extend(new.by_ref());
+            } else if slots.binary_search(&index).is_ok() {
+                self.pages.extend(new.next());
+            } else {
+                self.pages.push(page);
+            }
+        }
+    }
+}
+
+/// Lays out packets on new pages of a logical stream, ending a page after every packet that asks for it and whenever a page runs out of lacing values.
+/// Each page gets the granule position of the last packet that ends on it. Sequence numbers are left for [`Ogg::replace_pages`] to assign.
+pub fn paginate(serial: u32, packets: &[PacketLayout]) -> Vec<OggPage> {
+    let mut pages = Vec::new();
+    let mut page = OggPage::new(serial, 0);
+    for packet in packets {
+        let mut lacing = vec![255; packet.data.len() / 255];
+        lacing.push((packet.data.len() % 255) as u8);
+        let mut offset = 0;
+        for (index, value) in lacing.into_iter().enumerate() {
+            if page.segments.len() == OggPage::MAX_SEGMENTS {
+                let continued = match index {
+                    0 => 0,
+                    _ => OggPage::CONTINUED,
+                };
+                pages.push(std::mem::replace(
+                    &mut page,
+                    OggPage::new(serial, continued),
+                ));
+            }
+            page.segments.push(value);
+            page.data
+                .extend(&packet.data[offset..offset + value as usize]);
+            offset += value as usize;
+        }
+        page.granule_position = packet.granule_position;
+        if packet.page_break {
+            pages.push(std::mem::replace(&mut page, OggPage::new(serial, 0)));
+        }
+    }
+    if !page.segments.is_empty() {
+        pages.push(page);
+    }
+    pages
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// Serial number of the stream built by [`ogg_bytes`].
+    pub(crate) const SERIAL: u32 = 0x5EED;
+
+    /// Builds an Ogg Opus stream of `count` 20 ms audio packets, 25 to a page, whose comment header ends with 64 bytes of padding.
+    /// The packets alternate between single frames and pairs of 10 ms frames, and some are long enough to take two lacing values.
+    pub(crate) fn ogg_bytes(count: usize) -> Vec<u8> {
+        let mut head = b"OpusHead".to_vec();
+        head.extend([1, 2]);
+        head.extend(312u16.to_le_bytes());
+        head.extend(48000u32.to_le_bytes());
+        head.extend([0, 0, 0]);
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend(6u32.to_le_bytes());
+        tags.extend(b"vendor");
+        tags.extend(1u32.to_le_bytes());
+        tags.extend(11u32.to_le_bytes());
+        tags.extend(b"TITLE=Cover");
+        tags.extend([0; 64]);
+
+        let audio: Vec<Vec<u8>> = (0..count)
+            .map(|number| {
+                // SILK wideband 20 ms frames, or CELT fullband 10 ms frames packed two to a packet.
+                let toc = match number % 2 {
+                    0 => 9 << 3,
+                    _ => 30 << 3 | 1,
+                };
+                let mut packet = vec![toc];
+                packet.extend((0..40 + number * 37 % 300).map(|byte| (byte * number) as u8));
+                packet
+            })
+            .collect();
+
+        let mut pages = paginate(
+            SERIAL,
+            &[PacketLayout {
+                data: &head,
+                granule_position: 0,
+                page_break: true,
+            }],
+        );
+        pages[0].header_type = OggPage::BEGIN;
+        pages.extend(paginate(
+            SERIAL,
+            &[PacketLayout {
+                data: &tags,
+                granule_position: 0,
+                page_break: true,
+            }],
+        ));
+        let layout: Vec<PacketLayout> = audio
+            .iter()
+            .enumerate()
+            .map(|(number, packet)| PacketLayout {
+                data: packet,
+                granule_position: 312 + 960 * (number as u64 + 1),
+                page_break: number % 25 == 24 || number + 1 == count,
+            })
+            .collect();
+        pages.extend(paginate(SERIAL, &layout));
+        let last = pages.len() - 1;
+        pages[last].header_type |= OggPage::END;
+        for (sequence, page) in pages.iter_mut().enumerate() {
+            page.sequence = sequence as u32;
+        }
+        Ogg {
+            pages,
+            trailing: Vec::new(),
+        }
+        .to_bytes()
+    }
+
+    #[test]
+    fn writes_pages_back_byte_for_byte() {
+        let bytes = ogg_bytes(60);
+        let ogg = Ogg::parse(&bytes).unwrap();
+        assert_eq!(ogg.pages.len(), 5);
+        assert_eq!(
+            ogg.pages
+                .iter()
+                .map(|page| page.sequence)
+                .collect::<Vec<u32>>(),
+            [0, 1, 2, 3, 4]
+        );
+        assert_eq!(ogg.pages[4].granule_position, 312 + 960 * 60);
+        assert_eq!(ogg.to_bytes(), bytes);
+        assert_eq!(ogg.packets(&ogg.stream_pages(SERIAL)).len(), 62);
+
+        let mut corrupted = bytes.clone();
+        corrupted[40] ^= 1;
+        assert!(matches!(
+            Ogg::parse(&corrupted),
+            Err(Error::Ogg {
+                error: OggParseError::ChecksumMismatch,
+                offset: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_page() {
+        let bytes = ogg_bytes(60);
+        let ogg = Ogg::parse(&bytes).unwrap();
+        let last = bytes.len() - ogg.pages[4].to_bytes().len();
+        for length in [last + 10, bytes.len() - 1] {
+            assert!(matches!(
+                Ogg::parse(&bytes[..length]),
+                Err(Error::Ogg {
+                    error: OggParseError::TruncatedPage,
+                    offset
+                }) if offset == last
+            ));
+        }
+    }
+
+    #[test]
+    fn spreads_long_packets_over_pages() {
+        let packet = vec![7; 300 * 255];
+        let pages = paginate(
+            SERIAL,
+            &[
+                PacketLayout {
+                    data: &packet,
+                    granule_position: 100,
+                    page_break: false,
+                },
+                PacketLayout {
+                    data: b"end",
+                    granule_position: 200,
+                    page_break: true,
+                },
+            ],
+        );
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].granule_position, u64::MAX);
+        assert_eq!(pages[1].header_type, OggPage::CONTINUED);
+        assert_eq!(pages[1].granule_position, 200);
+        assert_eq!(pages[1].pieces(), [(45 * 255, true), (3, true)]);
+    }
+}
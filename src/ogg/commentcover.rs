@@ -0,0 +1,199 @@
+use std::ops::Range;
+
+use crate::carrier::{embed_growing, Cover};
+use crate::ogg::{paginate, Ogg, OggPage, OggParseError, PacketLayout};
+use crate::stego::{EmbedOptions, StegoError, StegoKey};
+
+/// Codecs whose comment header can carry padding: the signature of their identification header, the signature of their comment header and the number of header packets after the identification header.
+const CODECS: [(&[u8], &[u8], usize); 2] = [
+    (b"OpusHead", b"OpusTags", 1),
+    (b"\x01vorbis", b"\x03vorbis", 2),
+];
+
+/// The header packets of a logical stream, located on their pages.
+struct Headers {
+    /// Pages carrying the header packets after the identification header, which start and end with those packets.
+    pages: Vec<usize>,
+    /// Bytes of the comment header that follow its last comment, counted from the start of the first page.
+    padding: Range<usize>,
+}
+
+impl Headers {
+    fn locate(ogg: &Ogg) -> Result<Headers, OggParseError> {
+        let (identification, signature, count) = CODECS
+            .into_iter()
+            .find(|(identification, _, _)| ogg.find_stream(identification).is_some())
+            .ok_or(OggParseError::UnsupportedCodec)?;
+        let serial = ogg.find_stream(identification).unwrap();
+        let mut stream = ogg.stream_pages(serial).into_iter();
+        if stream.next().map(|index| ogg.pages[index].pieces().len()) != Some(1) {
+            return Err(OggParseError::InvalidHeaders);
+        }
+
+        let mut pages = Vec::new();
+        let mut packets = 0;
+        while packets < count {
+            let index = stream.next().ok_or(OggParseError::InvalidHeaders)?;
+            let page = &ogg.pages[index];
+            if pages.is_empty() && page.header_type & OggPage::CONTINUED != 0 {
+                return Err(OggParseError::InvalidHeaders);
+            }
+            pages.push(index);
+            packets += page
+                .pieces()
+                .iter()
+                .filter(|(_, complete)| *complete)
+                .count();
+        }
+        let last = &ogg.pages[*pages.last().unwrap()];
+        if packets > count || last.segments.last() == Some(&255) {
+            return Err(OggParseError::InvalidHeaders);
+        }
+
+        let comment = &ogg.packets(&pages)[0];
+        if !comment.starts_with(signature) {
+            return Err(OggParseError::InvalidHeaders);
+        }
+        let end = comment_end(comment, signature).ok_or(OggParseError::InvalidHeaders)?;
+        Ok(Headers {
+            pages,
+            padding: end..comment.len(),
+        })
+    }
+
+    /// Returns the page and offset within it of a byte of padding.
+    fn locate_byte(&self, ogg: &Ogg, byte: usize) -> (usize, usize) {
+        let mut offset = self.padding.start + byte;
+        for index in &self.pages {
+            let length = ogg.pages[*index].data.len();
+            if offset < length {
+                return (*index, offset);
+            }
+            offset -= length;
+        }
+        unreachable!("padding lies within the header pages")
+    }
+}
+
+/// Returns the offset where the comments of a comment header end: past the vendor string, the comments and, for Vorbis, the framing bit.
+fn comment_end(packet: &[u8], signature: &[u8]) -> Option<usize> {
+    let read_length = |offset: usize| -> Option<usize> {
+        let bytes = packet.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    };
+    let mut offset = signature.len();
+    offset = offset.checked_add(4 + read_length(offset)?)?;
+    let count = read_length(offset)?;
+    offset += 4;
+    for _ in 0..count {
+        offset = offset.checked_add(4 + read_length(offset)?)?;
+    }
+    if signature == b"\x03vorbis" {
+        if packet.get(offset)? & 1 == 0 {
+            return None;
+        }
+        offset += 1;
+    }
+    (offset <= packet.len()).then_some(offset)
+}
+
+/// An Ogg Opus or Vorbis stream carrying payload bits in the padding after the last comment of its comment header.
+/// Players skip the padding, and Opus encoders leave some there for tags to be added later, so a little of it looks normal.
+/// The padding grows to fit the payload, spreading the header over as many pages as it takes.
+pub struct CommentCover {
+    pub ogg: Ogg,
+    headers: Headers,
+}
+
+impl CommentCover {
+    pub fn new(ogg: Ogg) -> Result<CommentCover, OggParseError> {
+        let headers = Headers::locate(&ogg)?;
+        Ok(CommentCover { ogg, headers })
+    }
+
+    /// Replaces the padding with `length` zero bytes and lays the header packets out on pages again.
+    pub fn reserve(&mut self, length: usize) {
+        let mut packets = self.ogg.packets(&self.headers.pages);
+        packets[0].resize(self.headers.padding.start, 0);
+        packets[0].resize(self.headers.padding.start + length, 0);
+        let layout: Vec<PacketLayout> = packets
+            .iter()
+            .enumerate()
+            .map(|(index, packet)| PacketLayout {
+                data: packet,
+                granule_position: 0,
+                page_break: index + 1 == packets.len(),
+            })
+            .collect();
+        let serial = self.ogg.pages[self.headers.pages[0]].serial;
+        let pages = paginate(serial, &layout);
+        self.ogg.replace_pages(&self.headers.pages, pages);
+        self.headers = Headers::locate(&self.ogg).expect("headers stay valid when padded");
+    }
+}
+
+impl Cover for CommentCover {
+    fn positions(&self) -> usize {
+        self.headers.padding.len() * 8
+    }
+
+    fn read_bit(&self, position: usize) -> bool {
+        let (page, offset) = self.headers.locate_byte(&self.ogg, position / 8);
+        self.ogg.pages[page].data[offset] >> (7 - position % 8) & 1 == 1
+    }
+
+    fn write_bit(&mut self, position: usize, bit: bool) {
+        let (page, offset) = self.headers.locate_byte(&self.ogg, position / 8);
+        let mask = 1 << (7 - position % 8);
+        let byte = &mut self.ogg.pages[page].data[offset];
+        *byte = (*byte & !mask) | if bit { mask } else { 0 };
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.ogg.to_bytes()
+    }
+
+    /// The padding is emptied and then grown to the payload.
+    fn embed_raw(
+        &mut self,
+        key: &StegoKey,
+        data: &[u8],
+        options: EmbedOptions,
+    ) -> Result<(), StegoError> {
+        self.reserve(0);
+        embed_growing(self, key, data, options, |cover, length| {
+            cover.reserve(length);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ogg::test::ogg_bytes;
+
+    #[test]
+    fn grows_padding_over_several_pages() {
+        let mut cover = CommentCover::new(Ogg::parse(&ogg_bytes(30)).unwrap()).unwrap();
+        assert_eq!(cover.positions(), 64 * 8);
+
+        cover.reserve(100_000);
+        cover.write_bit(8 * 99_999 + 7, true);
+        let ogg = Ogg::parse(&cover.to_bytes()).unwrap();
+        assert_eq!(ogg.pages.len(), 1 + 2 + 2);
+        assert_eq!(ogg.pages[1].granule_position, u64::MAX);
+        assert_eq!(ogg.pages[2].header_type, OggPage::CONTINUED);
+        assert_eq!(ogg.pages[2].granule_position, 0);
+        assert!(ogg
+            .pages
+            .iter()
+            .enumerate()
+            .all(|(sequence, page)| page.sequence == sequence as u32));
+
+        let cover = CommentCover::new(ogg).unwrap();
+        assert_eq!(cover.positions(), 100_000 * 8);
+        assert!(cover.read_bit(8 * 99_999 + 7));
+        assert!(!cover.read_bit(8 * 99_999 + 6));
+    }
+}
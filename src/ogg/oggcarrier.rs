@@ -0,0 +1,30 @@
+use crate::carrier::{Carrier, Cover};
+use crate::ogg::{CommentCover, Ogg, PageCover};
+use crate::Error;
+
+/// The Ogg container, embedding in the padding of an Opus or Vorbis comment header or in where the pages of an Opus stream end.
+pub struct OggCarrier;
+
+impl Carrier for OggCarrier {
+    fn name(&self) -> &'static str {
+        "ogg"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &["comment", "pages"]
+    }
+
+    fn parse(&self, data: &[u8], method: &str) -> Result<Box<dyn Cover>, Error> {
+        let error = |error| Error::Ogg { error, offset: 0 };
+        match method {
+            "comment" => Ok(Box::new(
+                CommentCover::new(Ogg::parse(data)?).map_err(error)?,
+            )),
+            "pages" => Ok(Box::new(PageCover::new(Ogg::parse(data)?).map_err(error)?)),
+            _ => Err(Error::UnsupportedMethod {
+                format: self.name(),
+                method: method.to_string(),
+            }),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crc::{Algorithm, Crc};
+
+use crate::ogg::OggParseError;
+
+/// CRC-32 of an Ogg page: polynomial 0x04C11DB7, initial value zero, neither reflected nor inverted.
+const OGG_CRC: Crc<u32> = Crc::<u32>::new(&Algorithm {
+    width: 32,
+    poly: 0x04C1_1DB7,
+    init: 0,
+    refin: false,
+    refout: false,
+    xorout: 0,
+    check: 0x89A1_897F,
+    residue: 0,
+});
+
+/// Length of the fixed part of a page header, up to and including the segment count.
+const HEADER_LENGTH: usize = 27;
+
+/// Offset of the checksum in the page header.
+const CHECKSUM_OFFSET: usize = 22;
+
+/// A page of an Ogg bitstream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OggPage {
+    pub header_type: u8,
+    /// Position, in codec units, at the end of the last packet that ends on this page, or `u64::MAX` if none does.
+    pub granule_position: u64,
+    pub serial: u32,
+    pub sequence: u32,
+    /// Lacing values: each packet is split into 255-byte segments, and a value below 255 ends it.
+    pub segments: Vec<u8>,
+    pub data: Vec<u8>,
+}
+
+impl OggPage {
+    /// Header type flag of a page that starts in the middle of a packet.
+    pub const CONTINUED: u8 = 0x01;
+    /// Header type flag of the first page of a logical stream.
+    pub const BEGIN: u8 = 0x02;
+    /// Header type flag of the last page of a logical stream.
+    pub const END: u8 = 0x04;
+
+    /// Most lacing values a page can hold.
+    pub const MAX_SEGMENTS: usize = 255;
+
+    pub fn new(serial: u32, header_type: u8) -> OggPage {
+        OggPage {
+            header_type,
+            granule_position: u64::MAX,
+            serial,
+            sequence: 0,
+            segments: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// Parses the page at the start of the data, checking its CRC, and returns it with its length.
+    pub fn parse(data: &[u8]) -> Result<(OggPage, usize), OggParseError> {
+        if !data.starts_with(b"OggS") {
+            return Err(OggParseError::NotOgg);
+        }
+        let header = data
+            .get(..HEADER_LENGTH)
+            .ok_or(OggParseError::TruncatedPage)?;
+        if header[4] != 0 {
+            return Err(OggParseError::UnsupportedVersion { version: header[4] });
+        }
+        let count = header[26] as usize;
+        let segments = data
+            .get(HEADER_LENGTH..HEADER_LENGTH + count)
+            .ok_or(OggParseError::TruncatedPage)?;
+        let start = HEADER_LENGTH + count;
+        let length = start + segments.iter().map(|value| *value as usize).sum::<usize>();
+        let body = data
+            .get(start..length)
+            .ok_or(OggParseError::TruncatedPage)?;
+
+        let page = OggPage {
+            header_type: header[5],
+            granule_position: u64::from_le_bytes(header[6..14].try_into().unwrap()),
+            serial: u32::from_le_bytes(header[14..18].try_into().unwrap()),
+            sequence: u32::from_le_bytes(header[18..22].try_into().unwrap()),
+            segments: segments.to_vec(),
+            data: body.to_vec(),
+        };
+        if page.to_bytes() != data[..length] {
+            return Err(OggParseError::ChecksumMismatch);
+        }
+        Ok((page, length))
+    }
+
+    /// Serializes the page with a freshly computed checksum.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + self.segments.len() + self.data.len());
+        bytes.extend(b"OggS");
+        bytes.extend([0, self.header_type]);
+        bytes.extend(self.granule_position.to_le_bytes());
+        bytes.extend(self.serial.to_le_bytes());
+        bytes.extend(self.sequence.to_le_bytes());
+        bytes.extend([0; 4]);
+        bytes.push(self.segments.len() as u8);
+        bytes.extend(&self.segments);
+        bytes.extend(&self.data);
+        let checksum = OGG_CRC.checksum(&bytes);
+        bytes[CHECKSUM_OFFSET..CHECKSUM_OFFSET + 4].copy_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    /// Returns the pieces of packets on the page: their lengths, and whether each one ends its packet.
+    pub fn pieces(&self) -> Vec<(usize, bool)> {
+        let mut pieces = Vec::new();
+        let mut length = 0;
+        for value in &self.segments {
+            length += *value as usize;
+            if *value < 255 {
+                pieces.push((length, true));
+                length = 0;
+            }
+        }
+        if self.segments.last() == Some(&255) {
+            pieces.push((length, false));
+        }
+        pieces
+    }
+}
@@ -0,0 +1,45 @@
+/// Error type for Ogg parsing.
+#[derive(Debug, PartialEq)]
+pub enum OggParseError {
+    NotOgg,
+    TruncatedPage,
+    UnsupportedVersion {
+        version: u8,
+    },
+    ChecksumMismatch,
+    /// No logical stream holds a codec the chosen method supports.
+    UnsupportedCodec,
+    /// The header packets of a logical stream are not laid out as their codec requires.
+    InvalidHeaders,
+}
+
+impl OggParseError {
+    pub fn description(&self) -> String {
+        match self {
+            OggParseError::NotOgg => String::from("Input does not start with an Ogg page."),
+            OggParseError::TruncatedPage => {
+                String::from("A page extends past the end of the data.")
+            }
+            OggParseError::UnsupportedVersion { version } => {
+                format!("Unsupported Ogg stream structure version {}.", version)
+            }
+            OggParseError::ChecksumMismatch => {
+                String::from("A page does not match its CRC-32 checksum.")
+            }
+            OggParseError::UnsupportedCodec => {
+                String::from("No logical stream holds a codec this method supports.")
+            }
+            OggParseError::InvalidHeaders => {
+                String::from("The header packets are not laid out on pages as the codec requires.")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for OggParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(&self.description())
+    }
+}
+
+impl std::error::Error for OggParseError {}
@@ -0,0 +1,233 @@
+use crate::carrier::Cover;
+use crate::ogg::{paginate, Ogg, OggPage, OggParseError, PacketLayout};
+
+/// Returns the number of 48 kHz samples an Opus packet decodes to, from its TOC byte and frame count.
+fn opus_duration(packet: &[u8]) -> Option<u64> {
+    let toc = *packet.first()?;
+    let config = (toc >> 3) as usize;
+    let frame = match config {
+        0..=11 => [480, 960, 1920, 2880][config % 4],
+        12..=15 => [480, 960][config % 2],
+        _ => [120, 240, 480, 960][config % 4],
+    };
+    let frames = match toc & 3 {
+        0 => 1,
+        1 | 2 => 2,
+        _ => (packet.get(1)? & 0x3F) as u64,
+    };
+    Some(frame * frames)
+}
+
+/// Returns how many lacing values a packet takes.
+fn lacing_values(packet: &[u8]) -> usize {
+    packet.len() / 255 + 1
+}
+
+/// An Ogg Opus stream carrying payload bits in where its audio pages end: each bit tells whether a page ends after a given packet.
+/// Pages are cut anew and their granule positions recomputed from the packet durations, so sequence numbers, granule positions and checksums stay valid.
+/// Packet boundaries where a page must end to stay within 255 lacing values carry no bit, and neither does the last page, whose granule position may trim the end of the stream.
+pub struct PageCover {
+    pub ogg: Ogg,
+    /// Pages whose layout is free: from the first audio page to the last one before the final page that ends with a whole packet.
+    pages: Vec<usize>,
+    packets: Vec<Vec<u8>>,
+    /// Granule position at the end of each packet.
+    granule_positions: Vec<u64>,
+    /// Whether a page ends after each packet.
+    breaks: Vec<bool>,
+    /// Index of the packet that each position follows.
+    boundaries: Vec<usize>,
+    changed: bool,
+}
+
+impl PageCover {
+    pub fn new(ogg: Ogg) -> Result<PageCover, OggParseError> {
+        let serial = ogg
+            .find_stream(b"OpusHead")
+            .ok_or(OggParseError::UnsupportedCodec)?;
+        let stream = ogg.stream_pages(serial);
+        let mut headers = 0;
+        let start = stream
+            .iter()
+            .position(|index| {
+                let done = headers >= 2;
+                headers += ogg.pages[*index]
+                    .pieces()
+                    .iter()
+                    .filter(|(_, complete)| *complete)
+                    .count();
+                done
+            })
+            .unwrap_or(stream.len());
+        let mut end = stream.len().saturating_sub(1).max(start);
+        while end > start
+            && ogg.pages[stream[end - 1]]
+                .segments
+                .last()
+                .is_none_or(|value| *value == 255)
+        {
+            end -= 1;
+        }
+
+        let mut cover = PageCover {
+            ogg,
+            pages: Vec::new(),
+            packets: Vec::new(),
+            granule_positions: Vec::new(),
+            breaks: Vec::new(),
+            boundaries: Vec::new(),
+            changed: false,
+        };
+        if end > start && cover.ogg.pages[stream[start]].header_type & OggPage::CONTINUED == 0 {
+            cover.layout(&stream[start..end]);
+        }
+        Ok(cover)
+    }
+
+    /// Reads the packets and page breaks of the given pages, leaving the cover without positions unless their granule positions follow from the packet durations.
+    fn layout(&mut self, pages: &[usize]) {
+        let packets = self.ogg.packets(pages);
+        let Some(durations) = packets
+            .iter()
+            .map(|packet| opus_duration(packet))
+            .collect::<Option<Vec<u64>>>()
+        else {
+            return;
+        };
+
+        let mut ends = Vec::new();
+        let mut count = 0;
+        for index in pages {
+            count += self.ogg.pages[*index]
+                .pieces()
+                .iter()
+                .filter(|(_, complete)| *complete)
+                .count();
+            ends.push(count);
+        }
+        let Some((first, first_end)) = pages.iter().zip(&ends).find(|(_, end)| **end > 0) else {
+            return;
+        };
+        let before: u64 = durations[..*first_end].iter().sum();
+        let mut granule_position = self.ogg.pages[*first].granule_position.wrapping_sub(before);
+        let granule_positions: Vec<u64> = durations
+            .iter()
+            .map(|duration| {
+                granule_position = granule_position.wrapping_add(*duration);
+                granule_position
+            })
+            .collect();
+
+        let mut breaks = vec![false; packets.len()];
+        let mut previous = 0;
+        for (index, end) in pages.iter().zip(&ends) {
+            let expected = match *end > previous {
+                true => granule_positions[end - 1],
+                false => u64::MAX,
+            };
+            if self.ogg.pages[*index].granule_position != expected {
+                return;
+            }
+            if *end > previous {
+                breaks[end - 1] = true;
+            }
+            previous = *end;
+        }
+
+        let mut boundaries = Vec::new();
+        let mut used = 0;
+        for index in 0..packets.len() - 1 {
+            used += lacing_values(&packets[index]);
+            if used + lacing_values(&packets[index + 1]) > OggPage::MAX_SEGMENTS {
+                breaks[index] = true;
+                used = 0;
+            } else {
+                boundaries.push(index);
+            }
+        }
+
+        self.pages = pages.to_vec();
+        self.packets = packets;
+        self.granule_positions = granule_positions;
+        self.breaks = breaks;
+        self.boundaries = boundaries;
+    }
+}
+
+impl Cover for PageCover {
+    fn positions(&self) -> usize {
+        self.boundaries.len()
+    }
+
+    fn read_bit(&self, position: usize) -> bool {
+        self.breaks[self.boundaries[position]]
+    }
+
+    fn write_bit(&mut self, position: usize, bit: bool) {
+        let packet = self.boundaries[position];
+        self.changed |= self.breaks[packet] != bit;
+        self.breaks[packet] = bit;
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        if !self.changed {
+            return self.ogg.to_bytes();
+        }
+        let layout: Vec<PacketLayout> = (0..self.packets.len())
+            .map(|index| PacketLayout {
+                data: &self.packets[index],
+                granule_position: self.granule_positions[index],
+                page_break: self.breaks[index] || index + 1 == self.packets.len(),
+            })
+            .collect();
+        let serial = self.ogg.pages[self.pages[0]].serial;
+        let mut ogg = self.ogg.clone();
+        ogg.replace_pages(&self.pages, paginate(serial, &layout));
+        ogg.to_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ogg::test::ogg_bytes;
+
+    #[test]
+    fn moves_page_breaks_and_keeps_granule_positions() {
+        let original = Ogg::parse(&ogg_bytes(100)).unwrap();
+        let mut cover = PageCover::new(original.clone()).unwrap();
+        assert_eq!(cover.pages, [2, 3, 4]);
+        assert_eq!(cover.positions(), 74);
+        assert!(cover.read_bit(24));
+        assert_eq!(cover.to_bytes(), original.to_bytes());
+
+        for position in 0..cover.positions() {
+            cover.write_bit(position, position % 3 == 0);
+        }
+        let ogg = Ogg::parse(&cover.to_bytes()).unwrap();
+        let pages = |ogg: &Ogg| (0..ogg.pages.len()).collect::<Vec<usize>>();
+        assert_eq!(
+            ogg.packets(&pages(&ogg)),
+            original.packets(&pages(&original))
+        );
+        let (last, original_last) = (ogg.pages.last().unwrap(), original.pages.last().unwrap());
+        assert_eq!(last.sequence as usize, ogg.pages.len() - 1);
+        assert_eq!(
+            (last.header_type, last.granule_position, &last.data),
+            (
+                original_last.header_type,
+                original_last.granule_position,
+                &original_last.data
+            )
+        );
+
+        let cover = PageCover::new(ogg).unwrap();
+        assert_eq!(cover.positions(), 74);
+        assert!((0..74).all(|position| cover.read_bit(position) == (position % 3 == 0)));
+        let mut granule_position = 312;
+        for (packet, expected) in cover.packets.iter().zip(&cover.granule_positions) {
+            granule_position += opus_duration(packet).unwrap();
+            assert_eq!(granule_position, *expected);
+        }
+    }
+}
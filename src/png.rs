@@ -0,0 +1,468 @@
+use std::io::{Read, Write};
+
+use crate::Error;
+
+pub use self::channelcarrier::ChannelCarrier;
+pub use self::imageheader::{ColorType, ImageHeader, Pass};
+pub use self::pngcarrier::{PNGCarrier, PNGCover};
+pub use self::pngchunk::PNGChunk;
+pub use self::pngparseerror::PNGParseError;
+
+mod channelcarrier;
+mod filter;
+mod imageheader;
+mod pngcarrier;
+mod pngchunk;
+mod pngparseerror;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// A parsed PNG image: its decoded scanlines and every other chunk, which is kept byte for byte.
+pub struct PNG {
+    pub header: ImageHeader,
+    /// Every chunk other than IDAT, in file order.
+    pub chunks: Vec<PNGChunk>,
+    /// Index in `chunks` of the chunk the image data is written before.
+    data_position: usize,
+    /// The IDAT chunks as read, written back unchanged as long as no sample has been changed.
+    data_chunks: Vec<PNGChunk>,
+    /// Unfiltered scanlines, without their filter type bytes.
+    pixels: Vec<u8>,
+    scanlines: Vec<Scanline>,
+    modified: bool,
+    /// Bytes after the IEND chunk, written back unchanged.
+    pub trailing: Vec<u8>,
+}
+
+/// Where a scanline is held in the unfiltered image data.
+struct Scanline {
+    offset: usize,
+    /// Index of the first pixel of the scanline, counting pixels in the order they are stored.
+    first_pixel: usize,
+    width: usize,
+    /// Filter type the scanline was stored with, reused when it is encoded again.
+    filter: u8,
+    /// Whether this is the first scanline of a pass, which is filtered against a row of zeros.
+    first_row: bool,
+}
+
+impl PNG {
+    /// Parses a PNG file and decodes its scanlines.
+    pub fn parse(data: &[u8]) -> Result<PNG, Error> {
+        let error = |error, offset| Error::PNG { error, offset };
+        if !data.starts_with(&SIGNATURE) {
+            return Err(error(PNGParseError::NotPNG, 0));
+        }
+
+        let mut chunks = Vec::new();
+        let mut data_chunks = Vec::new();
+        let mut data_position = None;
+        let mut offset = SIGNATURE.len();
+        while offset < data.len() {
+            let (chunk, length) = PNGChunk::parse(&data[offset..]).map_err(|e| error(e, offset))?;
+            offset += length;
+            if &chunk.kind == b"IDAT" {
+                data_position.get_or_insert(chunks.len());
+                data_chunks.push(chunk);
+                continue;
+            }
+            let end = &chunk.kind == b"IEND";
+            chunks.push(chunk);
+            if end {
+                break;
+            }
+        }
+
+        let missing = |kind| error(PNGParseError::MissingChunk { kind }, SIGNATURE.len());
+        let header = match chunks.first() {
+            Some(chunk) if &chunk.kind == b"IHDR" => {
+                ImageHeader::parse(&chunk.data).map_err(|e| error(e, SIGNATURE.len()))?
+            }
+            _ => return Err(missing("IHDR")),
+        };
+        let data_position = data_position.ok_or_else(|| missing("IDAT"))?;
+
+        let mut png = PNG {
+            header,
+            chunks,
+            data_position,
+            data_chunks,
+            pixels: Vec::new(),
+            scanlines: Vec::new(),
+            modified: false,
+            trailing: data[offset..].to_vec(),
+        };
+        if png.header.color_type == ColorType::Palette && png.palette().is_none() {
+            return Err(missing("PLTE"));
+        }
+        png.decode()
+            .map_err(|e| error(e, SIGNATURE.len() + png.data_offset()))?;
+        Ok(png)
+    }
+
+    /// Inflates and unfilters the image data.
+    fn decode(&mut self) -> Result<(), PNGParseError> {
+        let compressed: Vec<u8> = self
+            .data_chunks
+            .iter()
+            .flat_map(|c| c.data.iter().copied())
+            .collect();
+        let mut filtered = Vec::new();
+        flate2::read::ZlibDecoder::new(compressed.as_slice())
+            .read_to_end(&mut filtered)
+            .map_err(|_| PNGParseError::CorruptImageData)?;
+
+        let distance = self.pixel_distance();
+        let mut input = filtered.iter();
+        for pass in self.header.passes() {
+            let length = self.header.row_length(pass.width);
+            let mut previous = vec![0; length];
+            for row in 0..pass.height {
+                let filter = *input.next().ok_or(PNGParseError::CorruptImageData)?;
+                let mut scanline: Vec<u8> = input.by_ref().take(length).copied().collect();
+                if scanline.len() < length {
+                    return Err(PNGParseError::CorruptImageData);
+                }
+                filter::unfilter(filter, &mut scanline, &previous, distance)?;
+
+                self.scanlines.push(Scanline {
+                    offset: self.pixels.len(),
+                    first_pixel: self
+                        .scanlines
+                        .last()
+                        .map_or(0, |s: &Scanline| s.first_pixel + s.width),
+                    width: pass.width,
+                    filter,
+                    first_row: row == 0,
+                });
+                self.pixels.extend(&scanline);
+                previous = scanline;
+            }
+        }
+        Ok(())
+    }
+
+    /// Filters and deflates the image data into IDAT chunks no longer than the longest one read.
+    fn encode(&self) -> Vec<PNGChunk> {
+        let distance = self.pixel_distance();
+        let mut filtered = Vec::with_capacity(self.pixels.len() + self.scanlines.len());
+        for (index, scanline) in self.scanlines.iter().enumerate() {
+            let length = self.header.row_length(scanline.width);
+            let row = &self.pixels[scanline.offset..scanline.offset + length];
+            let previous = match scanline.first_row {
+                true => vec![0; length],
+                false => {
+                    let above = &self.scanlines[index - 1];
+                    self.pixels[above.offset..above.offset + length].to_vec()
+                }
+            };
+            filtered.push(scanline.filter);
+            filtered.extend(filter::filter(scanline.filter, row, &previous, distance));
+        }
+
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+        encoder
+            .write_all(&filtered)
+            .and_then(|_| encoder.finish())
+            .map(|compressed| {
+                let limit = self
+                    .data_chunks
+                    .iter()
+                    .map(|c| c.data.len())
+                    .max()
+                    .unwrap_or(0)
+                    .max(1);
+                compressed
+                    .chunks(limit)
+                    .map(|data| PNGChunk {
+                        kind: *b"IDAT",
+                        data: data.to_vec(),
+                    })
+                    .collect()
+            })
+            .expect("compressing into memory cannot fail")
+    }
+
+    /// Serializes the image. If no sample has been changed the original image data is kept, so the file is written back byte for byte.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let encoded;
+        let data_chunks = match self.modified {
+            true => {
+                encoded = self.encode();
+                &encoded
+            }
+            false => &self.data_chunks,
+        };
+
+        let mut bytes = SIGNATURE.to_vec();
+        for (index, chunk) in self.chunks.iter().enumerate() {
+            if index == self.data_position {
+                data_chunks
+                    .iter()
+                    .for_each(|chunk| bytes.extend(chunk.to_bytes()));
+            }
+            bytes.extend(chunk.to_bytes());
+        }
+        if self.data_position == self.chunks.len() {
+            data_chunks
+                .iter()
+                .for_each(|chunk| bytes.extend(chunk.to_bytes()));
+        }
+        bytes.extend(&self.trailing);
+        bytes
+    }
+
+    /// Byte offset of the first IDAT chunk after the signature, for locating image data errors.
+    fn data_offset(&self) -> usize {
+        self.chunks[..self.data_position]
+            .iter()
+            .map(|chunk| chunk.data.len() + 12)
+            .sum()
+    }
+
+    /// Number of bytes between corresponding bytes of adjacent pixels, as used by the scanline filters.
+    fn pixel_distance(&self) -> usize {
+        (self.header.bits_per_pixel() / 8).max(1)
+    }
+
+    /// Returns the number of pixels, which for an interlaced image are counted pass by pass.
+    pub fn pixel_count(&self) -> usize {
+        self.scanlines.last().map_or(0, |s| s.first_pixel + s.width)
+    }
+
+    /// Returns the byte offset and bit shift of a channel of a pixel within the unfiltered image data.
+    fn locate(&self, pixel: usize, channel: usize) -> (usize, u32) {
+        let row = self.scanlines.partition_point(|s| s.first_pixel <= pixel) - 1;
+        let scanline = &self.scanlines[row];
+        let index = (pixel - scanline.first_pixel) * self.header.color_type.channels() + channel;
+        let bit = index * self.header.bit_depth as usize;
+        let shift = match self.header.bit_depth {
+            depth @ 1..=4 => 8 - depth as u32 - (bit % 8) as u32,
+            _ => 0,
+        };
+        (scanline.offset + bit / 8, shift)
+    }
+
+    /// Returns a channel of a pixel, or the palette index of a palette image.
+    pub fn sample(&self, pixel: usize, channel: usize) -> u16 {
+        let (offset, shift) = self.locate(pixel, channel);
+        match self.header.bit_depth {
+            16 => u16::from_be_bytes([self.pixels[offset], self.pixels[offset + 1]]),
+            depth => ((self.pixels[offset] >> shift) & (0xFF >> (8 - depth))) as u16,
+        }
+    }
+
+    pub fn set_sample(&mut self, pixel: usize, channel: usize, value: u16) {
+        let (offset, shift) = self.locate(pixel, channel);
+        match self.header.bit_depth {
+            16 => self.pixels[offset..offset + 2].copy_from_slice(&value.to_be_bytes()),
+            depth => {
+                let mask = (0xFF >> (8 - depth)) << shift;
+                self.pixels[offset] =
+                    (self.pixels[offset] & !mask) | ((value as u8) << shift & mask);
+            }
+        }
+        self.modified = true;
+    }
+
+    /// Returns whether a pixel is fully transparent, through its alpha channel or the tRNS chunk.
+    pub fn is_transparent(&self, pixel: usize) -> bool {
+        let channels = self.header.color_type.channels();
+        if self.header.color_type.has_alpha() {
+            return self.sample(pixel, channels - 1) == 0;
+        }
+        let transparency = match self.chunk(b"tRNS") {
+            Some(chunk) => &chunk.data,
+            None => return false,
+        };
+        match self.header.color_type {
+            ColorType::Palette => transparency.get(self.sample(pixel, 0) as usize) == Some(&0),
+            _ => self.is_transparent_color(pixel, transparency),
+        }
+    }
+
+    /// Returns whether a grayscale or RGB pixel has the color the tRNS chunk marks as transparent.
+    fn is_transparent_color(&self, pixel: usize, transparency: &[u8]) -> bool {
+        let channels = self.header.color_type.channels();
+        transparency.len() == channels * 2
+            && (0..channels).all(|channel| {
+                let key =
+                    u16::from_be_bytes([transparency[channel * 2], transparency[channel * 2 + 1]]);
+                self.sample(pixel, channel) == key
+            })
+    }
+
+    /// The RGB entries of the PLTE chunk, if there is one.
+    pub fn palette(&self) -> Option<&[u8]> {
+        self.chunk(b"PLTE").map(|chunk| chunk.data.as_slice())
+    }
+
+    /// Returns the first chunk of a type.
+    pub fn chunk(&self, kind: &[u8; 4]) -> Option<&PNGChunk> {
+        self.chunks.iter().find(|chunk| &chunk.kind == kind)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// Builds a PNG of the given color type and bit depth from the samples of its (non-interlaced) scanlines, with a tEXt chunk before and a tIME chunk after the image data.
+    pub(crate) fn png_bytes(
+        color_type: u8,
+        bit_depth: u8,
+        width: u32,
+        rows: &[Vec<u8>],
+        extra: &[PNGChunk],
+    ) -> Vec<u8> {
+        let mut header = width.to_be_bytes().to_vec();
+        header.extend((rows.len() as u32).to_be_bytes());
+        header.extend([bit_depth, color_type, 0, 0, 0]);
+
+        let filtered: Vec<u8> = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(index, row)| {
+                let filter = (index % 5) as u8;
+                let previous = match index {
+                    0 => vec![0; row.len()],
+                    _ => rows[index - 1].clone(),
+                };
+                let distance = (color_type_channels(color_type) * bit_depth as usize / 8).max(1);
+                std::iter::once(filter).chain(filter::filter(filter, row, &previous, distance))
+            })
+            .collect();
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(&filtered).unwrap();
+
+        let mut chunks = vec![
+            PNGChunk {
+                kind: *b"IHDR",
+                data: header,
+            },
+            PNGChunk {
+                kind: *b"tEXt",
+                data: b"Software\0test".to_vec(),
+            },
+        ];
+        chunks.extend(extra.iter().cloned());
+        chunks.push(PNGChunk {
+            kind: *b"IDAT",
+            data: encoder.finish().unwrap(),
+        });
+        chunks.push(PNGChunk {
+            kind: *b"tIME",
+            data: vec![7, 234, 10, 18, 12, 0, 0],
+        });
+        chunks.push(PNGChunk {
+            kind: *b"IEND",
+            data: Vec::new(),
+        });
+
+        let mut bytes = SIGNATURE.to_vec();
+        chunks
+            .iter()
+            .for_each(|chunk| bytes.extend(chunk.to_bytes()));
+        bytes
+    }
+
+    fn color_type_channels(color_type: u8) -> usize {
+        [1, 0, 3, 1, 2, 0, 4][color_type as usize]
+    }
+
+    /// Builds an 8-bit RGBA image whose scanlines are noise, with every seventh pixel fully transparent.
+    pub(crate) fn rgba_png_bytes(width: u32, height: u32) -> Vec<u8> {
+        let rows: Vec<Vec<u8>> = (0..height as usize)
+            .map(|y| {
+                (0..width as usize * 4)
+                    .map(|i| match i % 4 {
+                        3 if (y * width as usize + i / 4).is_multiple_of(7) => 0,
+                        3 => 255,
+                        _ => ((y * 31 + i * 17) % 256) as u8,
+                    })
+                    .collect()
+            })
+            .collect();
+        png_bytes(6, 8, width, &rows, &[])
+    }
+
+    #[test]
+    fn round_trips_image_with_ancillary_chunks() {
+        let bytes = rgba_png_bytes(20, 10);
+        let mut png = PNG::parse(&bytes).unwrap();
+        assert_eq!(png.to_bytes(), bytes);
+        assert_eq!(png.pixel_count(), 200);
+        assert_eq!(png.sample(1, 0), 68);
+        assert!(png.is_transparent(0) && !png.is_transparent(1));
+
+        png.set_sample(1, 0, 99);
+        let changed = PNG::parse(&png.to_bytes()).unwrap();
+        assert_eq!(changed.sample(1, 0), 99);
+        assert_eq!(changed.chunks, png.chunks);
+        assert_eq!(changed.pixels.len(), png.pixels.len());
+    }
+
+    /// Returns the offset of the image data chunk in the file.
+    fn data_chunk_offset(bytes: &[u8]) -> usize {
+        bytes.windows(4).position(|kind| kind == b"IDAT").unwrap() - 4
+    }
+
+    #[test]
+    fn rejects_damaged_data_checksum() {
+        let mut bytes = rgba_png_bytes(20, 10);
+        let offset = data_chunk_offset(&bytes);
+        bytes[offset + 10] ^= 1;
+        assert!(matches!(
+            PNG::parse(&bytes),
+            Err(Error::PNG {
+                error: PNGParseError::BadChecksum { ref kind },
+                offset: at,
+            }) if kind == "IDAT" && at == offset
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_image_data() {
+        let bytes = rgba_png_bytes(20, 10);
+        let offset = data_chunk_offset(&bytes);
+        let (mut chunk, length) = PNGChunk::parse(&bytes[offset..]).unwrap();
+        chunk.data.truncate(chunk.data.len() / 2);
+
+        let mut truncated = bytes[..offset].to_vec();
+        truncated.extend(chunk.to_bytes());
+        truncated.extend(&bytes[offset + length..]);
+        assert!(matches!(
+            PNG::parse(&truncated),
+            Err(Error::PNG {
+                error: PNGParseError::CorruptImageData,
+                ..
+            })
+        ));
+        assert!(matches!(
+            PNG::parse(&bytes[..offset + 20]),
+            Err(Error::PNG {
+                error: PNGParseError::TruncatedChunk,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn reads_packed_and_wide_samples() {
+        let gray = png_bytes(0, 16, 2, &[vec![0x12, 0x34, 0xAB, 0xCD]], &[]);
+        let png = PNG::parse(&gray).unwrap();
+        assert_eq!((png.sample(0, 0), png.sample(1, 0)), (0x1234, 0xABCD));
+
+        let palette = PNGChunk {
+            kind: *b"PLTE",
+            data: vec![0; 12],
+        };
+        let mut png = PNG::parse(&png_bytes(3, 2, 4, &[vec![0b00_01_10_11]], &[palette])).unwrap();
+        assert_eq!(
+            (0..4).map(|p| png.sample(p, 0)).collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+        png.set_sample(2, 0, 1);
+        assert_eq!(png.pixels, [0b00_01_01_11]);
+    }
+}
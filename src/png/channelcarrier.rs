@@ -0,0 +1,220 @@
+use crate::png::{ColorType, PNG};
+use crate::stego::{lsb_match, BitCarrier};
+
+/// Carries one payload bit in the least significant bit of each color channel of every pixel that is not fully transparent.
+/// Bits are written by LSB matching, moving a channel up or down by one instead of overwriting its low bit. Alpha channels are left alone.
+/// Palette images carry a bit per pixel in the rank of its palette entry when the entries are ordered by luminance,
+/// so that a change swaps the entry for one of similar brightness.
+pub struct ChannelCarrier {
+    /// Indices of the pixels that are not fully transparent.
+    pixels: Vec<usize>,
+    /// Color channels per pixel, excluding alpha.
+    channels: usize,
+    /// Palette indices in luminance order, leaving out fully transparent entries.
+    order: Vec<u16>,
+    /// Position of each palette index in `order`.
+    ranks: Vec<Option<usize>>,
+}
+
+impl ChannelCarrier {
+    /// Finds the pixels of the image that can carry bits.
+    pub fn new(png: &PNG) -> ChannelCarrier {
+        let color_type = png.header.color_type;
+        let channels = color_type.channels() - color_type.has_alpha() as usize;
+        let (order, ranks) = match color_type {
+            ColorType::Palette => ChannelCarrier::palette_order(png),
+            _ => (Vec::new(), Vec::new()),
+        };
+        // In a one-bit grayscale image with a tRNS key, the visible pixels could only change to the key.
+        let keyed = color_type == ColorType::Grayscale
+            && png.header.bit_depth == 1
+            && png.chunk(b"tRNS").is_some();
+        let pixels = (0..png.pixel_count())
+            .filter(|&pixel| !keyed && !png.is_transparent(pixel))
+            // A palette pixel needs another visible entry to move to, or its bit could never change.
+            .filter(|&pixel| {
+                color_type != ColorType::Palette
+                    || order.len() >= 2
+                        && ranks
+                            .get(png.sample(pixel, 0) as usize)
+                            .copied()
+                            .flatten()
+                            .is_some()
+            })
+            .collect();
+
+        ChannelCarrier {
+            pixels,
+            channels,
+            order,
+            ranks,
+        }
+    }
+
+    /// Orders the visible palette entries by luminance.
+    fn palette_order(png: &PNG) -> (Vec<u16>, Vec<Option<usize>>) {
+        let palette = png.palette().unwrap_or_default();
+        let transparency = png.chunk(b"tRNS").map_or(&[][..], |chunk| &chunk.data);
+        let entries = palette.len() / 3;
+
+        let mut order: Vec<u16> = (0..entries as u16)
+            .filter(|&index| transparency.get(index as usize) != Some(&0))
+            .collect();
+        order.sort_by_key(|&index| {
+            let rgb = &palette[index as usize * 3..index as usize * 3 + 3];
+            (
+                299 * rgb[0] as u32 + 587 * rgb[1] as u32 + 114 * rgb[2] as u32,
+                index,
+            )
+        });
+
+        let mut ranks = vec![None; entries];
+        for (rank, &index) in order.iter().enumerate() {
+            ranks[index as usize] = Some(rank);
+        }
+        (order, ranks)
+    }
+
+    /// Returns the value that carries the bit: the channel itself, or the luminance rank of a palette index.
+    fn value(&self, png: &PNG, pixel: usize, channel: usize) -> i64 {
+        let sample = png.sample(pixel, channel);
+        match png.header.color_type {
+            ColorType::Palette => self.ranks[sample as usize]
+                .expect("pixels with unranked entries are skipped")
+                as i64,
+            _ => sample as i64,
+        }
+    }
+}
+
+impl BitCarrier<PNG> for ChannelCarrier {
+    fn positions(&self, _cover: &PNG) -> usize {
+        self.pixels.len() * self.channels
+    }
+
+    fn read_bit(&self, cover: &PNG, position: usize) -> bool {
+        let pixel = self.pixels[position / self.channels];
+        self.value(cover, pixel, position % self.channels) & 1 == 1
+    }
+
+    fn write_bit(&self, cover: &mut PNG, position: usize, bit: bool) {
+        let pixel = self.pixels[position / self.channels];
+        let channel = position % self.channels;
+        let value = self.value(cover, pixel, channel);
+
+        if cover.header.color_type == ColorType::Palette {
+            let rank = lsb_match(value, 1, 0, bit, 0, self.order.len() as i64 - 1, position);
+            cover.set_sample(pixel, channel, self.order[rank as usize]);
+            return;
+        }
+
+        let max = (1i64 << cover.header.bit_depth) - 1;
+        let changed = lsb_match(value, 1, 0, bit, 0, max, position);
+        cover.set_sample(pixel, channel, changed as u16);
+        // A pixel must not take on the color that tRNS marks as transparent, or it would drop out of the positions.
+        // It moves the other way instead, or past the key when that is out of range.
+        if changed != value && cover.is_transparent(pixel) {
+            let step = changed - value;
+            let changed = [value - step, value + 3 * step]
+                .into_iter()
+                .find(|candidate| (0..=max).contains(candidate))
+                .unwrap_or(value);
+            cover.set_sample(pixel, channel, changed as u16);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::png::test::{png_bytes, rgba_png_bytes};
+    use crate::png::PNGChunk;
+
+    #[test]
+    fn skips_alpha_and_transparent_pixels() {
+        let mut png = PNG::parse(&rgba_png_bytes(7, 3)).unwrap();
+        let carrier = ChannelCarrier::new(&png);
+        assert_eq!(carrier.positions(&png), 18 * 3);
+
+        for position in 0..carrier.positions(&png) {
+            carrier.write_bit(&mut png, position, position % 3 == 0);
+        }
+        let png = PNG::parse(&png.to_bytes()).unwrap();
+        let carrier = ChannelCarrier::new(&png);
+        assert!((0..carrier.positions(&png)).all(|p| carrier.read_bit(&png, p) == (p % 3 == 0)));
+        assert!((0..21).all(|pixel| png.sample(pixel, 3) == if pixel % 7 == 0 { 0 } else { 255 }));
+    }
+
+    #[test]
+    fn moves_palette_pixels_to_neighbouring_luminance() {
+        let palette = PNGChunk {
+            kind: *b"PLTE",
+            data: vec![255, 255, 255, 0, 0, 0, 128, 128, 128, 10, 10, 10],
+        };
+        let transparency = PNGChunk {
+            kind: *b"tRNS",
+            data: vec![255, 255, 255, 0],
+        };
+        let mut png = PNG::parse(&png_bytes(
+            3,
+            8,
+            4,
+            &[vec![0, 1, 2, 3]],
+            &[palette, transparency],
+        ))
+        .unwrap();
+        let carrier = ChannelCarrier::new(&png);
+        assert_eq!(carrier.positions(&png), 3);
+
+        // Luminance order is black, gray, white: black has rank 0 and can only move to gray.
+        carrier.write_bit(&mut png, 1, true);
+        assert_eq!(png.sample(1, 0), 2);
+        assert!(carrier.read_bit(&png, 1));
+    }
+
+    #[test]
+    fn skips_palette_with_one_visible_entry() {
+        let palette = PNGChunk {
+            kind: *b"PLTE",
+            data: vec![255, 255, 255, 0, 0, 0],
+        };
+        let transparency = PNGChunk {
+            kind: *b"tRNS",
+            data: vec![255, 0],
+        };
+        let png = PNG::parse(&png_bytes(3, 8, 2, &[vec![0, 0]], &[palette, transparency])).unwrap();
+        assert_eq!(ChannelCarrier::new(&png).positions(&png), 0);
+    }
+
+    #[test]
+    fn steps_past_transparent_key_at_the_edge_of_the_range() {
+        let transparency = PNGChunk {
+            kind: *b"tRNS",
+            data: vec![0, 1],
+        };
+        let mut png = PNG::parse(&png_bytes(
+            0,
+            8,
+            4,
+            &[vec![0, 1, 254, 255]],
+            &[transparency],
+        ))
+        .unwrap();
+        let carrier = ChannelCarrier::new(&png);
+        assert_eq!(carrier.positions(&png), 3);
+
+        carrier.write_bit(&mut png, 0, true);
+        assert_eq!(png.sample(0, 0), 3);
+        assert!(carrier.read_bit(&png, 0));
+    }
+
+    #[test]
+    fn skips_one_bit_grayscale_with_transparent_key() {
+        let transparency = PNGChunk {
+            kind: *b"tRNS",
+            data: vec![0, 1],
+        };
+        let png = PNG::parse(&png_bytes(0, 1, 8, &[vec![0b0110_0000]], &[transparency])).unwrap();
+        assert_eq!(ChannelCarrier::new(&png).positions(&png), 0);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::png::PNGParseError;
+
+/// Reverses the filter of a scanline in place, given the unfiltered scanline above it (all zeros for the first row of a pass).
+/// `distance` is the number of bytes between corresponding bytes of adjacent pixels, at least one.
+pub fn unfilter(
+    filter: u8,
+    row: &mut [u8],
+    previous: &[u8],
+    distance: usize,
+) -> Result<(), PNGParseError> {
+    for i in 0..row.len() {
+        let left = if i >= distance { row[i - distance] } else { 0 };
+        let upper_left = if i >= distance {
+            previous[i - distance]
+        } else {
+            0
+        };
+        row[i] = row[i].wrapping_add(predict(filter, left, previous[i], upper_left)?);
+    }
+    Ok(())
+}
+
+/// Applies a filter to an unfiltered scanline, the inverse of [`unfilter`].
+pub fn filter(filter: u8, row: &[u8], previous: &[u8], distance: usize) -> Vec<u8> {
+    (0..row.len())
+        .map(|i| {
+            let left = if i >= distance { row[i - distance] } else { 0 };
+            let upper_left = if i >= distance {
+                previous[i - distance]
+            } else {
+                0
+            };
+            let prediction = predict(filter, left, previous[i], upper_left)
+                .expect("filter types are checked when the image is decoded");
+            row[i].wrapping_sub(prediction)
+        })
+        .collect()
+}
+
+/// Returns the value each filter type predicts for a byte from its neighbours.
+fn predict(filter: u8, left: u8, up: u8, upper_left: u8) -> Result<u8, PNGParseError> {
+    match filter {
+        0 => Ok(0),
+        1 => Ok(left),
+        2 => Ok(up),
+        3 => Ok(((left as u16 + up as u16) / 2) as u8),
+        4 => Ok(paeth(left, up, upper_left)),
+        filter => Err(PNGParseError::InvalidFilter { filter }),
+    }
+}
+
+fn paeth(left: u8, up: u8, upper_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - upper_left as i16;
+    let distance = |value: u8| (estimate - value as i16).abs();
+    if distance(left) <= distance(up) && distance(left) <= distance(upper_left) {
+        left
+    } else if distance(up) <= distance(upper_left) {
+        up
+    } else {
+        upper_left
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unfilters_every_filter_type() {
+        let previous = [10, 200, 30, 40, 250, 60];
+        let row = [1, 2, 3, 255, 128, 7];
+        for kind in 0..5 {
+            let mut filtered = filter(kind, &row, &previous, 3);
+            unfilter(kind, &mut filtered, &previous, 3).unwrap();
+            assert_eq!(filtered, row);
+        }
+        assert_eq!(
+            unfilter(5, &mut [0], &[0], 1),
+            Err(PNGParseError::InvalidFilter { filter: 5 })
+        );
+    }
+}
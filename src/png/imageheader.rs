@@ -0,0 +1,151 @@
+#[cfg(feature = "report")]
+use serde::Serialize;
+
+use crate::png::PNGParseError;
+
+/// How the channels of a pixel are laid out.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "report", derive(Serialize))]
+pub enum ColorType {
+    Grayscale,
+    RGB,
+    /// Each pixel is an index into the PLTE chunk.
+    Palette,
+    GrayscaleAlpha,
+    RGBA,
+}
+
+impl ColorType {
+    /// Number of channels stored for each pixel.
+    pub fn channels(&self) -> usize {
+        match self {
+            ColorType::Grayscale | ColorType::Palette => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::RGB => 3,
+            ColorType::RGBA => 4,
+        }
+    }
+
+    /// Returns whether the last channel of each pixel is an alpha channel.
+    pub fn has_alpha(&self) -> bool {
+        matches!(self, ColorType::GrayscaleAlpha | ColorType::RGBA)
+    }
+}
+
+/// The contents of the IHDR chunk.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "report", derive(Serialize))]
+pub struct ImageHeader {
+    pub width: u32,
+    pub height: u32,
+    /// Bits per channel, or per palette index.
+    pub bit_depth: u8,
+    pub color_type: ColorType,
+    /// Whether the scanlines are stored in the seven passes of Adam7 interlacing.
+    pub interlaced: bool,
+}
+
+/// A rectangle of the image whose scanlines are stored one after the other: the whole image, or one Adam7 pass.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Pass {
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Column and row of the first pixel of each Adam7 pass, and the spacing between its pixels.
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+impl ImageHeader {
+    /// Parses the body of an IHDR chunk.
+    pub fn parse(data: &[u8]) -> Result<ImageHeader, PNGParseError> {
+        if data.len() != 13 {
+            return Err(PNGParseError::InvalidHeader);
+        }
+        let width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        let height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+        let bit_depth = data[8];
+        let color_type = match (data[9], bit_depth) {
+            (0, 1 | 2 | 4 | 8 | 16) => ColorType::Grayscale,
+            (2, 8 | 16) => ColorType::RGB,
+            (3, 1 | 2 | 4 | 8) => ColorType::Palette,
+            (4, 8 | 16) => ColorType::GrayscaleAlpha,
+            (6, 8 | 16) => ColorType::RGBA,
+            (color_type, bit_depth) => {
+                return Err(PNGParseError::UnsupportedFormat {
+                    color_type,
+                    bit_depth,
+                })
+            }
+        };
+        // Compression and filter methods have a single defined value; interlacing is off or Adam7.
+        if width == 0 || height == 0 || data[10] != 0 || data[11] != 0 || data[12] > 1 {
+            return Err(PNGParseError::InvalidHeader);
+        }
+
+        Ok(ImageHeader {
+            width,
+            height,
+            bit_depth,
+            color_type,
+            interlaced: data[12] == 1,
+        })
+    }
+
+    pub fn bits_per_pixel(&self) -> usize {
+        self.color_type.channels() * self.bit_depth as usize
+    }
+
+    /// Bytes in one scanline of the given width, excluding its filter type byte.
+    pub fn row_length(&self, width: usize) -> usize {
+        (width * self.bits_per_pixel()).div_ceil(8)
+    }
+
+    /// Returns the passes the scanlines are stored in, leaving out empty Adam7 passes.
+    pub fn passes(&self) -> Vec<Pass> {
+        let (width, height) = (self.width as usize, self.height as usize);
+        if !self.interlaced {
+            return vec![Pass { width, height }];
+        }
+        ADAM7
+            .iter()
+            .map(|&(x, y, dx, dy)| Pass {
+                width: width.saturating_sub(x).div_ceil(dx),
+                height: height.saturating_sub(y).div_ceil(dy),
+            })
+            .filter(|pass| pass.width > 0 && pass.height > 0)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn splits_interlaced_image_into_passes() {
+        let mut data = [0, 0, 0, 5, 0, 0, 0, 3, 8, 2, 0, 0, 1];
+        let header = ImageHeader::parse(&data).unwrap();
+        let pixels: usize = header.passes().iter().map(|p| p.width * p.height).sum();
+        assert_eq!(header.passes().len(), 6);
+        assert_eq!(pixels, 15);
+        assert_eq!(header.row_length(5), 15);
+
+        data[9] = 3;
+        data[8] = 16;
+        assert_eq!(
+            ImageHeader::parse(&data),
+            Err(PNGParseError::UnsupportedFormat {
+                color_type: 3,
+                bit_depth: 16
+            })
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use crate::carrier::{Carrier, Cover};
+use crate::png::{ChannelCarrier, PNG};
+use crate::stego::BitCarrier;
+use crate::Error;
+
+/// The PNG format, embedding in the low bits of the color channels.
+pub struct PNGCarrier;
+
+/// A parsed PNG image with its carrier positions located.
+pub struct PNGCover {
+    pub png: PNG,
+    carrier: ChannelCarrier,
+}
+
+impl Carrier for PNGCarrier {
+    fn name(&self) -> &'static str {
+        "png"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &["lsb"]
+    }
+
+    fn parse(&self, data: &[u8], method: &str) -> Result<Box<dyn Cover>, Error> {
+        if method != "lsb" {
+            return Err(Error::UnsupportedMethod {
+                format: self.name(),
+                method: method.to_string(),
+            });
+        }
+        let png = PNG::parse(data)?;
+        let carrier = ChannelCarrier::new(&png);
+        Ok(Box::new(PNGCover { png, carrier }))
+    }
+}
+
+impl Cover for PNGCover {
+    fn positions(&self) -> usize {
+        self.carrier.positions(&self.png)
+    }
+
+    fn read_bit(&self, position: usize) -> bool {
+        self.carrier.read_bit(&self.png, position)
+    }
+
+    fn write_bit(&mut self, position: usize, bit: bool) {
+        self.carrier.write_bit(&mut self.png, position, bit)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.png.to_bytes()
+    }
+}
@@ -0,0 +1,74 @@
+use crc::{Crc, CRC_32_ISO_HDLC};
+
+use crate::png::PNGParseError;
+
+/// CRC-32 stored after every chunk, covering its type and data.
+const CHUNK_CRC: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+/// A chunk of a PNG file, kept as raw bytes so that ancillary chunks are written back unchanged.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PNGChunk {
+    pub kind: [u8; 4],
+    pub data: Vec<u8>,
+}
+
+impl PNGChunk {
+    /// Parses the chunk at the start of the data, returning it with the number of bytes it occupies.
+    pub fn parse(data: &[u8]) -> Result<(PNGChunk, usize), PNGParseError> {
+        let length = data
+            .get(0..4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or(PNGParseError::TruncatedChunk)?;
+        let end = 8 + length;
+        let crc = data
+            .get(end..end + 4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or(PNGParseError::TruncatedChunk)?;
+
+        let chunk = PNGChunk {
+            kind: [data[4], data[5], data[6], data[7]],
+            data: data[8..end].to_vec(),
+        };
+        if CHUNK_CRC.checksum(&data[4..end]) != crc {
+            return Err(PNGParseError::BadChecksum { kind: chunk.name() });
+        }
+        Ok((chunk, end + 4))
+    }
+
+    /// Returns the chunk type as text, such as "IHDR" or "tEXt".
+    pub fn name(&self) -> String {
+        String::from_utf8_lossy(&self.kind).to_string()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.data.len() + 12);
+        bytes.extend((self.data.len() as u32).to_be_bytes());
+        bytes.extend(self.kind);
+        bytes.extend(&self.data);
+        bytes.extend(CHUNK_CRC.checksum(&bytes[4..]).to_be_bytes());
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trips_chunk_and_checks_crc() {
+        let chunk = PNGChunk {
+            kind: *b"tEXt",
+            data: b"Comment\0hello".to_vec(),
+        };
+        let mut bytes = chunk.to_bytes();
+        assert_eq!(PNGChunk::parse(&bytes), Ok((chunk, bytes.len())));
+
+        bytes[10] ^= 1;
+        assert_eq!(
+            PNGChunk::parse(&bytes),
+            Err(PNGParseError::BadChecksum {
+                kind: String::from("tEXt")
+            })
+        );
+    }
+}
@@ -0,0 +1,52 @@
+/// Error type for PNG parsing.
+#[derive(Debug, PartialEq)]
+pub enum PNGParseError {
+    NotPNG,
+    TruncatedChunk,
+    BadChecksum { kind: String },
+    MissingChunk { kind: &'static str },
+    InvalidHeader,
+    UnsupportedFormat { color_type: u8, bit_depth: u8 },
+    InvalidFilter { filter: u8 },
+    CorruptImageData,
+}
+
+impl PNGParseError {
+    pub fn description(&self) -> String {
+        match self {
+            PNGParseError::NotPNG => String::from("Input does not start with the PNG signature."),
+            PNGParseError::TruncatedChunk => {
+                String::from("Chunk extends past the end of the data.")
+            }
+            PNGParseError::BadChecksum { kind } => {
+                format!(
+                    "Checksum of the \"{}\" chunk does not match its contents.",
+                    kind
+                )
+            }
+            PNGParseError::MissingChunk { kind } => format!("No \"{}\" chunk was found.", kind),
+            PNGParseError::InvalidHeader => String::from("The IHDR chunk is malformed."),
+            PNGParseError::UnsupportedFormat {
+                color_type,
+                bit_depth,
+            } => format!(
+                "Unsupported combination of color type {} and bit depth {}.",
+                color_type, bit_depth
+            ),
+            PNGParseError::InvalidFilter { filter } => {
+                format!("Invalid scanline filter type: {}.", filter)
+            }
+            PNGParseError::CorruptImageData => String::from(
+                "The image data could not be decompressed into the expected scanlines.",
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for PNGParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(&self.description())
+    }
+}
+
+impl std::error::Error for PNGParseError {}
@@ -0,0 +1,594 @@
+pub(crate) use self::bits::{bits_to_bytes, bytes_to_bits};
+
+pub use self::bitcarrier::BitCarrier;
+pub use self::capacity::Capacity;
+pub use self::compression::Compression;
+#[cfg(feature = "crypto")]
+pub use self::container::{Container, CONTAINER_OVERHEAD};
+pub use self::fec::Fec;
+pub use self::hammingcode::{HammingCode, MAX_K};
+pub use self::lsbmatching::lsb_match;
+pub use self::packet::{Packet, PacketScanner};
+pub use self::permutation::Permutation;
+pub use self::samplecost::{sample_cost, SILENT_SAMPLE_COST};
+pub use self::stegoerror::StegoError;
+pub use self::stegokey::StegoKey;
+pub use self::trelliscode::TrellisCode;
+
+mod bitcarrier;
+mod bits;
+mod capacity;
+mod compression;
+#[cfg(feature = "crypto")]
+mod container;
+mod fec;
+mod hammingcode;
+mod lsbmatching;
+mod packet;
+mod permutation;
+mod reedsolomon;
+mod samplecost;
+mod stegoerror;
+mod stegokey;
+mod trelliscode;
+
+/// Number of bits in the header stored ahead of the payload: a 32-bit payload length, the 8-bit coding identifier and the 8-bit FEC overhead.
+const HEADER_FIELD_BITS: usize = 48;
+
+/// The header is stored three times and read back by majority vote, since the payload cannot be located without it.
+const HEADER_COPIES: usize = 3;
+/// Carrier bits taken by the header of a scattered payload.
+pub const HEADER_BITS: usize = HEADER_FIELD_BITS * HEADER_COPIES;
+
+/// Header value identifying syndrome-trellis coding. Values from 1 to `MAX_K` identify a Hamming code.
+const TRELLIS_CODING: u8 = 0x80;
+
+/// How payload bits are mapped onto carrier bits.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Coding {
+    /// Each payload bit is written directly to one carrier bit.
+    #[default]
+    Plain,
+    /// Matrix embedding with a Hamming code sized to the available capacity, so that fewer carrier bits change.
+    Hamming,
+    /// Syndrome-trellis coding, which picks the changes with the lowest total cost according to the carrier's cost model.
+    Trellis,
+}
+
+/// How the embedded bitstream is arranged over the carrier positions.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Layout {
+    /// A single header and payload scattered over all positions by the keyed permutation.
+    #[default]
+    Scattered,
+    /// Self-synchronizing packets written in carrier order and repeated until the carrier is full.
+    /// Each packet can be found on its own, so the payload survives frames being cut from either end or the file being spliced with others.
+    /// Packets are written without coding or error correction; the repetition provides the redundancy.
+    Packets,
+}
+
+/// Options controlling how a payload is written into the carrier.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EmbedOptions {
+    pub layout: Layout,
+    pub coding: Coding,
+    /// Compression tried on the payload before it is encrypted. The payload is stored raw if this does not make it smaller.
+    pub compression: Compression,
+    /// Reed–Solomon parity added to the payload, as a percentage of its length. Zero disables error correction.
+    pub fec_overhead: u8,
+}
+
+/// A payload recovered from a carrier.
+#[derive(Debug, PartialEq)]
+pub struct Extraction {
+    pub payload: Vec<u8>,
+    /// Number of corrupted bytes repaired by forward error correction.
+    pub corrected_errors: usize,
+}
+
+/// Embeds the payload into the cover using the given carrier.
+/// The payload is compressed and encrypted into a container, which is then arranged over the carrier according to the layout option.
+#[cfg(feature = "crypto")]
+pub fn embed<T: ?Sized>(
+    cover: &mut T,
+    carrier: &impl BitCarrier<T>,
+    key: &StegoKey,
+    payload: &[u8],
+    options: EmbedOptions,
+) -> Result<(), StegoError> {
+    let sealed = Container::new(payload, options.compression).seal(key);
+    embed_raw(cover, carrier, key, &sealed, options)
+}
+
+/// Extracts and decrypts a payload previously embedded with [`embed`] using the same carrier and key.
+#[cfg(feature = "crypto")]
+pub fn extract<T: ?Sized>(
+    cover: &T,
+    carrier: &impl BitCarrier<T>,
+    key: &StegoKey,
+) -> Result<Extraction, StegoError> {
+    let mut extraction = extract_raw(cover, carrier, key)?;
+    extraction.payload = Container::open(key, &extraction.payload)?;
+    Ok(extraction)
+}
+
+/// Embeds the data as it is, without compressing or encrypting it, for callers that seal their payloads themselves.
+/// The key still chooses the carrier positions. The compression option is ignored.
+pub fn embed_raw<T: ?Sized>(
+    cover: &mut T,
+    carrier: &impl BitCarrier<T>,
+    key: &StegoKey,
+    data: &[u8],
+    options: EmbedOptions,
+) -> Result<(), StegoError> {
+    match options.layout {
+        Layout::Scattered => embed_scattered(cover, carrier, key, data, options),
+        Layout::Packets => embed_packets(cover, carrier, key, data),
+    }
+}
+
+/// Reads back data written with [`embed_raw`].
+/// The scattered layout is tried first; if it yields nothing, the carrier is scanned for packets.
+pub fn extract_raw<T: ?Sized>(
+    cover: &T,
+    carrier: &impl BitCarrier<T>,
+    key: &StegoKey,
+) -> Result<Extraction, StegoError> {
+    let scattered = extract_scattered(cover, carrier, key);
+    if scattered.is_ok() {
+        return scattered;
+    }
+
+    let bits: Vec<bool> = (0..carrier.positions(cover))
+        .map(|position| carrier.read_bit(cover, position))
+        .collect();
+    let packets = Packet::find(key, &bits);
+    if packets.is_empty() {
+        return scattered;
+    }
+
+    let payload = Packet::join(&packets)
+        .map_err(|(found, total)| StegoError::MissingPackets { found, total })?;
+    Ok(Extraction {
+        payload,
+        corrected_errors: 0,
+    })
+}
+
+/// Writes the payload as a sequence of packets in carrier order, repeating the sequence for as long as it fits.
+fn embed_packets<T: ?Sized>(
+    cover: &mut T,
+    carrier: &impl BitCarrier<T>,
+    key: &StegoKey,
+    payload: &[u8],
+) -> Result<(), StegoError> {
+    let round = Packet::bitstream(key, payload)?;
+    let available = carrier.positions(cover);
+    if round.len() > available {
+        return Err(StegoError::PayloadTooLarge {
+            required: round.len(),
+            available,
+        });
+    }
+
+    let rounds = available / round.len();
+    for (position, bit) in round.iter().cycle().take(rounds * round.len()).enumerate() {
+        if carrier.read_bit(cover, position) != *bit {
+            carrier.write_bit(cover, position, *bit);
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits the carrier positions, in permutation order, into those of the header and those of the payload.
+/// The header is written whatever the coding, so it takes the first positions whose change has a finite cost.
+/// Positions it skips are spread evenly over the payload, since a run of them at the start would leave the coding no bits to change.
+/// Returns `None` if there are too few positions with a finite cost.
+fn split_positions<T: ?Sized>(
+    cover: &T,
+    carrier: &impl BitCarrier<T>,
+    permutation: &Permutation,
+) -> Option<(Vec<usize>, Vec<usize>)> {
+    let available = carrier.positions(cover);
+    let mut header = Vec::with_capacity(HEADER_BITS);
+    let mut skipped = Vec::new();
+    let mut rest = Vec::with_capacity(available.saturating_sub(HEADER_BITS));
+    for i in 0..available {
+        let position = permutation.position(i);
+        if header.len() < HEADER_BITS && carrier.cost(cover, position).is_finite() {
+            header.push(position);
+        } else if header.len() < HEADER_BITS {
+            skipped.push(position);
+        } else {
+            rest.push(position);
+        }
+    }
+    if header.len() < HEADER_BITS {
+        return None;
+    }
+
+    let step = (rest.len() / skipped.len().max(1)).max(1);
+    let mut skipped = skipped.into_iter();
+    let mut payload = Vec::with_capacity(available - HEADER_BITS);
+    for (i, position) in rest.into_iter().enumerate() {
+        if i % step == step - 1 {
+            payload.extend(skipped.next());
+        }
+        payload.push(position);
+    }
+    payload.extend(skipped);
+    Some((header, payload))
+}
+
+/// Writes a header and payload, protected by forward error correction if requested, with every bit at a carrier position chosen by a permutation seeded from the key.
+fn embed_scattered<T: ?Sized>(
+    cover: &mut T,
+    carrier: &impl BitCarrier<T>,
+    key: &StegoKey,
+    payload: &[u8],
+    options: EmbedOptions,
+) -> Result<(), StegoError> {
+    let fec = Fec::new(options.fec_overhead);
+    let message = bytes_to_bits(&fec.encode(payload));
+    let available = carrier.positions(cover);
+    let too_large = StegoError::PayloadTooLarge {
+        required: HEADER_BITS + message.len(),
+        available,
+    };
+
+    let payload_bits = available
+        .checked_sub(HEADER_BITS)
+        .filter(|payload_bits| *payload_bits >= message.len())
+        .ok_or(too_large.clone())?;
+    let permutation = Permutation::new(key, available);
+    let (header_positions, positions) =
+        split_positions(cover, carrier, &permutation).ok_or(too_large.clone())?;
+
+    let original: Vec<bool> = positions
+        .iter()
+        .map(|position| carrier.read_bit(cover, *position))
+        .collect();
+    let (coding, stego) = match options.coding {
+        Coding::Plain => {
+            let mut stego = original.clone();
+            stego[..message.len()].copy_from_slice(&message);
+            (1, stego)
+        }
+        Coding::Hamming => {
+            let code = HammingCode::for_capacity(message.len(), payload_bits).ok_or(too_large)?;
+            let mut stego = original.clone();
+            let block_length = code.block_length();
+            for (i, chunk) in message.chunks(code.k).enumerate() {
+                code.embed(&mut stego[i * block_length..(i + 1) * block_length], chunk);
+            }
+            (code.k as u8, stego)
+        }
+        Coding::Trellis => {
+            let costs: Vec<f64> = positions
+                .iter()
+                .map(|position| carrier.cost(cover, *position))
+                .collect();
+            let stego = TrellisCode::new(key, message.len(), payload_bits)
+                .and_then(|code| code.embed(&original, &costs, &message))
+                .ok_or(StegoError::NoEmbeddingPath)?;
+            (TRELLIS_CODING, stego)
+        }
+    };
+
+    let header = encode_header(payload.len(), coding, fec.overhead);
+    for (position, bit) in header_positions.into_iter().zip(header) {
+        carrier.write_bit(cover, position, bit);
+    }
+
+    for (i, position) in positions.into_iter().enumerate() {
+        if stego[i] != original[i] {
+            carrier.write_bit(cover, position, stego[i]);
+        }
+    }
+
+    Ok(())
+}
+
+/// Encodes the header fields ahead of a payload of the given length, repeated `HEADER_COPIES` times into `HEADER_BITS` bits.
+pub(crate) fn encode_header(length: usize, coding: u8, fec_overhead: u8) -> Vec<bool> {
+    let mut header = (length as u32).to_be_bytes().to_vec();
+    header.extend([coding, fec_overhead]);
+    bytes_to_bits(&header).repeat(HEADER_COPIES)
+}
+
+/// Reads the payload length, coding identifier and FEC overhead back from the copies of the header, taking a majority vote on every bit.
+pub(crate) fn decode_header(copies: &[bool]) -> (usize, u8, u8) {
+    let header: Vec<bool> = (0..HEADER_FIELD_BITS)
+        .map(|i| {
+            let votes = (0..HEADER_COPIES)
+                .filter(|copy| copies[copy * HEADER_FIELD_BITS + i])
+                .count();
+            votes * 2 > HEADER_COPIES
+        })
+        .collect();
+    let header = bits_to_bytes(&header);
+    let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    (length, header[4], header[5])
+}
+
+/// Reads back a scattered payload, repairing damage with the payload's error correction.
+fn extract_scattered<T: ?Sized>(
+    cover: &T,
+    carrier: &impl BitCarrier<T>,
+    key: &StegoKey,
+) -> Result<Extraction, StegoError> {
+    let available = carrier.positions(cover);
+    if available < HEADER_BITS {
+        return Err(StegoError::NoPayload);
+    }
+
+    let permutation = Permutation::new(key, available);
+    let (header_positions, positions) =
+        split_positions(cover, carrier, &permutation).ok_or(StegoError::NoPayload)?;
+    let read = |positions: Vec<usize>| -> Vec<bool> {
+        positions
+            .into_iter()
+            .map(|position| carrier.read_bit(cover, position))
+            .collect()
+    };
+
+    let (length, coding, fec_overhead) = decode_header(&read(header_positions));
+    let fec = Fec::new(fec_overhead);
+    let message_bits = fec
+        .encoded_length(length)
+        .checked_mul(8)
+        .ok_or(StegoError::NoPayload)?;
+    let payload_bits = available - HEADER_BITS;
+    if message_bits > payload_bits {
+        return Err(StegoError::NoPayload);
+    }
+
+    let stego = read(positions);
+    let mut message = match coding {
+        TRELLIS_CODING => TrellisCode::new(key, message_bits, payload_bits)
+            .map(|code| code.extract(&stego, message_bits))
+            .unwrap_or_default(),
+        k if (1..=MAX_K).contains(&(k as usize)) => {
+            let code = HammingCode::new(k as usize);
+            if code.cover_bits(message_bits) > payload_bits {
+                return Err(StegoError::NoPayload);
+            }
+            stego
+                .chunks(code.block_length())
+                .take(message_bits.div_ceil(code.k))
+                .flat_map(|block| code.extract(block))
+                .collect()
+        }
+        _ => return Err(StegoError::NoPayload),
+    };
+    message.truncate(message_bits);
+
+    let (payload, corrected_errors) = fec
+        .decode(&bits_to_bytes(&message), length)
+        .ok_or(StegoError::Uncorrectable)?;
+    Ok(Extraction {
+        payload,
+        corrected_errors,
+    })
+}
+
+#[cfg(all(test, feature = "crypto"))]
+mod test {
+    use super::*;
+
+    /// A bare bit vector standing in for a cover file.
+    struct VecCarrier;
+
+    impl BitCarrier<Vec<bool>> for VecCarrier {
+        fn positions(&self, cover: &Vec<bool>) -> usize {
+            cover.len()
+        }
+
+        fn read_bit(&self, cover: &Vec<bool>, position: usize) -> bool {
+            cover[position]
+        }
+
+        fn write_bit(&self, cover: &mut Vec<bool>, position: usize, bit: bool) {
+            cover[position] = bit;
+        }
+
+        fn cost(&self, _cover: &Vec<bool>, position: usize) -> f64 {
+            if position < 4000 {
+                100.0
+            } else {
+                1.0
+            }
+        }
+    }
+
+    /// A bit vector whose first quarter must never change.
+    struct FrozenCarrier;
+
+    impl BitCarrier<Vec<bool>> for FrozenCarrier {
+        fn positions(&self, cover: &Vec<bool>) -> usize {
+            cover.len()
+        }
+
+        fn read_bit(&self, cover: &Vec<bool>, position: usize) -> bool {
+            cover[position]
+        }
+
+        fn write_bit(&self, cover: &mut Vec<bool>, position: usize, bit: bool) {
+            cover[position] = bit;
+        }
+
+        fn cost(&self, cover: &Vec<bool>, position: usize) -> f64 {
+            match position < cover.len() / 4 {
+                true => f64::INFINITY,
+                false => 1.0,
+            }
+        }
+    }
+
+    /// A cover of pseudorandom bits, so that roughly half of them already match any payload.
+    fn noisy_cover(length: usize) -> Vec<bool> {
+        (0..length).map(|i| (i * 7919) % 13 < 6).collect()
+    }
+
+    fn changes(original: &[bool], cover: &[bool]) -> usize {
+        cover.iter().zip(original).filter(|(a, b)| a != b).count()
+    }
+
+    #[test]
+    fn round_trips_payload() {
+        let key = StegoKey::derive("hunter2");
+        let mut cover = vec![false; 1000];
+        embed(
+            &mut cover,
+            &VecCarrier,
+            &key,
+            b"hello",
+            EmbedOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            extract(&cover, &VecCarrier, &key).unwrap().payload,
+            b"hello"
+        );
+    }
+
+    #[test]
+    fn spreads_changes_across_cover() {
+        let key = StegoKey::derive("hunter2");
+        let mut cover = vec![false; 10000];
+        embed(
+            &mut cover,
+            &VecCarrier,
+            &key,
+            &[0xFF; 64],
+            EmbedOptions::default(),
+        )
+        .unwrap();
+        let changed_in_second_half = cover[5000..].iter().filter(|bit| **bit).count();
+        assert!(changed_in_second_half > 100);
+    }
+
+    #[test]
+    fn errors_when_payload_does_not_fit() {
+        let key = StegoKey::derive("hunter2");
+        let mut cover = vec![false; 60];
+        let result = embed(
+            &mut cover,
+            &VecCarrier,
+            &key,
+            b"hello",
+            EmbedOptions::default(),
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            StegoError::PayloadTooLarge {
+                required: 416,
+                available: 60
+            }
+        );
+    }
+
+    #[test]
+    fn matrix_embedding_changes_fewer_bits() {
+        let key = StegoKey::derive("hunter2");
+        let payload = [0xA5; 128];
+        let original = noisy_cover(8000);
+        let hamming = EmbedOptions {
+            coding: Coding::Hamming,
+            ..EmbedOptions::default()
+        };
+
+        let mut plain_cover = original.clone();
+        embed(
+            &mut plain_cover,
+            &VecCarrier,
+            &key,
+            &payload,
+            EmbedOptions::default(),
+        )
+        .unwrap();
+        let mut hamming_cover = original.clone();
+        embed(&mut hamming_cover, &VecCarrier, &key, &payload, hamming).unwrap();
+
+        assert!(changes(&original, &hamming_cover) < changes(&original, &plain_cover) * 2 / 3);
+        assert_eq!(
+            extract(&hamming_cover, &VecCarrier, &key).unwrap().payload,
+            payload
+        );
+    }
+
+    #[test]
+    fn trellis_coding_prefers_cheap_positions() {
+        let key = StegoKey::derive("hunter2");
+        let payload = [0xA5; 128];
+        let original = noisy_cover(8000);
+        let trellis = EmbedOptions {
+            coding: Coding::Trellis,
+            ..EmbedOptions::default()
+        };
+
+        let mut cover = original.clone();
+        embed(&mut cover, &VecCarrier, &key, &payload, trellis).unwrap();
+
+        assert_eq!(extract(&cover, &VecCarrier, &key).unwrap().payload, payload);
+        let expensive = changes(&original[..4000], &cover[..4000]);
+        let cheap = changes(&original[4000..], &cover[4000..]);
+        assert!(expensive * 2 < cheap);
+    }
+
+    #[test]
+    fn keeps_header_off_positions_that_must_not_change() {
+        let key = StegoKey::derive("hunter2");
+        let payload = [0xA5; 64];
+        let trellis = EmbedOptions {
+            coding: Coding::Trellis,
+            ..EmbedOptions::default()
+        };
+
+        let original = noisy_cover(8000);
+        let mut cover = original.clone();
+        embed(&mut cover, &FrozenCarrier, &key, &payload, trellis).unwrap();
+        assert_eq!(cover[..2000], original[..2000]);
+        assert_eq!(
+            extract(&cover, &FrozenCarrier, &key).unwrap().payload,
+            payload
+        );
+    }
+
+    #[test]
+    fn corrects_damaged_region_with_fec() {
+        let key = StegoKey::derive("hunter2");
+        let payload = [0xA5; 64];
+        let options = EmbedOptions {
+            fec_overhead: 50,
+            ..EmbedOptions::default()
+        };
+
+        let mut cover = noisy_cover(4000);
+        embed(&mut cover, &VecCarrier, &key, &payload, options).unwrap();
+        for bit in &mut cover[2000..2040] {
+            *bit = !*bit;
+        }
+
+        let extraction = extract(&cover, &VecCarrier, &key).unwrap();
+        assert_eq!(extraction.payload, payload);
+        assert!(extraction.corrected_errors > 0);
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn round_trips_compressed_payload() {
+        let key = StegoKey::derive("hunter2");
+        let payload = "meet at noon. ".repeat(20);
+        let options = EmbedOptions {
+            compression: Compression::Deflate,
+            ..EmbedOptions::default()
+        };
+
+        let mut cover = noisy_cover(1000);
+        embed(&mut cover, &VecCarrier, &key, payload.as_bytes(), options).unwrap();
+        let extraction = extract(&cover, &VecCarrier, &key).unwrap();
+        assert_eq!(extraction.payload, payload.as_bytes());
+    }
+}
@@ -0,0 +1,20 @@
+/// A method of storing individual payload bits in a cover of type `T`.
+/// Positions are numbered from zero up to `positions(cover)`; what a position maps to (a header flag, the low bit of a field, ...) is up to the carrier.
+pub trait BitCarrier<T: ?Sized> {
+    /// Returns the number of bit positions available in the cover.
+    fn positions(&self, cover: &T) -> usize;
+
+    /// Reads the bit currently stored at the given position.
+    fn read_bit(&self, cover: &T, position: usize) -> bool;
+
+    /// Stores a bit at the given position, modifying the cover in place.
+    fn write_bit(&self, cover: &mut T, position: usize, bit: bool);
+
+    /// Returns the distortion caused by changing the bit at the given position.
+    /// Carriers override this to steer adaptive coding away from positions where a change is easy to notice.
+    /// A cost of `f64::INFINITY` marks a position that must never change. Whether a cost is infinite must not depend on the bits written,
+    /// since extraction skips those positions when locating the header.
+    fn cost(&self, _cover: &T, _position: usize) -> f64 {
+        1.0
+    }
+}
@@ -0,0 +1,40 @@
+/// Splits bytes into individual bits, most significant bit first.
+pub fn bytes_to_bits(bytes: &[u8]) -> Vec<bool> {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1))
+        .collect()
+}
+
+/// Packs bits into bytes, most significant bit first.
+/// A trailing partial byte is padded with zero bits.
+pub fn bits_to_bytes(bits: &[bool]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0, |byte, (i, bit)| byte | ((*bit as u8) << (7 - i)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn splits_bytes_msb_first() {
+        let bits = bytes_to_bits(&[0b10000001]);
+        assert_eq!(
+            bits,
+            vec![true, false, false, false, false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn packs_bits_back_into_bytes() {
+        let bytes = vec![0xDE, 0xAD, 0xBE, 0xEF];
+        assert_eq!(bits_to_bytes(&bytes_to_bits(&bytes)), bytes);
+    }
+}
@@ -0,0 +1,153 @@
+use super::packet::{PACKET_DATA_BYTES, PACKET_OVERHEAD_BYTES};
+#[cfg(feature = "crypto")]
+use super::CONTAINER_OVERHEAD;
+use super::{Coding, EmbedOptions, Fec, HammingCode, Layout, HEADER_BITS};
+
+/// Bytes added to every payload by sealing it in a container.
+#[cfg(feature = "crypto")]
+const SEAL_OVERHEAD: usize = CONTAINER_OVERHEAD;
+#[cfg(not(feature = "crypto"))]
+const SEAL_OVERHEAD: usize = 0;
+
+/// How much a carrier can hold with given embedding options, and how much of it embedding would change.
+#[derive(Copy, Clone, Debug)]
+pub struct Capacity {
+    /// Number of carrier bit positions.
+    pub raw_bits: usize,
+    /// Largest payload that fits once the container, header, packet framing and error correction are accounted for.
+    /// Compression is not taken into account, since its effect depends on the payload.
+    pub payload_bytes: usize,
+    /// Expected fraction of carrier positions changed by embedding a payload of `payload_bytes`.
+    pub change_rate: f64,
+    options: EmbedOptions,
+}
+
+impl Capacity {
+    /// Estimates the capacity of a carrier with the given number of positions.
+    pub fn estimate(raw_bits: usize, options: EmbedOptions) -> Capacity {
+        let mut capacity = Capacity {
+            raw_bits,
+            payload_bytes: 0,
+            change_rate: 0.0,
+            options,
+        };
+
+        // Whether a payload fits only grows harder with its length, so the largest one is found by bisection.
+        let (mut low, mut high) = (0, raw_bits / 8 + 1);
+        while low + 1 < high {
+            let middle = (low + high) / 2;
+            match capacity.changes(middle) {
+                Some(_) => low = middle,
+                None => high = middle,
+            }
+        }
+        capacity.payload_bytes = low;
+        capacity.change_rate = capacity.change_rate_for(low).unwrap_or(0.0);
+        capacity
+    }
+
+    /// Returns the expected fraction of carrier positions changed by embedding a payload of the given length, or `None` if it does not fit.
+    pub fn change_rate_for(&self, payload_length: usize) -> Option<f64> {
+        Some(self.changes(payload_length)? / self.raw_bits as f64)
+    }
+
+    /// Returns the expected number of carrier bits changed by embedding a payload of the given length, assuming the cover bits are random.
+    fn changes(&self, payload_length: usize) -> Option<f64> {
+        let sealed = payload_length + SEAL_OVERHEAD;
+        match self.options.layout {
+            Layout::Packets => {
+                let packets = sealed.div_ceil(PACKET_DATA_BYTES).max(1);
+                let round = (sealed + packets * PACKET_OVERHEAD_BYTES) * 8;
+                if round > self.raw_bits {
+                    return None;
+                }
+                Some((self.raw_bits / round * round) as f64 / 2.0)
+            }
+            Layout::Scattered => {
+                let available = self.raw_bits.checked_sub(HEADER_BITS)?;
+                let message = Fec::new(self.options.fec_overhead).encoded_length(sealed) * 8;
+                if message > available {
+                    return None;
+                }
+                let header = HEADER_BITS as f64 / 2.0;
+                let payload = match self.options.coding {
+                    Coding::Plain => message as f64 / 2.0,
+                    Coding::Hamming => {
+                        let code = HammingCode::for_capacity(message, available)?;
+                        let blocks = message.div_ceil(code.k) as f64;
+                        blocks * (1.0 - 0.5f64.powi(code.k as i32))
+                    }
+                    Coding::Trellis if message == 0 => return None,
+                    Coding::Trellis => {
+                        available as f64 * inverse_entropy(message as f64 / available as f64)
+                    }
+                };
+                Some(header + payload)
+            }
+        }
+    }
+}
+
+/// Inverts the binary entropy function on [0, 0.5], giving the lowest change rate at which a relative payload can be embedded.
+/// Syndrome-trellis codes come close to this bound.
+fn inverse_entropy(relative_payload: f64) -> f64 {
+    let entropy = |p: f64| match p {
+        0.0 => 0.0,
+        p => -p * p.log2() - (1.0 - p) * (1.0 - p).log2(),
+    };
+    let (mut low, mut high) = (0.0, 0.5);
+    for _ in 0..50 {
+        let middle = (low + high) / 2.0;
+        if entropy(middle) < relative_payload {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    high
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn accounts_for_header_and_container() {
+        let capacity = Capacity::estimate(
+            HEADER_BITS + 8 * (100 + SEAL_OVERHEAD),
+            EmbedOptions::default(),
+        );
+        assert_eq!(capacity.payload_bytes, 100);
+        assert!(capacity.change_rate_for(101).is_none());
+        assert!((capacity.change_rate - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn matrix_embedding_lowers_change_rate() {
+        let plain = Capacity::estimate(100_000, EmbedOptions::default());
+        let options = |coding| EmbedOptions {
+            coding,
+            ..EmbedOptions::default()
+        };
+        let hamming = Capacity::estimate(100_000, options(Coding::Hamming));
+        let trellis = Capacity::estimate(100_000, options(Coding::Trellis));
+        assert_eq!(hamming.payload_bytes, plain.payload_bytes);
+
+        let plain = plain.change_rate_for(500).unwrap();
+        let hamming = hamming.change_rate_for(500).unwrap();
+        let trellis = trellis.change_rate_for(500).unwrap();
+        assert!(trellis < hamming && hamming < plain);
+    }
+
+    #[test]
+    fn packets_carry_less_than_scattered_layout() {
+        let options = EmbedOptions {
+            layout: Layout::Packets,
+            ..EmbedOptions::default()
+        };
+        let packets = Capacity::estimate(10_000, options);
+        let scattered = Capacity::estimate(10_000, EmbedOptions::default());
+        assert!(packets.payload_bytes < scattered.payload_bytes);
+        assert!(packets.payload_bytes > 0);
+    }
+}
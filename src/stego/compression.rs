@@ -0,0 +1,76 @@
+#[cfg(feature = "compression")]
+use std::io::{Read, Write};
+
+#[cfg(feature = "compression")]
+const ZSTD_LEVEL: i32 = 19;
+
+/// Compression applied to the payload before it is encrypted.
+/// Without the `compression` feature only `None` is functional: the other methods never shrink a payload and cannot be decompressed.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Compression {
+    #[default]
+    None,
+    Deflate,
+    Zstd,
+}
+
+impl Compression {
+    /// Identifier stored in a container to record the compression used.
+    pub fn id(&self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Deflate => 1,
+            Compression::Zstd => 2,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Compression> {
+        match id {
+            0 => Some(Compression::None),
+            1 => Some(Compression::Deflate),
+            2 => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Compresses the data. Returns it unchanged when the method is not available in this build.
+    pub fn compress(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Compression::None => data.to_vec(),
+            #[cfg(feature = "compression")]
+            Compression::Deflate => {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+                encoder
+                    .write_all(data)
+                    .expect("writing to a Vec cannot fail");
+                encoder.finish().expect("writing to a Vec cannot fail")
+            }
+            #[cfg(feature = "compression")]
+            Compression::Zstd => {
+                zstd::bulk::compress(data, ZSTD_LEVEL).expect("compressing to a Vec cannot fail")
+            }
+            #[cfg(not(feature = "compression"))]
+            Compression::Deflate | Compression::Zstd => data.to_vec(),
+        }
+    }
+
+    /// Decompresses the data, returning `None` if it is malformed or the method is not available in this build.
+    pub fn decompress(&self, data: &[u8]) -> Option<Vec<u8>> {
+        match self {
+            Compression::None => Some(data.to_vec()),
+            #[cfg(feature = "compression")]
+            Compression::Deflate => {
+                let mut decompressed = Vec::new();
+                flate2::read::DeflateDecoder::new(data)
+                    .read_to_end(&mut decompressed)
+                    .ok()?;
+                Some(decompressed)
+            }
+            #[cfg(feature = "compression")]
+            Compression::Zstd => zstd::stream::decode_all(data).ok(),
+            #[cfg(not(feature = "compression"))]
+            Compression::Deflate | Compression::Zstd => None,
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use rand::RngCore;
+
+use super::{Compression, StegoError, StegoKey};
+
+const ENCRYPTION_LABEL: &str = "encryption";
+const NONCE_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16;
+
+/// Bytes a sealed container adds to its payload: the nonce, the compression identifier and the authentication tag.
+pub const CONTAINER_OVERHEAD: usize = NONCE_LENGTH + 1 + TAG_LENGTH;
+
+/// The encrypted envelope around a payload.
+/// A sealed container is the random nonce followed by the ChaCha20-Poly1305 encryption of the compression identifier and the (possibly compressed) payload.
+/// Keeping the compression choice inside the ciphertext means nothing about the payload is visible without the key.
+#[derive(Debug, PartialEq)]
+pub struct Container {
+    pub compression: Compression,
+    pub data: Vec<u8>,
+}
+
+impl Container {
+    /// Compresses the payload with the requested method, falling back to storing it raw when compression does not make it smaller.
+    pub fn new(payload: &[u8], compression: Compression) -> Container {
+        let compressed = compression.compress(payload);
+        if compressed.len() < payload.len() {
+            Container {
+                compression,
+                data: compressed,
+            }
+        } else {
+            Container {
+                compression: Compression::None,
+                data: payload.to_vec(),
+            }
+        }
+    }
+
+    /// Encrypts the container with the key's encryption subkey.
+    pub fn seal(&self, key: &StegoKey) -> Vec<u8> {
+        let mut nonce = [0; NONCE_LENGTH];
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let mut plaintext = vec![self.compression.id()];
+        plaintext.extend(&self.data);
+        let ciphertext = cipher(key)
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .expect("encrypting to a Vec cannot fail");
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        sealed
+    }
+
+    /// Decrypts and decompresses a sealed container, returning the original payload.
+    pub fn open(key: &StegoKey, sealed: &[u8]) -> Result<Vec<u8>, StegoError> {
+        if sealed.len() < CONTAINER_OVERHEAD {
+            return Err(StegoError::DecryptionFailed);
+        }
+
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+        let plaintext = cipher(key)
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| StegoError::DecryptionFailed)?;
+
+        Compression::from_id(plaintext[0])
+            .and_then(|compression| compression.decompress(&plaintext[1..]))
+            .ok_or(StegoError::DecryptionFailed)
+    }
+}
+
+fn cipher(key: &StegoKey) -> ChaCha20Poly1305 {
+    ChaCha20Poly1305::new(&key.subkey(ENCRYPTION_LABEL).into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn compresses_text_payloads() {
+        let payload = br#"{"message": "meet at noon", "repeat": "meet at noon, meet at noon"}"#;
+        for compression in [Compression::Deflate, Compression::Zstd] {
+            let container = Container::new(payload, compression);
+            assert_eq!(container.compression, compression);
+            assert!(container.data.len() < payload.len());
+
+            let key = StegoKey::derive("hunter2");
+            assert_eq!(
+                Container::open(&key, &container.seal(&key)).unwrap(),
+                payload
+            );
+        }
+    }
+
+    #[test]
+    fn stores_incompressible_payloads_raw() {
+        let payload: Vec<u8> = (0..64).map(|i| (i * 197 + 89) as u8).collect();
+        let container = Container::new(&payload, Compression::Zstd);
+        assert_eq!(container.compression, Compression::None);
+        assert_eq!(container.data, payload);
+    }
+
+    #[test]
+    fn rejects_wrong_key() {
+        let sealed =
+            Container::new(b"secret", Compression::None).seal(&StegoKey::derive("hunter2"));
+        let result = Container::open(&StegoKey::derive("hunter3"), &sealed);
+        assert_eq!(result, Err(StegoError::DecryptionFailed));
+    }
+}
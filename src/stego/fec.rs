@@ -0,0 +1,93 @@
+use super::reedsolomon::ReedSolomon;
+
+/// Length of a full Reed–Solomon codeword.
+const CODEWORD_LENGTH: usize = 255;
+
+/// Forward error correction applied to the payload before it is embedded.
+/// The payload is split into Reed–Solomon blocks whose parity is `overhead` percent of the data, so a damaged carrier can still yield the message.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Fec {
+    pub overhead: u8,
+}
+
+impl Fec {
+    pub fn new(overhead: u8) -> Fec {
+        Fec { overhead }
+    }
+
+    /// Returns the number of bytes the encoded form of a payload of the given length occupies.
+    pub fn encoded_length(&self, payload_length: usize) -> usize {
+        self.blocks(payload_length)
+            .map(|(data, parity)| data + parity)
+            .sum()
+    }
+
+    pub fn encode(&self, payload: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(self.encoded_length(payload.len()));
+        let mut offset = 0;
+        for (data, parity) in self.blocks(payload.len()) {
+            encoded.extend(ReedSolomon::new(parity).encode(&payload[offset..offset + data]));
+            offset += data;
+        }
+        encoded
+    }
+
+    /// Decodes the encoded payload, returning it with the number of corrupted bytes that were corrected.
+    /// Returns `None` if any block has more errors than its parity can correct.
+    pub fn decode(&self, encoded: &[u8], payload_length: usize) -> Option<(Vec<u8>, usize)> {
+        let mut payload = Vec::with_capacity(payload_length);
+        let mut corrected = 0;
+        let mut offset = 0;
+        for (data, parity) in self.blocks(payload_length) {
+            let mut codeword = encoded.get(offset..offset + data + parity)?.to_vec();
+            corrected += ReedSolomon::new(parity).decode(&mut codeword)?;
+            payload.extend_from_slice(&codeword[..data]);
+            offset += data + parity;
+        }
+        Some((payload, corrected))
+    }
+
+    /// Splits a payload into blocks, yielding the data and parity length of each.
+    /// Full blocks fill a 255-byte codeword; the last block gets parity in proportion to its length.
+    fn blocks(&self, payload_length: usize) -> impl Iterator<Item = (usize, usize)> {
+        let overhead = self.overhead as usize;
+        let data_per_block = CODEWORD_LENGTH * 100 / (100 + overhead);
+        let parity_per_block = CODEWORD_LENGTH - data_per_block;
+
+        (0..payload_length.div_ceil(data_per_block)).map(move |i| {
+            let data = data_per_block.min(payload_length - i * data_per_block);
+            let parity = if overhead == 0 {
+                0
+            } else {
+                (data * parity_per_block).div_ceil(data_per_block).max(2)
+            };
+            (data, parity)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn adds_requested_overhead() {
+        let fec = Fec::new(25);
+        assert_eq!(fec.encoded_length(1000), 1250);
+        assert_eq!(Fec::new(0).encoded_length(1000), 1000);
+    }
+
+    #[test]
+    fn recovers_payload_spanning_several_blocks() {
+        let fec = Fec::new(20);
+        let payload: Vec<u8> = (0..600).map(|i| i as u8).collect();
+        let mut encoded = fec.encode(&payload);
+        for i in (0..encoded.len()).step_by(40) {
+            encoded[i] ^= 0xFF;
+        }
+
+        let (decoded, corrected) = fec.decode(&encoded, payload.len()).unwrap();
+        assert_eq!(decoded, payload);
+        assert_eq!(corrected, encoded.len().div_ceil(40));
+    }
+}
@@ -0,0 +1,102 @@
+/// Largest supported Hamming code parameter, giving blocks of 4095 carrier bits.
+pub const MAX_K: usize = 12;
+
+/// A (1, 2^k - 1, k) Hamming code used for matrix embedding.
+/// Each block of `2^k - 1` carrier bits holds `k` message bits as its syndrome, and embedding changes at most one carrier bit per block.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HammingCode {
+    pub k: usize,
+}
+
+impl HammingCode {
+    pub fn new(k: usize) -> HammingCode {
+        HammingCode { k }
+    }
+
+    /// Picks the largest code that still fits the message into the available carrier bits.
+    /// Larger blocks change fewer carrier bits per message bit, so the code is chosen from the payload-to-capacity ratio.
+    /// Returns `None` if the message does not fit even without matrix embedding.
+    pub fn for_capacity(message_bits: usize, available: usize) -> Option<HammingCode> {
+        (1..=MAX_K)
+            .rev()
+            .map(HammingCode::new)
+            .find(|code| code.cover_bits(message_bits) <= available)
+    }
+
+    /// Returns the number of carrier bits in one block.
+    pub fn block_length(&self) -> usize {
+        (1 << self.k) - 1
+    }
+
+    /// Returns the number of carrier bits needed to hold a message of the given length.
+    pub fn cover_bits(&self, message_bits: usize) -> usize {
+        message_bits.div_ceil(self.k) * self.block_length()
+    }
+
+    /// Adjusts a block of carrier bits so that its syndrome equals the message bits.
+    /// A message shorter than `k` bits is padded with zeros.
+    pub fn embed(&self, block: &mut [bool], message: &[bool]) {
+        if let Some(index) = self.change(block, message) {
+            block[index] = !block[index];
+        }
+    }
+
+    /// Returns the index of the carrier bit that [`HammingCode::embed`] would flip, or `None` if the block already holds the message.
+    pub fn change(&self, block: &[bool], message: &[bool]) -> Option<usize> {
+        let target = message.iter().enumerate().fold(0, |target, (i, bit)| {
+            target | ((*bit as usize) << (self.k - 1 - i))
+        });
+        let difference = self.syndrome(block) ^ target;
+        difference.checked_sub(1)
+    }
+
+    /// Reads the `k` message bits held by a block of carrier bits.
+    pub fn extract(&self, block: &[bool]) -> Vec<bool> {
+        let syndrome = self.syndrome(block);
+        (0..self.k)
+            .map(|i| (syndrome >> (self.k - 1 - i)) & 1 == 1)
+            .collect()
+    }
+
+    /// The syndrome of a block is the XOR of the (one-based) indices of its set bits.
+    fn syndrome(&self, block: &[bool]) -> usize {
+        block
+            .iter()
+            .enumerate()
+            .filter(|(_, bit)| **bit)
+            .fold(0, |syndrome, (i, _)| syndrome ^ (i + 1))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn embeds_with_at_most_one_change_per_block() {
+        let code = HammingCode::new(3);
+        for target in 0..8u8 {
+            let message: Vec<bool> = (0..3).map(|i| (target >> (2 - i)) & 1 == 1).collect();
+            let original = vec![true, false, true, true, false, false, true];
+            let mut block = original.clone();
+            code.embed(&mut block, &message);
+
+            assert_eq!(code.extract(&block), message);
+            let changes = block.iter().zip(&original).filter(|(a, b)| a != b).count();
+            assert!(changes <= 1);
+        }
+    }
+
+    #[test]
+    fn picks_largest_code_that_fits() {
+        assert_eq!(
+            HammingCode::for_capacity(100, 100),
+            Some(HammingCode::new(1))
+        );
+        assert_eq!(
+            HammingCode::for_capacity(100, 300),
+            Some(HammingCode::new(3))
+        );
+        assert_eq!(HammingCode::for_capacity(100, 99), None);
+    }
+}
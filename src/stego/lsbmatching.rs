@@ -0,0 +1,68 @@
+/// Returns the value closest to `value` whose low `width` bits have bit `index` set to `bit` and the other low bits unchanged, staying within `min..=max`.
+/// With a width of one this is LSB matching: the value moves by one in either direction, rather than having its low bit overwritten, which leaves the telltale pairing of values that LSB replacement causes.
+/// Ties between moving up and down are broken by `position`, hashed so that the direction is unrelated to the cover.
+/// Returns `value` unchanged if no value in range has the wanted bits.
+pub fn lsb_match(
+    value: i64,
+    width: u32,
+    index: u32,
+    bit: bool,
+    min: i64,
+    max: i64,
+    position: usize,
+) -> i64 {
+    let modulus = 1i64 << width;
+    let low = value.rem_euclid(modulus);
+    let target = match bit {
+        true => low | (1 << index),
+        false => low & !(1 << index),
+    };
+    if target == low {
+        return value;
+    }
+
+    let base = value - low + target;
+    let up = mix(position as u64) & 1 == 1;
+    [base - modulus, base, base + modulus]
+        .into_iter()
+        .filter(|candidate| (min..=max).contains(candidate))
+        .min_by_key(|candidate| {
+            let distance = (candidate - value).abs();
+            // Of two candidates at the same distance, prefer the one in the chosen direction.
+            let against = (*candidate > value) != up;
+            (distance, against)
+        })
+        .unwrap_or(value)
+}
+
+/// SplitMix64 finalizer, spreading the bits of a position over the whole word.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E3779B97F4A7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn moves_by_one_in_both_directions() {
+        let moves: Vec<i64> = (0..64)
+            .map(|position| lsb_match(100, 1, 0, true, -128, 127, position) - 100)
+            .collect();
+        assert!(moves.iter().all(|change| change.abs() == 1));
+        assert!(moves.contains(&1) && moves.contains(&-1));
+        assert_eq!(lsb_match(100, 1, 0, false, -128, 127, 0), 100);
+    }
+
+    #[test]
+    fn keeps_other_low_bits_and_range() {
+        let value = lsb_match(0b1010, 3, 2, false, 0, 255, 7);
+        assert_eq!(value & 0b111, 0b010);
+        assert!((value - 0b1010).abs() <= 4);
+        assert_eq!(lsb_match(127, 1, 0, false, -128, 127, 1), 126);
+        assert_eq!(lsb_match(-128, 1, 0, true, -128, 127, 2), -127);
+    }
+}
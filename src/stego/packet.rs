@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+
+use crc::{Crc, CRC_16_IBM_3740};
+use rand::RngCore;
+
+use super::bits::bytes_to_bits;
+use super::{StegoError, StegoKey};
+
+/// Number of payload bytes carried by a full packet.
+pub const PACKET_DATA_BYTES: usize = 32;
+
+/// Bytes in a packet besides its data: the sync marker, sequence number, packet count, data length and checksum.
+pub const PACKET_OVERHEAD_BYTES: usize = 4 + 2 + 2 + 1 + 2;
+
+/// Bits in the longest packet, sync marker included.
+const MAX_PACKET_BITS: usize = (PACKET_OVERHEAD_BYTES + PACKET_DATA_BYTES) * 8;
+
+/// Bits a scanner collects before searching them for packets.
+const SCAN_BITS: usize = 64 * MAX_PACKET_BITS;
+
+const SYNC_LABEL: &str = "sync";
+const WHITENING_LABEL: &str = "packet";
+const CHECKSUM: Crc<u16> = Crc::<u16>::new(&CRC_16_IBM_3740);
+
+/// One independently decodable piece of a payload.
+/// On the carrier each packet starts with a sync marker derived from the key, so it can be found again after the frames before it are removed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet {
+    pub sequence: u16,
+    pub count: u16,
+    pub data: Vec<u8>,
+}
+
+impl Packet {
+    /// Splits a payload into packets. An empty payload still produces one (empty) packet.
+    /// Fails if the payload needs more packets than a 16-bit count can number.
+    pub fn split(payload: &[u8]) -> Result<Vec<Packet>, StegoError> {
+        let chunks: Vec<&[u8]> = if payload.is_empty() {
+            vec![&[]]
+        } else {
+            payload.chunks(PACKET_DATA_BYTES).collect()
+        };
+        let count = u16::try_from(chunks.len()).map_err(|_| StegoError::TooManyPackets {
+            count: chunks.len(),
+        })?;
+        Ok(chunks
+            .into_iter()
+            .enumerate()
+            .map(|(sequence, data)| Packet {
+                sequence: sequence as u16,
+                count,
+                data: data.to_vec(),
+            })
+            .collect())
+    }
+
+    /// Returns the bits of every packet of the payload in order, which is one round of the packet layout.
+    pub fn bitstream(key: &StegoKey, payload: &[u8]) -> Result<Vec<bool>, StegoError> {
+        Ok(Packet::split(payload)?
+            .iter()
+            .flat_map(|packet| bytes_to_bits(&packet.to_bytes(key)))
+            .collect())
+    }
+
+    /// Reassembles a payload from packets found on the carrier, ignoring duplicates.
+    /// Returns the number of distinct packets found and the expected total if any are missing.
+    pub fn join(packets: &[Packet]) -> Result<Vec<u8>, (usize, usize)> {
+        let count = packets.first().map(|packet| packet.count).unwrap_or(1) as usize;
+        let mut slots: Vec<Option<&Packet>> = vec![None; count];
+        for packet in packets
+            .iter()
+            .filter(|packet| packet.count as usize == count)
+        {
+            slots[packet.sequence as usize] = Some(packet);
+        }
+
+        let found = slots.iter().flatten().count();
+        if found < count {
+            return Err((found, count));
+        }
+        Ok(slots
+            .into_iter()
+            .flatten()
+            .flat_map(|packet| packet.data.clone())
+            .collect())
+    }
+
+    /// Serializes the packet for embedding. Everything after the sync marker is whitened with a keystream so that repeated fields do not stand out.
+    pub fn to_bytes(&self, key: &StegoKey) -> Vec<u8> {
+        let mut body = Vec::with_capacity(PACKET_OVERHEAD_BYTES + self.data.len());
+        body.extend(self.sequence.to_be_bytes());
+        body.extend(self.count.to_be_bytes());
+        body.push(self.data.len() as u8);
+        body.extend(&self.data);
+        body.extend(CHECKSUM.checksum(&body).to_be_bytes());
+
+        let mut bytes = sync_marker(key).to_vec();
+        bytes.extend(
+            body.iter()
+                .zip(whitening(key))
+                .map(|(byte, mask)| byte ^ mask),
+        );
+        bytes
+    }
+
+    /// Scans a carrier bitstream for packets, testing every bit offset for the sync marker.
+    /// Packets that were cut, damaged or embedded with another key fail the marker or checksum and are skipped.
+    pub fn find(key: &StegoKey, bits: &[bool]) -> Vec<Packet> {
+        let marker = u32::from_be_bytes(sync_marker(key));
+        let whitening = whitening(key);
+        let read_byte = |offset: usize| -> Option<u8> {
+            let bits = bits.get(offset..offset + 8)?;
+            Some(bits.iter().fold(0, |byte, bit| (byte << 1) | *bit as u8))
+        };
+
+        let mut packets = Vec::new();
+        let mut window: u32 = 0;
+        let mut i = 0;
+        while i < bits.len() {
+            window = (window << 1) | bits[i] as u32;
+            i += 1;
+            if i < 32 || window != marker {
+                continue;
+            }
+
+            let body: Option<Vec<u8>> = (0..5).map(|j| read_byte(i + j * 8)).collect();
+            let Some(mut body) = body else {
+                break;
+            };
+            for (byte, mask) in body.iter_mut().zip(&whitening) {
+                *byte ^= mask;
+            }
+            let length = body[4] as usize;
+            if length > PACKET_DATA_BYTES {
+                continue;
+            }
+
+            let rest: Option<Vec<u8>> = (5..7 + length).map(|j| read_byte(i + j * 8)).collect();
+            let Some(rest) = rest else {
+                continue;
+            };
+            body.extend(
+                rest.iter()
+                    .zip(&whitening[5..])
+                    .map(|(byte, mask)| byte ^ mask),
+            );
+
+            let (contents, checksum) = body.split_at(5 + length);
+            if CHECKSUM.checksum(contents).to_be_bytes() != checksum {
+                continue;
+            }
+
+            let sequence = u16::from_be_bytes([body[0], body[1]]);
+            let count = u16::from_be_bytes([body[2], body[3]]);
+            if sequence >= count {
+                continue;
+            }
+
+            packets.push(Packet {
+                sequence,
+                count,
+                data: body[5..5 + length].to_vec(),
+            });
+            i += (7 + length) * 8;
+            window = 0;
+        }
+        packets
+    }
+}
+
+/// Finds packets in a carrier bitstream that arrives one bit at a time, such as while reading a file frame by frame.
+/// Only a bounded window of bits and one copy of each distinct packet are held in memory.
+pub struct PacketScanner<'a> {
+    key: &'a StegoKey,
+    bits: Vec<bool>,
+    packets: BTreeMap<(u16, u16), Packet>,
+    complete: bool,
+}
+
+impl<'a> PacketScanner<'a> {
+    pub fn new(key: &'a StegoKey) -> PacketScanner<'a> {
+        PacketScanner {
+            key,
+            bits: Vec::new(),
+            packets: BTreeMap::new(),
+            complete: false,
+        }
+    }
+
+    pub fn push(&mut self, bit: bool) {
+        self.bits.push(bit);
+        if self.bits.len() >= SCAN_BITS {
+            self.scan();
+        }
+    }
+
+    /// Returns whether every packet of some payload has been found, so that the rest of the carrier need not be read.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Reassembles the payload from the packets found.
+    pub fn finish(mut self) -> Result<Vec<u8>, StegoError> {
+        self.scan();
+        let packets: Vec<Packet> = self.packets.into_values().collect();
+        if packets.is_empty() {
+            return Err(StegoError::NoPayload);
+        }
+        Packet::join(&packets).map_err(|(found, total)| StegoError::MissingPackets { found, total })
+    }
+
+    /// Searches the collected bits, keeping only those that may be the start of a packet that has not fully arrived.
+    fn scan(&mut self) {
+        for packet in Packet::find(self.key, &self.bits) {
+            self.packets
+                .entry((packet.count, packet.sequence))
+                .or_insert(packet);
+        }
+        let keep = self.bits.len().saturating_sub(MAX_PACKET_BITS - 1);
+        self.bits.drain(..keep);
+
+        // Packets are keyed by count first, so the packets of one payload are adjacent.
+        let mut found = BTreeMap::new();
+        for (count, _) in self.packets.keys() {
+            *found.entry(*count).or_insert(0) += 1;
+        }
+        self.complete = found.into_iter().any(|(count, found)| found == count);
+    }
+}
+
+fn sync_marker(key: &StegoKey) -> [u8; 4] {
+    let subkey = key.subkey(SYNC_LABEL);
+    [subkey[0], subkey[1], subkey[2], subkey[3]]
+}
+
+fn whitening(key: &StegoKey) -> Vec<u8> {
+    let mut mask = vec![0; PACKET_OVERHEAD_BYTES + PACKET_DATA_BYTES];
+    key.rng(WHITENING_LABEL).fill_bytes(&mut mask);
+    mask
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn splits_and_joins_payload() {
+        let payload: Vec<u8> = (0..100).collect();
+        let packets = Packet::split(&payload).unwrap();
+        assert_eq!(packets.len(), 4);
+        assert_eq!(Packet::join(&packets).unwrap(), payload);
+    }
+
+    #[test]
+    fn rejects_payload_with_too_many_packets() {
+        let payload = vec![0; (u16::MAX as usize + 1) * PACKET_DATA_BYTES];
+        assert_eq!(
+            Packet::split(&payload),
+            Err(StegoError::TooManyPackets {
+                count: u16::MAX as usize + 1
+            })
+        );
+        assert!(Packet::split(&payload[PACKET_DATA_BYTES..]).is_ok());
+    }
+
+    #[test]
+    fn finds_packets_at_any_bit_offset() {
+        let key = StegoKey::derive("hunter2");
+        let payload: Vec<u8> = (0..100).collect();
+        let mut bits = vec![true, false, true];
+        for packet in Packet::split(&payload).unwrap() {
+            bits.extend(bytes_to_bits(&packet.to_bytes(&key)));
+        }
+
+        let found = Packet::find(&key, &bits);
+        assert_eq!(Packet::join(&found).unwrap(), payload);
+        assert!(Packet::find(&StegoKey::derive("hunter3"), &bits).is_empty());
+    }
+
+    #[test]
+    fn reports_missing_packets() {
+        let payload: Vec<u8> = (0..100).collect();
+        let mut packets = Packet::split(&payload).unwrap();
+        packets.remove(1);
+        assert_eq!(Packet::join(&packets), Err((3, 4)));
+    }
+
+    #[test]
+    fn scans_bitstream_in_bounded_window() {
+        let key = StegoKey::derive("hunter2");
+        let payload: Vec<u8> = (0..=255).cycle().take(3000).collect();
+        let round = Packet::bitstream(&key, &payload).unwrap();
+
+        let mut scanner = PacketScanner::new(&key);
+        for bit in round.iter().chain(&round).skip(5000) {
+            scanner.push(*bit);
+            assert!(scanner.bits.len() <= SCAN_BITS);
+        }
+        assert!(scanner.is_complete());
+        assert_eq!(scanner.finish().unwrap(), payload);
+    }
+}
@@ -0,0 +1,54 @@
+use rand::seq::SliceRandom;
+
+use super::StegoKey;
+
+const PLACEMENT_LABEL: &str = "placement";
+
+/// A keyed pseudorandom ordering of carrier positions.
+/// Payload bit `i` is stored at `position(i)`, which spreads the changes evenly over the whole cover instead of clustering them at the start of the file.
+pub struct Permutation {
+    positions: Vec<usize>,
+}
+
+impl Permutation {
+    /// Shuffles the positions `0..length` with a generator seeded from the key's placement subkey.
+    pub fn new(key: &StegoKey, length: usize) -> Permutation {
+        let mut positions: Vec<usize> = (0..length).collect();
+        positions.shuffle(&mut key.rng(PLACEMENT_LABEL));
+        Permutation { positions }
+    }
+
+    /// Returns the carrier position that holds payload bit `index`.
+    pub fn position(&self, index: usize) -> usize {
+        self.positions[index]
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn covers_every_position_once() {
+        let key = StegoKey::derive("hunter2");
+        let permutation = Permutation::new(&key, 100);
+        let mut positions: Vec<usize> = (0..100).map(|i| permutation.position(i)).collect();
+        positions.sort();
+        assert_eq!(positions, (0..100).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn depends_on_key() {
+        let a = Permutation::new(&StegoKey::derive("hunter2"), 100);
+        let b = Permutation::new(&StegoKey::derive("hunter3"), 100);
+        assert!((0..100).any(|i| a.position(i) != b.position(i)));
+    }
+}
@@ -0,0 +1,238 @@
+/// Primitive polynomial x^8 + x^4 + x^3 + x^2 + 1 defining GF(2^8).
+const PRIMITIVE: u16 = 0x11D;
+
+/// Exponent and logarithm tables for GF(2^8) with generator 2.
+/// The exponent table is doubled so products can be looked up without reducing the sum of logarithms.
+struct GaloisTables {
+    exp: [u8; 512],
+    log: [u8; 256],
+}
+
+const TABLES: GaloisTables = {
+    let mut exp = [0u8; 512];
+    let mut log = [0u8; 256];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= PRIMITIVE;
+        }
+        i += 1;
+    }
+    while i < 512 {
+        exp[i] = exp[i - 255];
+        i += 1;
+    }
+    GaloisTables { exp, log }
+};
+
+fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    TABLES.exp[TABLES.log[a as usize] as usize + TABLES.log[b as usize] as usize]
+}
+
+fn div(a: u8, b: u8) -> u8 {
+    if a == 0 {
+        return 0;
+    }
+    TABLES.exp[(TABLES.log[a as usize] as usize + 255 - TABLES.log[b as usize] as usize) % 255]
+}
+
+/// Returns 2 raised to the given (possibly negative) power.
+fn pow2(power: isize) -> u8 {
+    TABLES.exp[power.rem_euclid(255) as usize]
+}
+
+// Polynomials are stored with the highest-degree coefficient first.
+
+fn poly_scale(p: &[u8], x: u8) -> Vec<u8> {
+    p.iter().map(|c| mul(*c, x)).collect()
+}
+
+fn poly_add(p: &[u8], q: &[u8]) -> Vec<u8> {
+    let length = p.len().max(q.len());
+    let mut r = vec![0; length];
+    for (i, c) in p.iter().enumerate() {
+        r[i + length - p.len()] = *c;
+    }
+    for (i, c) in q.iter().enumerate() {
+        r[i + length - q.len()] ^= *c;
+    }
+    r
+}
+
+fn poly_mul(p: &[u8], q: &[u8]) -> Vec<u8> {
+    let mut r = vec![0; p.len() + q.len() - 1];
+    for (i, a) in p.iter().enumerate() {
+        for (j, b) in q.iter().enumerate() {
+            r[i + j] ^= mul(*a, *b);
+        }
+    }
+    r
+}
+
+fn poly_eval(p: &[u8], x: u8) -> u8 {
+    p.iter().skip(1).fold(p[0], |y, c| mul(y, x) ^ c)
+}
+
+/// Divides by a monic polynomial and returns the remainder.
+fn poly_remainder(dividend: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut out = dividend.to_vec();
+    for i in 0..dividend.len() + 1 - divisor.len() {
+        let coefficient = out[i];
+        if coefficient != 0 {
+            for (j, d) in divisor.iter().enumerate().skip(1) {
+                out[i + j] ^= mul(*d, coefficient);
+            }
+        }
+    }
+    out.split_off(dividend.len() + 1 - divisor.len())
+}
+
+/// A systematic Reed–Solomon code over GF(2^8).
+/// Each codeword of up to 255 bytes carries `parity` check bytes, and up to `parity / 2` corrupted bytes at unknown positions can be corrected.
+pub struct ReedSolomon {
+    parity: usize,
+    generator: Vec<u8>,
+}
+
+impl ReedSolomon {
+    pub fn new(parity: usize) -> ReedSolomon {
+        let generator = (0..parity).fold(vec![1], |g, i| poly_mul(&g, &[1, pow2(i as isize)]));
+        ReedSolomon { parity, generator }
+    }
+
+    /// Returns the data followed by its parity bytes.
+    pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+        let mut padded = data.to_vec();
+        padded.resize(data.len() + self.parity, 0);
+        let mut codeword = data.to_vec();
+        codeword.extend(poly_remainder(&padded, &self.generator));
+        codeword
+    }
+
+    /// Corrects the codeword in place and returns the number of bytes that were corrected.
+    /// Returns `None` if there are more errors than the code can correct.
+    pub fn decode(&self, codeword: &mut [u8]) -> Option<usize> {
+        let syndromes = self.syndromes(codeword);
+        if syndromes.iter().all(|s| *s == 0) {
+            return Some(0);
+        }
+
+        let locator = self.error_locator(&syndromes)?;
+        let reversed: Vec<u8> = locator.iter().rev().copied().collect();
+        let positions: Vec<usize> = (0..codeword.len())
+            .filter(|i| poly_eval(&reversed, pow2(*i as isize)) == 0)
+            .map(|i| codeword.len() - 1 - i)
+            .collect();
+        if positions.len() != locator.len() - 1 {
+            return None;
+        }
+
+        self.correct(codeword, &syndromes, &positions);
+        if self.syndromes(codeword).iter().any(|s| *s != 0) {
+            return None;
+        }
+        Some(positions.len())
+    }
+
+    /// Evaluates the codeword at each root of the generator, with a leading zero for the Berlekamp–Massey indexing.
+    fn syndromes(&self, codeword: &[u8]) -> Vec<u8> {
+        std::iter::once(0)
+            .chain((0..self.parity).map(|i| poly_eval(codeword, pow2(i as isize))))
+            .collect()
+    }
+
+    /// Finds the error locator polynomial with the Berlekamp–Massey algorithm.
+    fn error_locator(&self, syndromes: &[u8]) -> Option<Vec<u8>> {
+        let mut locator = vec![1];
+        let mut old = vec![1];
+        let shift = syndromes.len() - self.parity;
+
+        for i in 0..self.parity {
+            let k = i + shift;
+            let mut delta = syndromes[k];
+            for j in 1..locator.len() {
+                delta ^= mul(locator[locator.len() - 1 - j], syndromes[k - j]);
+            }
+
+            old.push(0);
+            if delta != 0 {
+                if old.len() > locator.len() {
+                    let new = poly_scale(&old, delta);
+                    old = poly_scale(&locator, div(1, delta));
+                    locator = new;
+                }
+                locator = poly_add(&locator, &poly_scale(&old, delta));
+            }
+        }
+
+        let leading_zeros = locator.iter().take_while(|c| **c == 0).count();
+        locator.drain(..leading_zeros);
+        let errors = locator.len().checked_sub(1)?;
+        if errors * 2 > self.parity {
+            return None;
+        }
+        Some(locator)
+    }
+
+    /// Computes the error magnitudes with the Forney algorithm and applies them.
+    fn correct(&self, codeword: &mut [u8], syndromes: &[u8], positions: &[usize]) {
+        let coefficients: Vec<usize> = positions.iter().map(|p| codeword.len() - 1 - p).collect();
+        let locator = coefficients
+            .iter()
+            .fold(vec![1], |l, c| poly_mul(&l, &[pow2(*c as isize), 1]));
+
+        let reversed_syndromes: Vec<u8> = syndromes.iter().rev().copied().collect();
+        let mut divisor = vec![0; locator.len() + 1];
+        divisor[0] = 1;
+        let evaluator = poly_remainder(&poly_mul(&reversed_syndromes, &locator), &divisor);
+
+        let roots: Vec<u8> = coefficients
+            .iter()
+            .map(|c| pow2(*c as isize - 255))
+            .collect();
+        for (i, root) in roots.iter().enumerate() {
+            let inverse = div(1, *root);
+            let derivative = roots
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .fold(1, |d, (_, other)| mul(d, 1 ^ mul(inverse, *other)));
+            let y = mul(*root, poly_eval(&evaluator, inverse));
+            codeword[positions[i]] ^= div(y, derivative);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn corrects_up_to_half_the_parity() {
+        let code = ReedSolomon::new(10);
+        let codeword = code.encode(b"hello, reed solomon");
+        let mut damaged = codeword.clone();
+        for i in [0, 3, 7, 20, 28] {
+            damaged[i] ^= 0x5A;
+        }
+        assert_eq!(code.decode(&mut damaged), Some(5));
+        assert_eq!(damaged, codeword);
+    }
+
+    #[test]
+    fn rejects_too_many_errors() {
+        let code = ReedSolomon::new(4);
+        let mut damaged = code.encode(b"hello, reed solomon");
+        for byte in damaged.iter_mut().take(6) {
+            *byte ^= 0xFF;
+        }
+        assert_eq!(code.decode(&mut damaged), None);
+    }
+}
@@ -0,0 +1,10 @@
+/// Cost of changing a sample of digital silence, where any change stands out against the surrounding zeros.
+pub const SILENT_SAMPLE_COST: f64 = 8.0;
+
+/// Returns the cost of changing an audio sample with the given value, for carriers that write to the samples themselves.
+pub fn sample_cost(sample: i64) -> f64 {
+    match sample {
+        0 => SILENT_SAMPLE_COST,
+        _ => 1.0,
+    }
+}
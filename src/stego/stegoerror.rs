@@ -0,0 +1,52 @@
+/// Error type for embedding and extracting payloads.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StegoError {
+    PayloadTooLarge { required: usize, available: usize },
+    NoPayload,
+    NoEmbeddingPath,
+    Uncorrectable,
+    MissingPackets { found: usize, total: usize },
+    TooManyPackets { count: usize },
+    DecryptionFailed,
+}
+
+impl StegoError {
+    pub fn description(&self) -> String {
+        match self {
+            StegoError::PayloadTooLarge {
+                required,
+                available,
+            } => format!(
+                "Payload requires {} carrier bits but only {} are available.",
+                required, available
+            ),
+            StegoError::NoPayload => String::from("No payload could be found for the given key."),
+            StegoError::NoEmbeddingPath => String::from(
+                "The payload cannot be embedded without changing a position that must not change.",
+            ),
+            StegoError::Uncorrectable => {
+                String::from("The payload is too damaged for its error correction to repair.")
+            }
+            StegoError::MissingPackets { found, total } => format!(
+                "Only {} of the payload's {} packets could be found.",
+                found, total
+            ),
+            StegoError::TooManyPackets { count } => format!(
+                "The payload needs {} packets, more than the {} a packet layout can number.",
+                count,
+                u16::MAX
+            ),
+            StegoError::DecryptionFailed => {
+                String::from("The payload could not be decrypted with the given key.")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for StegoError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(&self.description())
+    }
+}
+
+impl std::error::Error for StegoError {}
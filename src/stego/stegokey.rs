@@ -0,0 +1,55 @@
+use hmac::{Hmac, Mac};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use sha2::Sha256;
+
+const KEY_SALT: &[u8] = b"stegocrypt";
+const KEY_ROUNDS: u32 = 100_000;
+
+/// A secret key derived from the user's password.
+/// Each keyed step of the embedding derives its own subkey from this, so one password drives the whole process.
+#[derive(Clone)]
+pub struct StegoKey {
+    bytes: [u8; 32],
+}
+
+impl StegoKey {
+    /// Derives a key from a password with PBKDF2-HMAC-SHA256.
+    /// The salt is fixed because extraction has to reproduce the key from the password alone, before anything is read from the carrier.
+    pub fn derive(password: &str) -> StegoKey {
+        let bytes =
+            pbkdf2::pbkdf2_hmac_array::<Sha256, 32>(password.as_bytes(), KEY_SALT, KEY_ROUNDS);
+        StegoKey { bytes }
+    }
+
+    /// Returns the subkey for the given purpose, computed as HMAC-SHA256 over the label.
+    pub fn subkey(&self, label: &str) -> [u8; 32] {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.bytes).expect("HMAC accepts any key length");
+        mac.update(label.as_bytes());
+        mac.finalize().into_bytes().into()
+    }
+
+    /// Returns a deterministic random number generator seeded from the subkey for the given purpose.
+    pub fn rng(&self, label: &str) -> ChaCha20Rng {
+        ChaCha20Rng::from_seed(self.subkey(label))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn derives_same_key_from_same_password() {
+        let a = StegoKey::derive("hunter2");
+        let b = StegoKey::derive("hunter2");
+        assert_eq!(a.subkey("placement"), b.subkey("placement"));
+    }
+
+    #[test]
+    fn derives_distinct_subkeys_per_label() {
+        let key = StegoKey::derive("hunter2");
+        assert_ne!(key.subkey("placement"), key.subkey("encryption"));
+    }
+}
@@ -0,0 +1,147 @@
+use rand::Rng;
+
+use super::StegoKey;
+
+/// Constraint height of the code. The trellis has `2^HEIGHT` states.
+const HEIGHT: usize = 7;
+const STATES: usize = 1 << HEIGHT;
+const TRELLIS_LABEL: &str = "trellis";
+
+/// A syndrome-trellis code for minimal-distortion embedding.
+/// Each message bit is the syndrome of a window of `width` carrier bits, and the Viterbi algorithm picks the set of changes with the lowest total cost.
+pub struct TrellisCode {
+    width: usize,
+    columns: Vec<u128>,
+}
+
+impl TrellisCode {
+    /// Builds the code for spreading `message_bits` over `cover_bits` carrier bits.
+    /// The columns of the submatrix are drawn from the key so that the code is not public.
+    /// Returns `None` if there are fewer carrier bits than message bits.
+    pub fn new(key: &StegoKey, message_bits: usize, cover_bits: usize) -> Option<TrellisCode> {
+        if message_bits == 0 || cover_bits < message_bits {
+            return None;
+        }
+
+        let width = cover_bits / message_bits;
+        let mut rng = key.rng(TRELLIS_LABEL);
+        let top_and_bottom = 1 | (1 << (HEIGHT - 1));
+        let columns = (0..width)
+            .map(|_| (rng.gen::<u128>() & (STATES as u128 - 1)) | top_and_bottom)
+            .collect();
+
+        Some(TrellisCode { width, columns })
+    }
+
+    /// Returns the carrier bits to store, changed from `cover` so that their syndrome equals the message at the lowest total cost.
+    /// Positions with an infinite cost are never changed.
+    pub fn embed(&self, cover: &[bool], costs: &[f64], message: &[bool]) -> Option<Vec<bool>> {
+        let length = message.len() * self.width;
+        let mut weights = [f64::INFINITY; STATES];
+        weights[0] = 0.0;
+        let mut paths = vec![0u128; length];
+
+        for (i, bit) in message.iter().enumerate() {
+            for (j, column) in self.columns.iter().enumerate() {
+                let index = i * self.width + j;
+                let (keep_zero, keep_one) = match cover[index] {
+                    false => (0.0, costs[index]),
+                    true => (costs[index], 0.0),
+                };
+
+                let mut next = [f64::INFINITY; STATES];
+                for (state, weight) in next.iter_mut().enumerate() {
+                    let zero = weights[state] + keep_zero;
+                    let one = weights[state ^ *column as usize] + keep_one;
+                    if one < zero {
+                        *weight = one;
+                        paths[index] |= 1u128 << state;
+                    } else {
+                        *weight = zero;
+                    }
+                }
+                weights = next;
+            }
+
+            let mut shifted = [f64::INFINITY; STATES];
+            for (state, weight) in shifted.iter_mut().take(STATES / 2).enumerate() {
+                *weight = weights[2 * state + *bit as usize];
+            }
+            weights = shifted;
+        }
+
+        let (mut state, cost) = weights
+            .iter()
+            .enumerate()
+            .min_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(state, cost)| (state, *cost))?;
+        if cost.is_infinite() {
+            return None;
+        }
+
+        let mut stego = cover.to_vec();
+        for (i, bit) in message.iter().enumerate().rev() {
+            state = 2 * state + *bit as usize;
+            for (j, column) in self.columns.iter().enumerate().rev() {
+                let index = i * self.width + j;
+                let y = (paths[index] >> state) & 1 == 1;
+                stego[index] = y;
+                if y {
+                    state ^= *column as usize;
+                }
+            }
+        }
+
+        Some(stego)
+    }
+
+    /// Computes the message held by the given carrier bits.
+    pub fn extract(&self, stego: &[bool], message_bits: usize) -> Vec<bool> {
+        let mut state = 0;
+        let mut message = Vec::with_capacity(message_bits);
+        for i in 0..message_bits {
+            for (j, column) in self.columns.iter().enumerate() {
+                if stego[i * self.width + j] {
+                    state ^= *column as usize;
+                }
+            }
+            message.push(state & 1 == 1);
+            state >>= 1;
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn noisy_bits(length: usize, seed: usize) -> Vec<bool> {
+        (0..length).map(|i| (i * 7919 + seed) % 13 < 6).collect()
+    }
+
+    #[test]
+    fn round_trips_message() {
+        let key = StegoKey::derive("hunter2");
+        let code = TrellisCode::new(&key, 100, 1000).unwrap();
+        let cover = noisy_bits(1000, 0);
+        let message = noisy_bits(100, 5);
+        let stego = code.embed(&cover, &vec![1.0; 1000], &message).unwrap();
+        assert_eq!(code.extract(&stego, 100), message);
+    }
+
+    #[test]
+    fn avoids_expensive_positions() {
+        let key = StegoKey::derive("hunter2");
+        let code = TrellisCode::new(&key, 50, 1000).unwrap();
+        let cover = noisy_bits(1000, 0);
+        let message = noisy_bits(50, 3);
+        let costs: Vec<f64> = (0..1000)
+            .map(|i| if i % 2 == 0 { f64::INFINITY } else { 1.0 })
+            .collect();
+
+        let stego = code.embed(&cover, &costs, &message).unwrap();
+        assert_eq!(code.extract(&stego, 50), message);
+        assert!((0..1000).step_by(2).all(|i| stego[i] == cover[i]));
+    }
+}
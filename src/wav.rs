@@ -0,0 +1,237 @@
+use crate::Error;
+
+pub use self::riffchunk::RIFFChunk;
+pub use self::samplecarrier::SampleCarrier;
+pub use self::wavcarrier::{WAVCarrier, WAVCover};
+pub use self::waveformat::{SampleFormat, WaveFormat};
+pub use self::wavparseerror::WAVParseError;
+
+mod riffchunk;
+mod samplecarrier;
+mod wavcarrier;
+mod waveformat;
+mod wavparseerror;
+
+/// Length of the RIFF header: "RIFF", the size of the rest of the file and "WAVE".
+const RIFF_HEADER_LENGTH: usize = 12;
+
+/// A parsed RIFF/WAVE file: its format, the samples of its `data` chunk, and every other chunk kept byte for byte.
+pub struct WAV {
+    pub format: WaveFormat,
+    /// Chunks in file order, including `fmt ` and `data`.
+    pub chunks: Vec<RIFFChunk>,
+    /// Index of the `data` chunk in `chunks`.
+    data_chunk: usize,
+    /// Bytes after the end of the RIFF chunk, written back unchanged.
+    pub trailing: Vec<u8>,
+}
+
+impl WAV {
+    /// Parses a RIFF/WAVE file holding integer PCM or floating-point samples.
+    pub fn parse(data: &[u8]) -> Result<WAV, Error> {
+        let error = |error, offset| Error::WAV { error, offset };
+        if data.len() < RIFF_HEADER_LENGTH || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+            return Err(error(WAVParseError::NotWave, 0));
+        }
+        let size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        let end = (8 + size).clamp(RIFF_HEADER_LENGTH, data.len());
+
+        let mut chunks = Vec::new();
+        let mut offset = RIFF_HEADER_LENGTH;
+        while offset < end {
+            let (chunk, length) =
+                RIFFChunk::parse(&data[offset..end]).map_err(|e| error(e, offset))?;
+            chunks.push(chunk);
+            offset += length;
+        }
+
+        let find = |id: &'static str| {
+            chunks
+                .iter()
+                .position(|chunk| chunk.id == id.as_bytes())
+                .ok_or_else(|| error(WAVParseError::MissingChunk { id }, RIFF_HEADER_LENGTH))
+        };
+        let format_chunk = find("fmt ")?;
+        let data_chunk = find("data")?;
+        let format = WaveFormat::parse(&chunks[format_chunk].data)
+            .map_err(|e| error(e, RIFF_HEADER_LENGTH))?;
+
+        Ok(WAV {
+            format,
+            chunks,
+            data_chunk,
+            trailing: data[offset.min(data.len())..].to_vec(),
+        })
+    }
+
+    /// Serializes the file. The RIFF size is recomputed, so a well-formed file is written back byte for byte.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let size: usize = 4 + self.chunks.iter().map(RIFFChunk::length).sum::<usize>();
+        let mut bytes = Vec::with_capacity(8 + size + self.trailing.len());
+        bytes.extend(b"RIFF");
+        bytes.extend((size as u32).to_le_bytes());
+        bytes.extend(b"WAVE");
+        for chunk in &self.chunks {
+            bytes.extend(chunk.to_bytes());
+        }
+        bytes.extend(&self.trailing);
+        bytes
+    }
+
+    /// Returns the number of samples, counting each channel separately.
+    pub fn sample_count(&self) -> usize {
+        self.chunks[self.data_chunk].data.len() / self.format.bytes_per_sample()
+    }
+
+    /// Returns a sample as a signed integer. 8-bit samples are re-centred on zero.
+    /// Floating-point samples are returned as their bit pattern in sign-magnitude order, so that adding one moves the value by one unit in the last place.
+    pub fn sample(&self, index: usize) -> i64 {
+        let width = self.format.bytes_per_sample();
+        let bytes = &self.chunks[self.data_chunk].data[index * width..(index + 1) * width];
+        let mut raw = [0; 8];
+        raw[..width].copy_from_slice(bytes);
+        let raw = u64::from_le_bytes(raw);
+
+        let bits = width as u32 * 8;
+        match (self.format.sample_format, bits) {
+            (SampleFormat::Integer, 8) => raw as i64 - 128,
+            (SampleFormat::Integer, _) => ((raw << (64 - bits)) as i64) >> (64 - bits),
+            (SampleFormat::Float, _) => {
+                let sign = 1 << (bits - 1);
+                let magnitude = (raw & (sign - 1)) as i64;
+                if raw & sign != 0 {
+                    -magnitude
+                } else {
+                    magnitude
+                }
+            }
+        }
+    }
+
+    /// Stores a sample given in the form returned by [`WAV::sample`].
+    pub fn set_sample(&mut self, index: usize, value: i64) {
+        let width = self.format.bytes_per_sample();
+        let bits = width as u32 * 8;
+        let raw = match (self.format.sample_format, bits) {
+            (SampleFormat::Integer, 8) => (value + 128) as u64,
+            (SampleFormat::Integer, _) => value as u64,
+            (SampleFormat::Float, _) if value < 0 => value.unsigned_abs() | 1 << (bits - 1),
+            (SampleFormat::Float, _) => value as u64,
+        };
+        let data = &mut self.chunks[self.data_chunk].data;
+        data[index * width..(index + 1) * width].copy_from_slice(&raw.to_le_bytes()[..width]);
+    }
+
+    /// Returns the smallest and largest values [`WAV::sample`] can return, excluding infinities and NaNs for floating-point samples.
+    pub fn sample_range(&self) -> (i64, i64) {
+        match (self.format.sample_format, self.format.bits_per_sample) {
+            (SampleFormat::Float, 32) => {
+                let max = f32::MAX.to_bits() as i64;
+                (-max, max)
+            }
+            (SampleFormat::Float, _) => {
+                let max = f64::MAX.to_bits() as i64;
+                (-max, max)
+            }
+            (SampleFormat::Integer, bits) => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// Builds a 16-bit stereo PCM file of `count` sample frames, with an odd-sized LIST chunk before and a bext chunk after the audio.
+    pub(crate) fn wav_bytes(count: usize) -> Vec<u8> {
+        let mut format = vec![1, 0, 2, 0];
+        format.extend(44100u32.to_le_bytes());
+        format.extend((44100u32 * 4).to_le_bytes());
+        format.extend([4, 0, 16, 0]);
+
+        let samples: Vec<u8> = (0..count * 2)
+            .flat_map(|index| (((index * 7919) % 20000) as i16 - 10000).to_le_bytes())
+            .collect();
+        let chunks = [
+            RIFFChunk::new(*b"fmt ", format),
+            RIFFChunk::new(*b"LIST", b"INFOISFT\x05\0\0\0test\0".to_vec()),
+            RIFFChunk::new(*b"data", samples),
+            RIFFChunk::new(*b"bext", vec![0x42; 31]),
+        ];
+        let body: Vec<u8> = chunks.iter().flat_map(RIFFChunk::to_bytes).collect();
+
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend((body.len() as u32 + 4).to_le_bytes());
+        bytes.extend(b"WAVE");
+        bytes.extend(body);
+        bytes
+    }
+
+    #[test]
+    fn round_trips_file_with_extra_chunks() {
+        let bytes = wav_bytes(100);
+        let wav = WAV::parse(&bytes).unwrap();
+        assert_eq!(wav.sample_count(), 200);
+        assert_eq!(wav.chunks.len(), 4);
+        assert_eq!(wav.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn writes_back_file_without_last_pad_byte() {
+        let mut bytes = wav_bytes(100);
+        bytes.pop();
+        let size = bytes.len() as u32 - 8;
+        bytes[4..8].copy_from_slice(&size.to_le_bytes());
+        let wav = WAV::parse(&bytes).unwrap();
+        assert_eq!(wav.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn rejects_file_cut_inside_data_chunk() {
+        let bytes = wav_bytes(100);
+        let error = WAV::parse(&bytes[..bytes.len() - 100]).err().unwrap();
+        assert!(matches!(
+            error,
+            Error::WAV {
+                error: WAVParseError::TruncatedChunk { ref id },
+                ..
+            } if id == "data"
+        ));
+    }
+
+    #[test]
+    fn rejects_file_without_format_chunk() {
+        let mut bytes = wav_bytes(10);
+        bytes[12..16].copy_from_slice(b"junk");
+        assert!(matches!(
+            WAV::parse(&bytes),
+            Err(Error::WAV {
+                error: WAVParseError::MissingChunk { id: "fmt " },
+                offset: RIFF_HEADER_LENGTH,
+            })
+        ));
+    }
+
+    #[test]
+    fn reads_and_writes_signed_samples() {
+        let mut wav = WAV::parse(&wav_bytes(10)).unwrap();
+        assert_eq!(wav.sample(0), -10000);
+        assert_eq!(wav.sample(1), 7919 - 10000);
+        wav.set_sample(1, -32768);
+        assert_eq!(wav.sample(1), -32768);
+        assert_eq!(wav.sample_range(), (-32768, 32767));
+
+        let mut float = WAV::parse(&wav_bytes(10)).unwrap();
+        float.format.sample_format = SampleFormat::Float;
+        float.format.bits_per_sample = 32;
+        float.format.valid_bits = 32;
+        float.set_sample(0, -(1.5f32.to_bits() as i64));
+        float.set_sample(1, -(1.5f32.to_bits() as i64) - 1);
+        let value = |wav: &WAV, index: usize| {
+            let bytes = &wav.chunks[wav.data_chunk].data[index * 4..index * 4 + 4];
+            f32::from_le_bytes(bytes.try_into().unwrap())
+        };
+        assert_eq!(value(&float, 0), -1.5);
+        assert_eq!(value(&float, 1), -f32::from_bits(1.5f32.to_bits() + 1));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::wav::WAVParseError;
+
+/// Length of a chunk header: a four-character identifier and a little-endian size.
+pub const CHUNK_HEADER_LENGTH: usize = 8;
+
+/// A chunk of a RIFF file. Chunks are kept as raw bytes so that those the carrier does not touch are written back unchanged.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RIFFChunk {
+    pub id: [u8; 4],
+    pub data: Vec<u8>,
+    /// Whether an odd-sized chunk is followed by its pad byte. Some writers leave it out of the last chunk, and it is only written back if it was there.
+    pub padded: bool,
+}
+
+impl RIFFChunk {
+    /// Creates a chunk that is padded to an even length, as RIFF requires.
+    pub fn new(id: [u8; 4], data: Vec<u8>) -> RIFFChunk {
+        RIFFChunk {
+            id,
+            data,
+            padded: true,
+        }
+    }
+
+    /// Parses the chunk at the start of the data, returning it with the number of bytes it occupies, including the pad byte that follows odd-sized chunks.
+    pub fn parse(data: &[u8]) -> Result<(RIFFChunk, usize), WAVParseError> {
+        let truncated = || WAVParseError::TruncatedChunk {
+            id: String::from_utf8_lossy(&data[..data.len().min(4)]).to_string(),
+        };
+        let header = data.get(..CHUNK_HEADER_LENGTH).ok_or_else(truncated)?;
+        let id = [header[0], header[1], header[2], header[3]];
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let end = CHUNK_HEADER_LENGTH + size;
+        let body = data.get(CHUNK_HEADER_LENGTH..end).ok_or_else(truncated)?;
+
+        // Some writers leave out the pad byte of the last chunk.
+        let length = (end + size % 2).min(data.len());
+        Ok((
+            RIFFChunk {
+                id,
+                data: body.to_vec(),
+                padded: length > end,
+            },
+            length,
+        ))
+    }
+
+    /// Returns the identifier as text, such as "fmt " or "LIST".
+    pub fn name(&self) -> String {
+        String::from_utf8_lossy(&self.id).to_string()
+    }
+
+    /// Number of bytes the chunk occupies in the file.
+    pub fn length(&self) -> usize {
+        CHUNK_HEADER_LENGTH + self.data.len() + self.pad_length()
+    }
+
+    fn pad_length(&self) -> usize {
+        (self.padded && self.data.len() % 2 == 1) as usize
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.length());
+        bytes.extend(self.id);
+        bytes.extend((self.data.len() as u32).to_le_bytes());
+        bytes.extend(&self.data);
+        bytes.resize(bytes.len() + self.pad_length(), 0);
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_odd_sized_chunk_with_pad_byte() {
+        let bytes = b"note\x03\0\0\0abc\0next";
+        let (chunk, length) = RIFFChunk::parse(bytes).unwrap();
+        assert_eq!(chunk.name(), "note");
+        assert_eq!(chunk.data, b"abc");
+        assert_eq!(length, 12);
+        assert_eq!(chunk.to_bytes(), bytes[..12]);
+    }
+
+    #[test]
+    fn keeps_missing_pad_byte_missing() {
+        let bytes = b"note\x03\0\0\0abc";
+        let (chunk, length) = RIFFChunk::parse(bytes).unwrap();
+        assert!(!chunk.padded);
+        assert_eq!(length, 11);
+        assert_eq!(chunk.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn rejects_truncated_chunk() {
+        let error = RIFFChunk::parse(b"data\x10\0\0\0short").unwrap_err();
+        assert_eq!(
+            error,
+            WAVParseError::TruncatedChunk {
+                id: String::from("data")
+            }
+        );
+    }
+}
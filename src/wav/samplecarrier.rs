@@ -0,0 +1,159 @@
+use crate::stego::{lsb_match, sample_cost, BitCarrier};
+use crate::wav::WAV;
+
+/// Carries payload bits in the low bits of every sample, `bits_per_sample` bits each.
+/// Bits are written by LSB matching, moving a sample up or down to the nearest value with the wanted bits instead of overwriting them.
+/// Padding bits below the valid bits of a WAVE_FORMAT_EXTENSIBLE container are left alone.
+/// Floating-point infinities and NaNs lie outside the range LSB matching moves samples in, so they carry no bits.
+pub struct SampleCarrier {
+    bits_per_sample: u32,
+    /// Indices of the samples outside [`WAV::sample_range`], in increasing order.
+    skipped: Vec<usize>,
+}
+
+impl SampleCarrier {
+    /// Sets up the carrier for a file. Samples stay within their range when bits are written, so the skipped samples are the same after embedding.
+    pub fn new(cover: &WAV, bits_per_sample: u32) -> SampleCarrier {
+        let (min, max) = cover.sample_range();
+        let skipped = (0..cover.sample_count())
+            .filter(|index| !(min..=max).contains(&cover.sample(*index)))
+            .collect();
+        SampleCarrier {
+            bits_per_sample,
+            skipped,
+        }
+    }
+
+    /// Returns the index of the sample holding a position, stepping over the skipped samples.
+    fn sample_index(&self, position: usize) -> usize {
+        let mut index = position / self.bits_per_sample as usize;
+        for skipped in &self.skipped {
+            if *skipped > index {
+                break;
+            }
+            index += 1;
+        }
+        index
+    }
+
+    /// Number of padding bits below the valid bits of each sample.
+    fn shift(cover: &WAV) -> u32 {
+        (cover.format.bits_per_sample - cover.format.valid_bits) as u32
+    }
+}
+
+impl BitCarrier<WAV> for SampleCarrier {
+    fn positions(&self, cover: &WAV) -> usize {
+        (cover.sample_count() - self.skipped.len()) * self.bits_per_sample as usize
+    }
+
+    fn read_bit(&self, cover: &WAV, position: usize) -> bool {
+        let sample = cover.sample(self.sample_index(position));
+        let index = (position % self.bits_per_sample as usize) as u32;
+        (sample >> (SampleCarrier::shift(cover) + index)) & 1 == 1
+    }
+
+    fn write_bit(&self, cover: &mut WAV, position: usize, bit: bool) {
+        let sample_index = self.sample_index(position);
+        let index = (position % self.bits_per_sample as usize) as u32;
+        let shift = SampleCarrier::shift(cover);
+        let sample = cover.sample(sample_index);
+        let (min, max) = cover.sample_range();
+
+        let value = lsb_match(
+            sample >> shift,
+            self.bits_per_sample,
+            index,
+            bit,
+            min >> shift,
+            max >> shift,
+            position,
+        );
+        let padding = sample & ((1 << shift) - 1);
+        cover.set_sample(sample_index, (value << shift) | padding);
+    }
+
+    fn cost(&self, cover: &WAV, position: usize) -> f64 {
+        sample_cost(cover.sample(self.sample_index(position)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::stego::SILENT_SAMPLE_COST;
+    use crate::wav::test::wav_bytes;
+    use crate::wav::SampleFormat;
+
+    #[test]
+    fn changes_samples_by_one_step() {
+        let mut wav = WAV::parse(&wav_bytes(50)).unwrap();
+        let original: Vec<i64> = (0..wav.sample_count()).map(|i| wav.sample(i)).collect();
+        let carrier = SampleCarrier::new(&wav, 1);
+        for (position, sample) in original.iter().enumerate() {
+            let bit = !carrier.read_bit(&wav, position);
+            carrier.write_bit(&mut wav, position, bit);
+            assert_eq!(carrier.read_bit(&wav, position), bit);
+            assert_eq!((wav.sample(position) - sample).abs(), 1);
+        }
+    }
+
+    #[test]
+    fn keeps_padding_bits_of_extensible_samples() {
+        let mut wav = WAV::parse(&wav_bytes(4)).unwrap();
+        wav.format.valid_bits = 12;
+        wav.set_sample(0, 0x1238);
+        let carrier = SampleCarrier::new(&wav, 2);
+        carrier.write_bit(&mut wav, 1, true);
+        assert_eq!(wav.sample(0) & 0xF, 0x8);
+        assert!(carrier.read_bit(&wav, 1));
+    }
+
+    #[test]
+    fn keeps_full_scale_samples_in_range() {
+        let mut wav = WAV::parse(&wav_bytes(2)).unwrap();
+        wav.set_sample(0, 32767);
+        wav.set_sample(1, -32768);
+        let carrier = SampleCarrier::new(&wav, 2);
+        for position in 0..4 {
+            let bit = !carrier.read_bit(&wav, position);
+            carrier.write_bit(&mut wav, position, bit);
+            assert_eq!(carrier.read_bit(&wav, position), bit);
+        }
+        assert!((32764..=32767).contains(&wav.sample(0)));
+        assert!((-32768..=-32765).contains(&wav.sample(1)));
+    }
+
+    #[test]
+    fn charges_more_for_silent_samples() {
+        let mut wav = WAV::parse(&wav_bytes(2)).unwrap();
+        wav.set_sample(0, 0);
+        let carrier = SampleCarrier::new(&wav, 1);
+        assert_eq!(carrier.cost(&wav, 0), SILENT_SAMPLE_COST);
+        assert_eq!(carrier.cost(&wav, 1), 1.0);
+    }
+
+    #[test]
+    fn skips_samples_that_are_not_numbers() {
+        let mut wav = WAV::parse(&wav_bytes(8)).unwrap();
+        wav.format.sample_format = SampleFormat::Float;
+        wav.format.bits_per_sample = 32;
+        wav.format.valid_bits = 32;
+        for index in 0..wav.sample_count() {
+            wav.set_sample(index, (index as f32 / 10.0).to_bits() as i64);
+        }
+        wav.set_sample(2, f32::NAN.to_bits() as i64);
+        wav.set_sample(5, f32::INFINITY.to_bits() as i64);
+
+        let carrier = SampleCarrier::new(&wav, 2);
+        assert_eq!(carrier.positions(&wav), 2 * 6);
+        for position in 0..carrier.positions(&wav) {
+            let bit = !carrier.read_bit(&wav, position);
+            carrier.write_bit(&mut wav, position, bit);
+            assert_eq!(carrier.read_bit(&wav, position), bit);
+        }
+        assert!(f32::from_bits(wav.sample(2) as u32).is_nan());
+        assert_eq!(wav.sample(5), f32::INFINITY.to_bits() as i64);
+        assert_eq!(SampleCarrier::new(&wav, 2).skipped, [2, 5]);
+    }
+}
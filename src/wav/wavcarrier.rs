@@ -0,0 +1,90 @@
+use crate::carrier::{Carrier, Cover};
+use crate::stego::BitCarrier;
+use crate::wav::{SampleCarrier, WAV};
+use crate::Error;
+
+/// The RIFF/WAVE format, embedding in the low one to four bits of every sample.
+pub struct WAVCarrier;
+
+/// A parsed WAV file with the number of bits per sample to embed in.
+pub struct WAVCover {
+    pub wav: WAV,
+    carrier: SampleCarrier,
+}
+
+impl Carrier for WAVCarrier {
+    fn name(&self) -> &'static str {
+        "wav"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &["lsb", "lsb-2", "lsb-3", "lsb-4"]
+    }
+
+    fn parse(&self, data: &[u8], method: &str) -> Result<Box<dyn Cover>, Error> {
+        let bits_per_sample = match method {
+            "lsb" => 1,
+            "lsb-2" => 2,
+            "lsb-3" => 3,
+            "lsb-4" => 4,
+            _ => {
+                return Err(Error::UnsupportedMethod {
+                    format: self.name(),
+                    method: method.to_string(),
+                })
+            }
+        };
+        let wav = WAV::parse(data)?;
+        Ok(Box::new(WAVCover {
+            carrier: SampleCarrier::new(&wav, bits_per_sample),
+            wav,
+        }))
+    }
+}
+
+impl Cover for WAVCover {
+    fn positions(&self) -> usize {
+        self.carrier.positions(&self.wav)
+    }
+
+    fn read_bit(&self, position: usize) -> bool {
+        self.carrier.read_bit(&self.wav, position)
+    }
+
+    fn write_bit(&mut self, position: usize, bit: bool) {
+        self.carrier.write_bit(&mut self.wav, position, bit)
+    }
+
+    fn cost(&self, position: usize) -> f64 {
+        self.carrier.cost(&self.wav, position)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.wav.to_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::wav::test::wav_bytes;
+
+    #[test]
+    fn keeps_file_length_when_writing_bits() {
+        let bytes = wav_bytes(200);
+        let mut cover = WAVCarrier.parse(&bytes, "lsb-4").unwrap();
+        for position in 0..cover.positions() {
+            let bit = !cover.read_bit(position);
+            cover.write_bit(position, bit);
+        }
+        assert_eq!(cover.to_bytes().len(), bytes.len());
+    }
+
+    #[test]
+    fn rejects_unknown_method() {
+        assert!(matches!(
+            WAVCarrier.parse(&wav_bytes(10), "lsb-5"),
+            Err(Error::UnsupportedMethod { format: "wav", .. })
+        ));
+    }
+}
@@ -0,0 +1,135 @@
+#[cfg(feature = "report")]
+use serde::Serialize;
+
+use crate::wav::WAVParseError;
+
+const WAVE_FORMAT_PCM: u16 = 0x0001;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 0x0003;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// How samples are encoded.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "report", derive(Serialize))]
+pub enum SampleFormat {
+    /// Integer PCM: unsigned for 8-bit samples and signed otherwise.
+    Integer,
+    /// IEEE floating point.
+    Float,
+}
+
+/// The contents of the `fmt ` chunk, describing how the samples in the `data` chunk are laid out.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "report", derive(Serialize))]
+pub struct WaveFormat {
+    /// Format tag as stored, which is `0xFFFE` for WAVE_FORMAT_EXTENSIBLE files.
+    pub format_tag: u16,
+    pub sample_format: SampleFormat,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub byte_rate: u32,
+    /// Bytes per sample frame, covering one sample of every channel.
+    pub block_align: u16,
+    /// Size of the sample container in bits.
+    pub bits_per_sample: u16,
+    /// Bits of each container that hold the sample, from the most significant end. Only WAVE_FORMAT_EXTENSIBLE files can have fewer than `bits_per_sample`.
+    pub valid_bits: u16,
+    /// Speaker positions of the channels, for WAVE_FORMAT_EXTENSIBLE files.
+    pub channel_mask: Option<u32>,
+}
+
+impl WaveFormat {
+    /// Parses the body of a `fmt ` chunk.
+    pub fn parse(data: &[u8]) -> Result<WaveFormat, WAVParseError> {
+        let u16_at = |offset: usize| {
+            data.get(offset..offset + 2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        };
+        let u32_at = |offset: usize| {
+            data.get(offset..offset + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+        let format_tag = u16_at(0).ok_or(WAVParseError::InvalidFormat)?;
+        let bits_per_sample = u16_at(14).ok_or(WAVParseError::InvalidFormat)?;
+        let mut format = WaveFormat {
+            format_tag,
+            sample_format: SampleFormat::Integer,
+            channels: u16_at(2).ok_or(WAVParseError::InvalidFormat)?,
+            sample_rate: u32_at(4).ok_or(WAVParseError::InvalidFormat)?,
+            byte_rate: u32_at(8).ok_or(WAVParseError::InvalidFormat)?,
+            block_align: u16_at(12).ok_or(WAVParseError::InvalidFormat)?,
+            bits_per_sample,
+            valid_bits: bits_per_sample,
+            channel_mask: None,
+        };
+
+        // The extensible format stores the real format tag in the first two bytes of its sub-format GUID.
+        let encoding = if format_tag == WAVE_FORMAT_EXTENSIBLE {
+            let valid_bits = u16_at(18).ok_or(WAVParseError::InvalidFormat)?;
+            if valid_bits != 0 {
+                format.valid_bits = valid_bits;
+            }
+            format.channel_mask = Some(u32_at(20).ok_or(WAVParseError::InvalidFormat)?);
+            u16_at(24).ok_or(WAVParseError::InvalidFormat)?
+        } else {
+            format_tag
+        };
+        format.sample_format = match (encoding, bits_per_sample) {
+            (WAVE_FORMAT_PCM, 8 | 16 | 24 | 32) => SampleFormat::Integer,
+            (WAVE_FORMAT_IEEE_FLOAT, 32 | 64) => SampleFormat::Float,
+            _ => {
+                return Err(WAVParseError::UnsupportedEncoding {
+                    format_tag: encoding,
+                    bits_per_sample,
+                })
+            }
+        };
+
+        if format.channels == 0
+            || format.valid_bits > bits_per_sample
+            || format.block_align as usize != format.channels as usize * format.bytes_per_sample()
+        {
+            return Err(WAVParseError::InvalidFormat);
+        }
+        Ok(format)
+    }
+
+    /// Bytes taken by one sample of one channel.
+    pub fn bytes_per_sample(&self) -> usize {
+        self.bits_per_sample as usize / 8
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_extensible_format() {
+        let mut data = vec![0xFE, 0xFF, 2, 0];
+        data.extend(48000u32.to_le_bytes());
+        data.extend((48000u32 * 8).to_le_bytes());
+        data.extend([8, 0, 32, 0, 22, 0, 24, 0]);
+        data.extend(3u32.to_le_bytes());
+        data.extend([
+            1, 0, 0, 0, 0, 0, 0x10, 0, 0x80, 0, 0, 0xAA, 0, 0x38, 0x9B, 0x71,
+        ]);
+
+        let format = WaveFormat::parse(&data).unwrap();
+        assert_eq!(format.sample_format, SampleFormat::Integer);
+        assert_eq!((format.bits_per_sample, format.valid_bits), (32, 24));
+        assert_eq!(format.channel_mask, Some(3));
+    }
+
+    #[test]
+    fn rejects_compressed_formats() {
+        let mut data = vec![0x55, 0, 1, 0];
+        data.extend([0x44, 0xAC, 0, 0, 0, 0x7D, 0, 0, 1, 0, 0, 0]);
+        assert_eq!(
+            WaveFormat::parse(&data),
+            Err(WAVParseError::UnsupportedEncoding {
+                format_tag: 0x55,
+                bits_per_sample: 0
+            })
+        );
+    }
+}
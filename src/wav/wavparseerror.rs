@@ -0,0 +1,46 @@
+/// Error type for RIFF/WAVE parsing.
+#[derive(Debug, PartialEq)]
+pub enum WAVParseError {
+    NotWave,
+    TruncatedChunk {
+        id: String,
+    },
+    MissingChunk {
+        id: &'static str,
+    },
+    UnsupportedEncoding {
+        format_tag: u16,
+        bits_per_sample: u16,
+    },
+    InvalidFormat,
+}
+
+impl WAVParseError {
+    pub fn description(&self) -> String {
+        match self {
+            WAVParseError::NotWave => String::from("Input does not start with a RIFF/WAVE header."),
+            WAVParseError::TruncatedChunk { id } => {
+                format!("Chunk \"{}\" extends past the end of the data.", id)
+            }
+            WAVParseError::MissingChunk { id } => format!("No \"{}\" chunk was found.", id),
+            WAVParseError::UnsupportedEncoding {
+                format_tag,
+                bits_per_sample,
+            } => format!(
+                "Unsupported sample encoding: format tag {:#06x} with {} bits per sample.",
+                format_tag, bits_per_sample
+            ),
+            WAVParseError::InvalidFormat => {
+                String::from("The format chunk describes an inconsistent sample layout.")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for WAVParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(&self.description())
+    }
+}
+
+impl std::error::Error for WAVParseError {}